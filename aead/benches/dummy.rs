@@ -0,0 +1,13 @@
+//! Benchmarks of the dummy (horribly insecure!) AEAD shared with the tests,
+//! exercising the benchmark macros of the `dev` module.
+#![feature(test)]
+extern crate test;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::PostfixDummyAead;
+
+aead::aead_encrypt_bench!(PostfixDummyAead);
+aead::aead_decrypt_bench!(PostfixDummyAead);
+aead::aead_inout_bench!(PostfixDummyAead);
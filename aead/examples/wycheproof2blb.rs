@@ -0,0 +1,121 @@
+//! Convert Wycheproof AEAD test vectors from JSON into the blobby format used
+//! by `aead::new_wycheproof_test!`.
+//!
+//! Usage:
+//!
+//! ```text
+//! cargo run --example wycheproof2blb -- aes_gcm_test.json aes_gcm.blb
+//! ```
+//!
+//! Every test case of every `AeadTest` group is stored as the following
+//! blobs: `tcId` in decimal, `key`, `iv`, `aad`, `msg`, `ct`, `tag`, `result`
+//! and the comma-separated `flags`.
+use serde_json::Value;
+use std::{env, fs, process};
+
+/// Get the field `key` of the JSON object `value`.
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, String> {
+    value
+        .get(key)
+        .ok_or_else(|| format!("missing field `{key}`"))
+}
+
+/// Get the string field `key` of the JSON object `value`.
+fn str_field<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    field(value, key)?
+        .as_str()
+        .ok_or_else(|| format!("expected string field `{key}`"))
+}
+
+/// Get the array field `key` of the JSON object `value`.
+fn array_field<'a>(value: &'a Value, key: &str) -> Result<&'a [Value], String> {
+    field(value, key)?
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| format!("expected array field `{key}`"))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    if s.len() % 2 != 0 {
+        return Err(format!("odd-length hex string: {s}"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Encode a variable-length quantity as used by blobby.
+fn encode_vlq(mut value: usize, out: &mut Vec<u8>) {
+    let mut buf = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        buf.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    out.extend(buf.iter().rev());
+}
+
+/// Encode blobs without deduplication.
+fn encode_blobs(blobs: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_vlq(0, &mut out);
+    for blob in blobs {
+        encode_vlq(blob.len() << 1, &mut out);
+        out.extend_from_slice(blob);
+    }
+    out
+}
+
+fn convert(json: &Value) -> Result<Vec<Vec<u8>>, String> {
+    let mut blobs = Vec::new();
+    for group in array_field(json, "testGroups")? {
+        if group.get("type").and_then(Value::as_str) != Some("AeadTest") {
+            continue;
+        }
+        for test in array_field(group, "tests")? {
+            let tc_id = field(test, "tcId")?
+                .as_u64()
+                .ok_or("expected numeric `tcId`")?;
+            let flags = array_field(test, "flags")?
+                .iter()
+                .map(|flag| flag.as_str().ok_or("expected string flag"))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(",");
+
+            blobs.push(tc_id.to_string().into_bytes());
+            for key in ["key", "iv", "aad", "msg", "ct", "tag"] {
+                blobs.push(decode_hex(str_field(test, key)?)?);
+            }
+            blobs.push(str_field(test, "result")?.as_bytes().to_vec());
+            blobs.push(flags.into_bytes());
+        }
+    }
+    Ok(blobs)
+}
+
+fn run(input: &str, output: &str) -> Result<usize, String> {
+    let data = fs::read(input).map_err(|e| format!("failed to read {input}: {e}"))?;
+    let json: Value =
+        serde_json::from_slice(&data).map_err(|e| format!("failed to parse {input}: {e}"))?;
+    let blobs = convert(&json)?;
+    fs::write(output, encode_blobs(&blobs))
+        .map_err(|e| format!("failed to write {output}: {e}"))?;
+    Ok(blobs.len() / 9)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let [_, input, output] = args.as_slice() else {
+        eprintln!("Usage: wycheproof2blb <input.json> <output.blb>");
+        process::exit(1);
+    };
+    match run(input, output) {
+        Ok(count) => println!("converted {count} test vectors"),
+        Err(err) => {
+            eprintln!("error: {err}");
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! Chunked encrypted container format with random access.
+//!
+//! The plaintext is split into chunks of a fixed size, each of which is
+//! encrypted separately using the [`StreamBE32`] construction, i.e. under a
+//! nonce derived from a per-container nonce prefix, the chunk index and a flag
+//! marking the final chunk. This allows decrypting any chunk independently
+//! while detecting truncation, reordering and swapping of chunks, including
+//! chunks taken from other containers.
+//!
+//! # Format
+//!
+//! | Field                  | Size                          |
+//! |------------------------|-------------------------------|
+//! | magic (`AEADCHNK`)     | 8 bytes                       |
+//! | version (`1`)          | 1 byte                        |
+//! | algorithm ID           | 2 bytes (big endian `u16`)    |
+//! | chunk size             | 4 bytes (big endian `u32`)    |
+//! | nonce prefix size      | 1 byte                        |
+//! | nonce prefix           | nonce prefix size             |
+//! | encrypted chunks       | variable                      |
+//!
+//! Every chunk but the last one contains exactly "chunk size" bytes of
+//! plaintext, and the last one contains at most as much (and is empty only
+//! for an empty plaintext). The encoded header is used as the associated data
+//! of every chunk, so it can't be modified either.
+//!
+//! The nonce prefix must never be reused with the same key. The algorithm ID
+//! is not interpreted by this module: it is meant to let applications select
+//! the AEAD to use when opening a container, see [`Header::read_from`].
+
+use crate::{
+    AeadInOut, Error,
+    array::{ArraySize, typenum::Unsigned},
+    stream::{EncryptorBE32, NewStream, StreamBE32, StreamPrimitive},
+};
+use alloc::vec::Vec;
+use core::{fmt, ops::Sub};
+use crypto_common::array::typenum::U5;
+use std::{
+    boxed::Box,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+/// Magic bytes identifying the container format.
+pub const MAGIC: [u8; 8] = *b"AEADCHNK";
+
+/// Version of the container format.
+pub const VERSION: u8 = 1;
+
+/// Size of the fixed part of the header, i.e. excluding the nonce prefix.
+const FIXED_HEADER_SIZE: usize = 16;
+
+/// Container header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// Application-defined identifier of the AEAD algorithm.
+    pub algorithm_id: u16,
+    /// Size of plaintext chunks.
+    pub chunk_size: u32,
+    /// Nonce prefix used to derive chunk nonces.
+    pub nonce_prefix: Vec<u8>,
+}
+
+impl Header {
+    /// Read and validate a header.
+    pub fn read_from<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let mut fixed = [0u8; FIXED_HEADER_SIZE];
+        reader.read_exact(&mut fixed)?;
+        if fixed[..8] != MAGIC {
+            return Err(invalid_data("invalid container magic"));
+        }
+        if fixed[8] != VERSION {
+            return Err(invalid_data("unsupported container version"));
+        }
+
+        let algorithm_id = u16::from_be_bytes([fixed[9], fixed[10]]);
+        let chunk_size = u32::from_be_bytes([fixed[11], fixed[12], fixed[13], fixed[14]]);
+        if chunk_size == 0 {
+            return Err(invalid_data("invalid chunk size"));
+        }
+
+        let mut nonce_prefix = alloc::vec![0u8; usize::from(fixed[15])];
+        reader.read_exact(&mut nonce_prefix)?;
+
+        Ok(Self {
+            algorithm_id,
+            chunk_size,
+            nonce_prefix,
+        })
+    }
+
+    /// Encode the header.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let nonce_prefix_len =
+            u8::try_from(self.nonce_prefix.len()).expect("nonce prefix is too long");
+        let mut bytes = Vec::with_capacity(FIXED_HEADER_SIZE + self.nonce_prefix.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.algorithm_id.to_be_bytes());
+        bytes.extend_from_slice(&self.chunk_size.to_be_bytes());
+        bytes.push(nonce_prefix_len);
+        bytes.extend_from_slice(&self.nonce_prefix);
+        bytes
+    }
+
+    fn chunk_size(&self) -> usize {
+        usize::try_from(self.chunk_size).expect("chunk size does not fit into usize")
+    }
+}
+
+/// Encrypting [`Write`] adapter.
+///
+/// [`Writer::finish`] must be called once all data is written in order to
+/// write the final chunk. Containers without one are rejected by [`Reader`].
+///
+/// Once a chunk fails to be encrypted or written, the writer is poisoned: all
+/// subsequent calls return an error, since the state of the inner writer is
+/// unknown and the buffered chunk may have already been encrypted.
+pub struct Writer<A, W>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+{
+    encryptor: EncryptorBE32<A>,
+    inner: W,
+    aad: Vec<u8>,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    poisoned: bool,
+}
+
+impl<A, W> Writer<A, W>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+    W: Write,
+{
+    /// Write the container header to `inner` and create a new writer.
+    ///
+    /// The size of `header.nonce_prefix` must be the size of the AEAD nonce
+    /// minus 5 bytes.
+    pub fn new(aead: A, header: Header, mut inner: W) -> io::Result<Self> {
+        let nonce_prefix = header
+            .nonce_prefix
+            .as_slice()
+            .try_into()
+            .map_err(|_| invalid_input("invalid nonce prefix size"))?;
+        if header.chunk_size == 0 {
+            return Err(invalid_input("invalid chunk size"));
+        }
+
+        let aad = header.to_vec();
+        inner.write_all(&aad)?;
+
+        let chunk_size = header.chunk_size();
+        Ok(Self {
+            encryptor: EncryptorBE32::from_aead(aead, nonce_prefix),
+            inner,
+            aad,
+            chunk_size,
+            buffer: Vec::with_capacity(chunk_size + A::TagSize::USIZE),
+            poisoned: false,
+        })
+    }
+
+    /// Encrypt and write the final chunk, returning the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.check_poisoned()?;
+        let Self {
+            encryptor,
+            mut inner,
+            aad,
+            mut buffer,
+            ..
+        } = self;
+        encryptor
+            .encrypt_last_in_place(&aad, &mut buffer)
+            .map_err(other)?;
+        inner.write_all(&buffer)?;
+        inner.flush()?;
+        Ok(inner)
+    }
+
+    fn write_chunk(&mut self) -> io::Result<()> {
+        // Cleared only once the chunk is fully written
+        self.poisoned = true;
+        self.encryptor
+            .encrypt_next_in_place(&self.aad, &mut self.buffer)
+            .map_err(other)?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.poisoned = false;
+        Ok(())
+    }
+
+    fn check_poisoned(&self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other("writer poisoned by an earlier error"));
+        }
+        Ok(())
+    }
+}
+
+impl<A, W> Write for Writer<A, W>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_poisoned()?;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // A full chunk is only written once more data arrives, since it may
+        // turn out to be the final one.
+        if self.buffer.len() == self.chunk_size {
+            self.write_chunk()?;
+        }
+
+        let len = buf.len().min(self.chunk_size - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.check_poisoned()?;
+        self.inner.flush().inspect_err(|_| self.poisoned = true)
+    }
+}
+
+impl<A, W> fmt::Debug for Writer<A, W>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer").finish_non_exhaustive()
+    }
+}
+
+/// Decrypting [`Read`] and [`Seek`] adapter.
+///
+/// Every chunk is authenticated before any of its plaintext is returned. The
+/// final chunk is authenticated when the reader is created, so truncated
+/// containers are rejected upfront.
+pub struct Reader<A, R>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+{
+    stream: StreamBE32<A>,
+    inner: R,
+    header: Header,
+    aad: Vec<u8>,
+    body_start: u64,
+    chunk_count: u64,
+    last_chunk_len: usize,
+    len: u64,
+    pos: u64,
+    chunk: Vec<u8>,
+    chunk_index: Option<u64>,
+}
+
+impl<A, R> Reader<A, R>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+    R: Read + Seek,
+{
+    /// Read the container header from `inner` and create a new reader.
+    pub fn new(aead: A, mut inner: R) -> io::Result<Self> {
+        let header = Header::read_from(&mut inner)?;
+        Self::from_header(aead, header, inner)
+    }
+
+    /// Create a new reader from a header previously read from `inner` with
+    /// [`Header::read_from`].
+    pub fn from_header(aead: A, header: Header, mut inner: R) -> io::Result<Self> {
+        let nonce_prefix = header
+            .nonce_prefix
+            .as_slice()
+            .try_into()
+            .map_err(|_| invalid_data("invalid nonce prefix size"))?;
+        let stream = StreamBE32::from_aead(aead, nonce_prefix);
+
+        let tag_size = A::TagSize::USIZE;
+        let ct_chunk_size = u64::from(header.chunk_size) + tag_size as u64;
+
+        let body_start = inner.stream_position()?;
+        let body_len = inner
+            .seek(SeekFrom::End(0))?
+            .checked_sub(body_start)
+            .ok_or_else(|| invalid_data("truncated container"))?;
+        let chunk_count = body_len.div_ceil(ct_chunk_size).max(1);
+        let last_chunk_len = usize::try_from(body_len - (chunk_count - 1) * ct_chunk_size)
+            .expect("chunk length fits into usize");
+        if last_chunk_len < tag_size {
+            return Err(invalid_data("truncated container"));
+        }
+        if chunk_count - 1 > u64::from(u32::MAX) {
+            return Err(invalid_data("too many chunks"));
+        }
+
+        let aad = header.to_vec();
+        let len =
+            (chunk_count - 1) * u64::from(header.chunk_size) + (last_chunk_len - tag_size) as u64;
+        let mut reader = Self {
+            stream,
+            inner,
+            header,
+            aad,
+            body_start,
+            chunk_count,
+            last_chunk_len,
+            len,
+            pos: 0,
+            // Sized by `load_chunk`, which never exceeds the length of the
+            // ciphertext since the chunk size is not authenticated yet
+            chunk: Vec::new(),
+            chunk_index: None,
+        };
+        reader.load_chunk(chunk_count - 1)?;
+        Ok(reader)
+    }
+
+    /// Container header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Length of the plaintext.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn load_chunk(&mut self, index: u64) -> io::Result<()> {
+        if self.chunk_index == Some(index) {
+            return Ok(());
+        }
+        self.chunk_index = None;
+
+        let last = index == self.chunk_count - 1;
+        let ct_chunk_size = self.header.chunk_size() + A::TagSize::USIZE;
+        let ct_len = if last {
+            self.last_chunk_len
+        } else {
+            ct_chunk_size
+        };
+
+        self.inner.seek(SeekFrom::Start(
+            self.body_start + index * ct_chunk_size as u64,
+        ))?;
+        self.chunk.resize(ct_len, 0);
+        self.inner.read_exact(&mut self.chunk)?;
+
+        let position = u32::try_from(index).expect("chunk count was checked");
+        self.stream
+            .decrypt_in_place(position, last, &self.aad, &mut self.chunk)
+            .map_err(|err| {
+                self.chunk.clear();
+                invalid_data(err)
+            })?;
+        self.chunk_index = Some(index);
+        Ok(())
+    }
+}
+
+impl<A, R> Read for Reader<A, R>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+
+        let chunk_size = u64::from(self.header.chunk_size);
+        self.load_chunk(self.pos / chunk_size)?;
+
+        let offset = usize::try_from(self.pos % chunk_size).expect("offset is within the chunk");
+        let available = &self.chunk[offset..];
+        let len = buf.len().min(available.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<A, R> Seek for Reader<A, R>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+    R: Read + Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::End(offset) => (self.len, offset),
+            SeekFrom::Current(offset) => (self.pos, offset),
+        };
+        self.pos = base
+            .checked_add_signed(offset)
+            .ok_or_else(|| invalid_input("invalid seek to a negative or overflowing position"))?;
+        Ok(self.pos)
+    }
+}
+
+impl<A, R> fmt::Debug for Reader<A, R>
+where
+    A: AeadInOut,
+    A::NonceSize: Sub<U5>,
+    <A::NonceSize as Sub<U5>>::Output: ArraySize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reader")
+            .field("header", &self.header)
+            .field("len", &self.len)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn invalid_input(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn other(err: Error) -> io::Error {
+    io::Error::other(err)
+}
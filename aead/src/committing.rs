@@ -0,0 +1,162 @@
+//! Key-committing AEAD wrapper.
+//!
+//! Common AEADs such as AES-GCM and ChaCha20Poly1305 are not key-committing:
+//! it is possible to craft a ciphertext which decrypts successfully under
+//! several keys. This enables partitioning oracle attacks against systems
+//! which try multiple keys during decryption, e.g. password-based ones.
+//!
+//! [`KeyCommitting`] fixes this by prefixing the tag of the underlying AEAD
+//! with a commitment to the key and nonce computed with a collision-resistant
+//! hash function:
+//!
+//! ```text
+//! commitment = H("aead key commitment" || key || nonce)
+//! tag        = commitment || inner tag
+//! ```
+//!
+//! The commitment is checked in constant time before decryption, so finding
+//! a ciphertext which decrypts under two keys requires finding a collision of
+//! the hash function.
+
+use crate::{
+    AeadCore, AeadInOut, Error, Key, KeyInit, KeySizeUser, Nonce, Result, Tag, TagPosition,
+    array::{Array, ArraySize},
+};
+use core::{fmt, ops::Add};
+use crypto_common::array::typenum::{Sum, Unsigned};
+use digest::{Digest, Output, OutputSizeUser};
+use inout::InOutBuf;
+use subtle::ConstantTimeEq;
+
+/// Domain separation label for the key commitment.
+const LABEL: &[u8] = b"aead key commitment";
+
+/// Key-committing AEAD wrapping the AEAD `A`, with commitments computed using
+/// the hash function `D`.
+#[derive(Clone)]
+pub struct KeyCommitting<A, D> {
+    aead: A,
+    /// Hasher state after absorbing the label and the key.
+    hasher: D,
+}
+
+impl<A, D> KeyCommitting<A, D>
+where
+    A: AeadCore,
+    D: Digest + Clone,
+    D::OutputSize: Add<A::TagSize>,
+    Sum<D::OutputSize, A::TagSize>: ArraySize,
+{
+    fn commitment(&self, nonce: &[u8]) -> Output<D> {
+        self.hasher.clone().chain_update(nonce).finalize()
+    }
+
+    /// Prefix the inner tag with the commitment for the given nonce.
+    fn tag(&self, nonce: &Nonce<Self>, inner_tag: &Tag<A>) -> Tag<Self> {
+        let mut tag = Tag::<Self>::default();
+        let (commitment, tail) = tag.split_at_mut(D::OutputSize::USIZE);
+        commitment.copy_from_slice(&self.commitment(nonce));
+        tail.copy_from_slice(inner_tag);
+        tag
+    }
+
+    /// Verify the commitment for the given nonce, returning the inner tag.
+    fn verify_commitment(&self, nonce: &Nonce<Self>, tag: &Tag<Self>) -> Result<Tag<A>> {
+        let (commitment, inner_tag) = tag.split_at(D::OutputSize::USIZE);
+        if !bool::from(self.commitment(nonce).ct_eq(commitment)) {
+            return Err(Error);
+        }
+        Ok(Array::try_from(inner_tag).expect("tag length mismatch"))
+    }
+}
+
+impl<A: KeySizeUser, D> KeySizeUser for KeyCommitting<A, D> {
+    type KeySize = A::KeySize;
+}
+
+impl<A, D> KeyInit for KeyCommitting<A, D>
+where
+    A: KeyInit,
+    D: Digest,
+{
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            aead: A::new(key),
+            hasher: D::new().chain_update(LABEL).chain_update(key),
+        }
+    }
+}
+
+impl<A, D> AeadCore for KeyCommitting<A, D>
+where
+    A: AeadCore,
+    D: OutputSizeUser,
+    D::OutputSize: Add<A::TagSize>,
+    Sum<D::OutputSize, A::TagSize>: ArraySize,
+{
+    type NonceSize = A::NonceSize;
+    type TagSize = Sum<D::OutputSize, A::TagSize>;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+impl<A, D> AeadInOut for KeyCommitting<A, D>
+where
+    A: AeadInOut,
+    D: Digest + Clone,
+    D::OutputSize: Add<A::TagSize>,
+    Sum<D::OutputSize, A::TagSize>: ArraySize,
+{
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        let inner_tag = self
+            .aead
+            .encrypt_inout_detached(nonce, associated_data, buffer)?;
+        Ok(self.tag(nonce, &inner_tag))
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        let inner_tag = self.verify_commitment(nonce, tag)?;
+        self.aead
+            .decrypt_inout_detached(nonce, associated_data, buffer, &inner_tag)
+    }
+
+    fn encrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+    ) -> Result<Tag<Self>> {
+        let inner_tag = self
+            .aead
+            .encrypt_vectored_detached(nonce, associated_data, message)?;
+        Ok(self.tag(nonce, &inner_tag))
+    }
+
+    fn decrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        let inner_tag = self.verify_commitment(nonce, tag)?;
+        self.aead
+            .decrypt_vectored_detached(nonce, associated_data, message, &inner_tag)
+    }
+}
+
+impl<A, D> fmt::Debug for KeyCommitting<A, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyCommitting").finish_non_exhaustive()
+    }
+}
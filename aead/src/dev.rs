@@ -1,8 +1,11 @@
 //! Development-related functionality
 use crate::{
-    Aead, AeadInOut, Payload, Tag, TagPosition, array::typenum::Unsigned, inout::InOutBuf,
+    Aead, AeadInOut, Key, Nonce, NonceMisuseResistant, Payload, Tag, TagPosition,
+    array::{Array, typenum::Unsigned},
+    inout::InOutBuf,
 };
 pub use blobby;
+use core::fmt;
 use crypto_common::KeyInit;
 
 /// AEAD test vector
@@ -17,6 +20,9 @@ pub struct TestVector {
     /// Plaintext
     pub plaintext: &'static [u8],
     /// Ciphertext
+    ///
+    /// Includes the tag, which for truncated-tag vectors is truncated to the
+    /// `TagSize` of the tested AEAD, e.g. a `truncated::Truncated` wrapper.
     pub ciphertext: &'static [u8],
     /// Whether the test vector should pass (`[1]`) or fail (`[0]`)
     pub pass: &'static [u8],
@@ -131,6 +137,268 @@ pub fn fail_test<C: AeadInOut + KeyInit>(
     }
 }
 
+/// Run nonce reuse safety test for the provided nonce misuse-resistant AEAD
+///
+/// Encrypts distinct messages with the same key and nonce, and checks that
+/// encryption is deterministic, that the ciphertexts don't reveal the XOR of
+/// the plaintexts (i.e. the keystream is not reused), and that they decrypt
+/// to the original messages.
+pub fn nonce_reuse_test<C: AeadInOut + KeyInit + NonceMisuseResistant>() -> Result<(), &'static str>
+{
+    let key: Key<C> = Array::from_fn(|i| i as u8);
+    let nonce = Nonce::<C>::default();
+    let cipher = C::new(&key);
+    let aad = b"nonce reuse test";
+
+    let msg1 = [0u8; 64];
+    let mut msg2 = msg1;
+    msg2[msg2.len() - 1] = 1;
+
+    let encrypt = |msg: &[u8], aad: &[u8]| {
+        cipher
+            .encrypt(&nonce, Payload { msg, aad })
+            .map_err(|_| "encryption failure")
+    };
+    let ct1 = encrypt(&msg1, aad)?;
+    let ct2 = encrypt(&msg2, aad)?;
+    if encrypt(&msg1, aad)? != ct1 {
+        return Err("encryption is not deterministic");
+    }
+    if encrypt(&msg1, b"other aad")? == ct1 {
+        return Err("ciphertext does not depend on associated data");
+    }
+
+    let strip_tag = |ct: &[u8]| -> alloc::vec::Vec<u8> {
+        match C::TAG_POSITION {
+            TagPosition::Prefix => ct[C::TagSize::USIZE..].to_vec(),
+            TagPosition::Postfix => ct[..msg1.len()].to_vec(),
+        }
+    };
+    let (c1, c2) = (strip_tag(&ct1), strip_tag(&ct2));
+    let reused = c1
+        .iter()
+        .zip(&c2)
+        .zip(msg1.iter().zip(&msg2))
+        .all(|((a, b), (m1, m2))| a ^ b == m1 ^ m2);
+    if reused {
+        return Err("keystream is reused for distinct messages");
+    }
+
+    for (msg, ct) in [(&msg1, &ct1), (&msg2, &ct2)] {
+        let res = cipher
+            .decrypt(&nonce, Payload { msg: ct, aad })
+            .map_err(|_| "decryption failure")?;
+        if res != msg {
+            return Err("decrypted data is different from original plaintext");
+        }
+    }
+
+    Ok(())
+}
+
+/// Wycheproof AEAD test vector
+///
+/// Vectors can be converted from Wycheproof JSON files into the blobby
+/// format expected by [`new_wycheproof_test!`](crate::new_wycheproof_test)
+/// using the `wycheproof2blb` example of this crate.
+#[derive(Debug, Clone, Copy)]
+pub struct WycheproofVector {
+    /// Test case ID in decimal
+    pub tc_id: &'static [u8],
+    /// Initialization key
+    pub key: &'static [u8],
+    /// Nonce
+    pub nonce: &'static [u8],
+    /// Additional associated data
+    pub aad: &'static [u8],
+    /// Plaintext
+    pub msg: &'static [u8],
+    /// Ciphertext without the tag
+    pub ct: &'static [u8],
+    /// Authentication tag
+    pub tag: &'static [u8],
+    /// Expected result: `valid`, `invalid` or `acceptable`
+    pub result: &'static [u8],
+    /// Comma-separated Wycheproof flags
+    pub flags: &'static [u8],
+}
+
+impl fmt::Display for WycheproofVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tc_id = core::str::from_utf8(self.tc_id).unwrap_or("?");
+        let flags = core::str::from_utf8(self.flags).unwrap_or("?");
+        write!(f, "#{tc_id} [{flags}]")
+    }
+}
+
+/// Per-flag handling of Wycheproof test vectors
+///
+/// Wycheproof marks vectors exercising optional or implementation-defined
+/// behaviour with flags, e.g. `SmallIv` or `LongIv`, and usually gives such
+/// vectors the `acceptable` result. These lists allow to skip such vectors or
+/// to pin down the expected behaviour of the tested implementation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WycheproofFlags {
+    /// Vectors with any of these flags are skipped
+    pub skip: &'static [&'static str],
+    /// Acceptable vectors with any of these flags must pass like valid ones
+    pub valid: &'static [&'static str],
+    /// Acceptable vectors with any of these flags must fail like invalid ones
+    pub invalid: &'static [&'static str],
+}
+
+impl WycheproofFlags {
+    /// No special handling of any flag
+    pub const NONE: Self = Self {
+        skip: &[],
+        valid: &[],
+        invalid: &[],
+    };
+}
+
+/// Returns `true` if the comma-separated `flags` contain any of `names`
+fn has_flag(flags: &[u8], names: &[&str]) -> bool {
+    flags
+        .split(|&b| b == b',')
+        .any(|flag| names.iter().any(|name| name.as_bytes() == flag))
+}
+
+/// Run AEAD test for the provided Wycheproof test vector
+///
+/// Valid vectors are encrypted and decrypted, and decryption is checked to
+/// fail for every single-bit modification of their tag. Invalid vectors must
+/// fail decryption, while acceptable ones must either fail or decrypt to the
+/// expected plaintext.
+///
+/// Vectors whose key size is not supported by `C` are skipped, as are valid
+/// and acceptable vectors whose nonce or tag size is not supported. Invalid
+/// vectors with such sizes, e.g. oversized or undersized nonces, pass since
+/// they can't be used with `C`. Returns `Ok(false)` for skipped vectors.
+pub fn wycheproof_test<C: AeadInOut + KeyInit>(
+    tv: &WycheproofVector,
+) -> Result<bool, &'static str> {
+    wycheproof_test_with_flags::<C>(tv, &WycheproofFlags::NONE)
+}
+
+/// Run AEAD test for the provided Wycheproof test vector with per-flag
+/// handling
+///
+/// Works like [`wycheproof_test`], but skips vectors with any of the
+/// [`WycheproofFlags::skip`] flags and tests acceptable vectors with any of
+/// the [`WycheproofFlags::valid`] or [`WycheproofFlags::invalid`] flags as
+/// valid or invalid respectively. Acceptable vectors with flags from both
+/// lists are an error.
+pub fn wycheproof_test_with_flags<C: AeadInOut + KeyInit>(
+    tv: &WycheproofVector,
+    flags: &WycheproofFlags,
+) -> Result<bool, &'static str> {
+    let result = match tv.result {
+        b"valid" => WycheproofResult::Valid,
+        b"invalid" => WycheproofResult::Invalid,
+        b"acceptable" => WycheproofResult::Acceptable,
+        _ => return Err("unknown result"),
+    };
+    if has_flag(tv.flags, flags.skip) {
+        return Ok(false);
+    }
+    let result = match (
+        result,
+        has_flag(tv.flags, flags.valid),
+        has_flag(tv.flags, flags.invalid),
+    ) {
+        (WycheproofResult::Acceptable, true, true) => {
+            return Err("conflicting flag expectations");
+        }
+        (WycheproofResult::Acceptable, true, false) => WycheproofResult::Valid,
+        (WycheproofResult::Acceptable, false, true) => WycheproofResult::Invalid,
+        (result, _, _) => result,
+    };
+    let Ok(cipher) = <C as KeyInit>::new_from_slice(tv.key) else {
+        return Ok(false);
+    };
+    let (Ok(nonce), Ok(tag)) = (Nonce::<C>::try_from(tv.nonce), Tag::<C>::try_from(tv.tag)) else {
+        return Ok(result == WycheproofResult::Invalid);
+    };
+
+    let mut ciphertext = alloc::vec::Vec::with_capacity(tv.ct.len() + tv.tag.len());
+    match C::TAG_POSITION {
+        TagPosition::Prefix => {
+            ciphertext.extend_from_slice(tv.tag);
+            ciphertext.extend_from_slice(tv.ct);
+        }
+        TagPosition::Postfix => {
+            ciphertext.extend_from_slice(tv.ct);
+            ciphertext.extend_from_slice(tv.tag);
+        }
+    }
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: tv.aad,
+    };
+
+    match result {
+        WycheproofResult::Valid => {
+            let res = cipher
+                .encrypt(
+                    &nonce,
+                    Payload {
+                        msg: tv.msg,
+                        aad: tv.aad,
+                    },
+                )
+                .map_err(|_| "encryption failure")?;
+            if res != ciphertext {
+                return Err("encrypted data is different from target ciphertext");
+            }
+
+            let res = cipher
+                .decrypt(&nonce, payload)
+                .map_err(|_| "decryption failure")?;
+            if res != tv.msg {
+                return Err("decrypted data is different from target plaintext");
+            }
+
+            let mut buf = tv.ct.to_vec();
+            for i in 0..8 * tag.len() {
+                let mut modified_tag = tag.clone();
+                modified_tag[i / 8] ^= 1 << (i % 8);
+                buf.copy_from_slice(tv.ct);
+                let res = cipher.decrypt_inout_detached(
+                    &nonce,
+                    tv.aad,
+                    buf.as_mut_slice().into(),
+                    &modified_tag,
+                );
+                if res.is_ok() {
+                    return Err("decryption succeeded with modified tag");
+                }
+            }
+        }
+        WycheproofResult::Invalid => {
+            if cipher.decrypt(&nonce, payload).is_ok() {
+                return Err("decryption must return error");
+            }
+        }
+        WycheproofResult::Acceptable => {
+            if let Ok(res) = cipher.decrypt(&nonce, payload) {
+                if res != tv.msg {
+                    return Err("decrypted data is different from target plaintext");
+                }
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Expected result of a Wycheproof test vector
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum WycheproofResult {
+    Valid,
+    Invalid,
+    Acceptable,
+}
+
 /// Define AEAD test
 #[macro_export]
 macro_rules! new_test {
@@ -166,3 +434,271 @@ macro_rules! new_test {
         }
     };
 }
+
+/// Define AEAD nonce reuse safety test
+#[macro_export]
+macro_rules! new_nonce_reuse_test {
+    ($name:ident, $cipher:ty $(,)?) => {
+        #[test]
+        fn $name() {
+            if let Err(reason) = $crate::dev::nonce_reuse_test::<$cipher>() {
+                panic!(
+                    "\n\
+                    Failed nonce reuse test\n\
+                    reason:\t{reason:?}\n"
+                );
+            }
+        }
+    };
+}
+
+/// Define Wycheproof AEAD test
+///
+/// Fails if all test vectors are skipped, e.g. because none of them uses the
+/// key size of the tested AEAD. An optional
+/// [`WycheproofFlags`](crate::dev::WycheproofFlags) argument configures the
+/// handling of flagged vectors:
+///
+/// ```ignore
+/// aead::new_wycheproof_test!(
+///     aes_gcm,
+///     "aes_gcm_test",
+///     Aes128Gcm,
+///     aead::dev::WycheproofFlags {
+///         skip: &["LongIv"],
+///         invalid: &["SmallIv"],
+///         ..aead::dev::WycheproofFlags::NONE
+///     },
+/// );
+/// ```
+#[macro_export]
+macro_rules! new_wycheproof_test {
+    ($name:ident, $test_name:expr, $cipher:ty $(,)?) => {
+        $crate::new_wycheproof_test!(
+            $name,
+            $test_name,
+            $cipher,
+            $crate::dev::WycheproofFlags::NONE,
+        );
+    };
+    ($name:ident, $test_name:expr, $cipher:ty, $flags:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            use $crate::dev::WycheproofVector;
+
+            $crate::dev::blobby::parse_into_structs!(
+                include_bytes!(concat!("data/", $test_name, ".blb"));
+                static TEST_VECTORS: &[
+                    WycheproofVector { tc_id, key, nonce, aad, msg, ct, tag, result, flags }
+                ];
+            );
+
+            let mut tested = 0;
+            for tv in TEST_VECTORS.iter() {
+                match $crate::dev::wycheproof_test_with_flags::<$cipher>(tv, &$flags) {
+                    Ok(true) => tested += 1,
+                    Ok(false) => {}
+                    Err(reason) => panic!(
+                        "\n\
+                        Failed Wycheproof test {tv}\n\
+                        reason:\t{reason:?}\n\
+                        test vector:\t{tv:?}\n"
+                    ),
+                }
+            }
+            assert!(tested > 0, "all test vectors were skipped");
+        }
+    };
+}
+
+/// Define AEAD encryption benchmark
+///
+/// Every benchmark encrypts a message of the given size using
+/// [`Aead::encrypt`], optionally with associated data of the given size.
+///
+/// ```ignore
+/// aead::aead_encrypt_bench!(
+///     Aes128Gcm;
+///     encrypt_16 16;
+///     encrypt_1k 1024;
+///     encrypt_16k 16384;
+///     encrypt_1k_aad_16k 1024, 16384;
+/// );
+/// ```
+///
+/// Given only the cipher, the macro defines benchmarks for messages of 16,
+/// 256, 1024 and 16384 bytes without associated data, and for messages of 16
+/// and 1024 bytes with 16384 bytes of associated data. The same applies to
+/// [`aead_decrypt_bench!`](crate::aead_decrypt_bench) and
+/// [`aead_inout_bench!`](crate::aead_inout_bench).
+///
+/// ```ignore
+/// aead::aead_encrypt_bench!(Aes128Gcm);
+/// ```
+#[macro_export]
+macro_rules! aead_encrypt_bench {
+    ($cipher:ty $(,)?) => {
+        $crate::aead_encrypt_bench!(
+            $cipher;
+            encrypt_16 16;
+            encrypt_256 256;
+            encrypt_1k 1024;
+            encrypt_16k 16384;
+            encrypt_16_aad_16k 16, 16384;
+            encrypt_1k_aad_16k 1024, 16384;
+        );
+    };
+    (
+        $cipher:ty;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $crate::aead_encrypt_bench!(
+            Init: {
+                use $crate::KeyInit;
+                let key = test::black_box(Default::default());
+                <$cipher>::new(&key)
+            };
+            $($name $bs $(, $aad)?;)*
+        );
+    };
+    (
+        Init: $init:expr;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $(
+            #[bench]
+            fn $name(b: &mut test::Bencher) {
+                use $crate::{Aead, Payload};
+
+                let cipher = $init;
+                let nonce = test::black_box(Default::default());
+                let msg = vec![0; $bs];
+                let aad = vec![0; 0 $(+ $aad)?];
+
+                b.iter(|| {
+                    let payload = Payload { msg: &msg, aad: &aad };
+                    let res = cipher.encrypt(&nonce, payload);
+                    test::black_box(res.expect("encryption failure"));
+                });
+
+                b.bytes = (msg.len() + aad.len()) as u64;
+            }
+        )*
+    };
+}
+
+/// Define AEAD decryption benchmark
+///
+/// Every benchmark decrypts a message of the given size using
+/// [`Aead::decrypt`], optionally with associated data of the given size.
+#[macro_export]
+macro_rules! aead_decrypt_bench {
+    ($cipher:ty $(,)?) => {
+        $crate::aead_decrypt_bench!(
+            $cipher;
+            decrypt_16 16;
+            decrypt_256 256;
+            decrypt_1k 1024;
+            decrypt_16k 16384;
+            decrypt_16_aad_16k 16, 16384;
+            decrypt_1k_aad_16k 1024, 16384;
+        );
+    };
+    (
+        $cipher:ty;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $crate::aead_decrypt_bench!(
+            Init: {
+                use $crate::KeyInit;
+                let key = test::black_box(Default::default());
+                <$cipher>::new(&key)
+            };
+            $($name $bs $(, $aad)?;)*
+        );
+    };
+    (
+        Init: $init:expr;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $(
+            #[bench]
+            fn $name(b: &mut test::Bencher) {
+                use $crate::{Aead, Payload};
+
+                let cipher = $init;
+                let nonce = test::black_box(Default::default());
+                let msg = vec![0; $bs];
+                let aad = vec![0; 0 $(+ $aad)?];
+                let ciphertext = cipher
+                    .encrypt(&nonce, Payload { msg: &msg, aad: &aad })
+                    .expect("encryption failure");
+
+                b.iter(|| {
+                    let payload = Payload { msg: &ciphertext, aad: &aad };
+                    let res = cipher.decrypt(&nonce, payload);
+                    test::black_box(res.expect("decryption failure"));
+                });
+
+                b.bytes = (msg.len() + aad.len()) as u64;
+            }
+        )*
+    };
+}
+
+/// Define in-place AEAD encryption benchmark
+///
+/// Every benchmark encrypts a buffer of the given size in place using
+/// [`AeadInOut::encrypt_inout_detached`], optionally with associated data of
+/// the given size.
+#[macro_export]
+macro_rules! aead_inout_bench {
+    ($cipher:ty $(,)?) => {
+        $crate::aead_inout_bench!(
+            $cipher;
+            inout_16 16;
+            inout_256 256;
+            inout_1k 1024;
+            inout_16k 16384;
+            inout_16_aad_16k 16, 16384;
+            inout_1k_aad_16k 1024, 16384;
+        );
+    };
+    (
+        $cipher:ty;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $crate::aead_inout_bench!(
+            Init: {
+                use $crate::KeyInit;
+                let key = test::black_box(Default::default());
+                <$cipher>::new(&key)
+            };
+            $($name $bs $(, $aad)?;)*
+        );
+    };
+    (
+        Init: $init:expr;
+        $($name:ident $bs:expr $(, $aad:expr)?;)*
+    ) => {
+        $(
+            #[bench]
+            fn $name(b: &mut test::Bencher) {
+                use $crate::AeadInOut;
+
+                let cipher = $init;
+                let nonce = test::black_box(Default::default());
+                let mut buf = vec![0; $bs];
+                let aad = vec![0; 0 $(+ $aad)?];
+
+                b.iter(|| {
+                    let res = cipher.encrypt_inout_detached(&nonce, &aad, buf.as_mut_slice().into());
+                    test::black_box(res.expect("encryption failure"));
+                    test::black_box(&buf);
+                });
+
+                b.bytes = (buf.len() + aad.len()) as u64;
+            }
+        )*
+    };
+}
@@ -0,0 +1,506 @@
+//! Generic Encrypt-then-MAC AEAD composition.
+//!
+//! [`EncryptThenMac`] combines an unauthenticated cipher with a MAC: the
+//! message is encrypted, then the MAC is computed over the nonce, associated
+//! data and ciphertext as specified by a [`MacFormat`]. The MAC is verified
+//! in constant time before anything is decrypted.
+//!
+//! The cipher is plugged in through the [`EtmCipher`] trait, which is
+//! implemented by:
+//!
+//! - [`StreamMode`] for stream ciphers such as AES-CTR or ChaCha20,
+//! - [`BlockMode`] for block modes such as AES-CBC.
+//!
+//! [`EncryptThenMac`] is length-preserving like any other [`AeadInOut`]
+//! implementation, so with a [`BlockMode`] it only accepts messages whose
+//! length is a multiple of the block size. [`Pkcs7EncryptThenMac`] pads
+//! messages of any length with PKCS#7 padding instead. As padding changes the
+//! ciphertext length, it only provides buffer-based encryption and does not
+//! implement [`AeadInOut`].
+//!
+//! For example AES-CBC-HMAC-SHA2 as specified by [RFC 7518 § 5.2], e.g. the
+//! `A128CBC-HS256` JOSE algorithm, corresponds to:
+//!
+//! ```ignore
+//! type A128CbcHs256 = Pkcs7EncryptThenMac<
+//!     cbc::Encryptor<Aes128>,
+//!     cbc::Decryptor<Aes128>,
+//!     Hmac<Sha256>,
+//!     Rfc7518<U16, U16>,
+//! >;
+//! ```
+//!
+//! [RFC 7518 § 5.2]: https://www.rfc-editor.org/rfc/rfc7518#section-5.2
+
+use crate::{
+    AeadCore, AeadInOut, Buffer, Error, Key, KeyInit, KeySizeUser, Nonce, Result, Tag, TagPosition,
+};
+use cipher::{
+    BlockModeDecrypt, BlockModeEncrypt, BlockSizeUser, Iv, IvSizeUser, KeyIvInit, StreamCipher,
+};
+use core::{fmt, marker::PhantomData, ops::Add};
+use crypto_common::array::{
+    Array, ArraySize,
+    typenum::{IsLessOrEqual, Sum, True, Unsigned},
+};
+use digest::Mac;
+use inout::InOutBuf;
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "truncated")]
+use crate::truncated::DecryptUnverified;
+#[cfg(feature = "alloc")]
+use crate::{Aead, Payload};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Unauthenticated cipher used by [`EncryptThenMac`].
+pub trait EtmCipher: KeySizeUser + IvSizeUser {
+    /// Encrypt the data in the provided [`InOutBuf`].
+    ///
+    /// The ciphertext has the same length as the plaintext, so ciphers which
+    /// require padding may reject some lengths.
+    fn encrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()>;
+
+    /// Decrypt the data in the provided [`InOutBuf`].
+    fn decrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()>;
+}
+
+/// [`EtmCipher`] adapter for stream ciphers, e.g. AES-CTR.
+pub struct StreamMode<C> {
+    _cipher: PhantomData<C>,
+}
+
+impl<C: KeySizeUser> KeySizeUser for StreamMode<C> {
+    type KeySize = C::KeySize;
+}
+
+impl<C: IvSizeUser> IvSizeUser for StreamMode<C> {
+    type IvSize = C::IvSize;
+}
+
+impl<C: KeyIvInit + StreamCipher> EtmCipher for StreamMode<C> {
+    fn encrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        C::new(key, iv)
+            .try_apply_keystream_inout(buffer)
+            .map_err(|_| Error)
+    }
+
+    fn decrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        Self::encrypt_inout(key, iv, buffer)
+    }
+}
+
+impl<C> fmt::Debug for StreamMode<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StreamMode")
+    }
+}
+
+/// [`EtmCipher`] adapter for block modes, e.g. AES-CBC, using the `E` and `D`
+/// mode encryptor and decryptor.
+///
+/// Only messages whose length is a multiple of the block size are accepted.
+/// Use [`Pkcs7EncryptThenMac`] to pad messages of any length.
+pub struct BlockMode<E, D> {
+    _mode: PhantomData<(E, D)>,
+}
+
+impl<E: KeySizeUser, D> KeySizeUser for BlockMode<E, D> {
+    type KeySize = E::KeySize;
+}
+
+impl<E: IvSizeUser, D> IvSizeUser for BlockMode<E, D> {
+    type IvSize = E::IvSize;
+}
+
+impl<E, D> EtmCipher for BlockMode<E, D>
+where
+    E: KeyIvInit + BlockModeEncrypt,
+    D: KeyIvInit<KeySize = E::KeySize, IvSize = E::IvSize>
+        + BlockModeDecrypt<BlockSize = E::BlockSize>,
+{
+    fn encrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        let (blocks, tail) = buffer.into_chunks();
+        if !tail.is_empty() {
+            return Err(Error);
+        }
+        E::new(key, iv).encrypt_blocks_inout(blocks);
+        Ok(())
+    }
+
+    fn decrypt_inout(key: &Key<Self>, iv: &Iv<Self>, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        let (blocks, tail) = buffer.into_chunks();
+        if !tail.is_empty() {
+            return Err(Error);
+        }
+        D::new(key, iv).decrypt_blocks_inout(blocks);
+        Ok(())
+    }
+}
+
+impl<E, D> fmt::Debug for BlockMode<E, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockMode")
+    }
+}
+
+/// Format of the MAC key, tag and input used by [`EncryptThenMac`].
+pub trait MacFormat {
+    /// Size of the MAC key.
+    type MacKeySize: ArraySize;
+
+    /// Size of the tag, which is the MAC output truncated to this size.
+    type TagSize: ArraySize;
+
+    /// Feed the MAC input for the given nonce, associated data and ciphertext.
+    fn update_mac(mac: &mut impl Mac, nonce: &[u8], associated_data: &[u8], ciphertext: &[u8]);
+}
+
+/// MAC input formatting of [RFC 7518 § 5.2.2.1]: `AAD || IV || C || AL`,
+/// where `AL` is the bit length of the AAD as a 64-bit big endian integer.
+///
+/// `A128CBC-HS256`, `A192CBC-HS384` and `A256CBC-HS512` respectively use
+/// `Rfc7518<U16, U16>`, `Rfc7518<U24, U24>` and `Rfc7518<U32, U32>`.
+///
+/// [RFC 7518 § 5.2.2.1]: https://www.rfc-editor.org/rfc/rfc7518#section-5.2.2.1
+pub struct Rfc7518<MacKeySize, TagSize> {
+    _sizes: PhantomData<(MacKeySize, TagSize)>,
+}
+
+impl<MK: ArraySize, T: ArraySize> MacFormat for Rfc7518<MK, T> {
+    type MacKeySize = MK;
+    type TagSize = T;
+
+    fn update_mac(mac: &mut impl Mac, nonce: &[u8], associated_data: &[u8], ciphertext: &[u8]) {
+        let aad_bits = (associated_data.len() as u64)
+            .checked_mul(8)
+            .expect("associated data is too long");
+        mac.update(associated_data);
+        mac.update(nonce);
+        mac.update(ciphertext);
+        mac.update(&aad_bits.to_be_bytes());
+    }
+}
+
+impl<MK, T> fmt::Debug for Rfc7518<MK, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Rfc7518")
+    }
+}
+
+/// MAC input formatting with both lengths appended:
+/// `nonce || AAD || C || len(AAD) || len(C)`, where the lengths are in bytes
+/// and encoded as 64-bit big endian integers.
+pub struct Lengths<MacKeySize, TagSize> {
+    _sizes: PhantomData<(MacKeySize, TagSize)>,
+}
+
+impl<MK: ArraySize, T: ArraySize> MacFormat for Lengths<MK, T> {
+    type MacKeySize = MK;
+    type TagSize = T;
+
+    fn update_mac(mac: &mut impl Mac, nonce: &[u8], associated_data: &[u8], ciphertext: &[u8]) {
+        mac.update(nonce);
+        mac.update(associated_data);
+        mac.update(ciphertext);
+        mac.update(&(associated_data.len() as u64).to_be_bytes());
+        mac.update(&(ciphertext.len() as u64).to_be_bytes());
+    }
+}
+
+impl<MK, T> fmt::Debug for Lengths<MK, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Lengths")
+    }
+}
+
+/// Encrypt-then-MAC AEAD composed of the cipher `C` and the MAC `M`, with the
+/// MAC key, tag and input format `F`.
+///
+/// The key is the concatenation of the MAC key and the encryption key, in
+/// that order, and the nonce is the IV of the cipher.
+pub struct EncryptThenMac<C: EtmCipher, M, F> {
+    enc_key: Key<C>,
+    mac: M,
+    _format: PhantomData<F>,
+}
+
+impl<C, M, F> EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    M: Mac + Clone,
+    F: MacFormat,
+    F::TagSize: IsLessOrEqual<M::OutputSize, Output = True>,
+{
+    fn compute_tag(&self, nonce: &[u8], associated_data: &[u8], ciphertext: &[u8]) -> Tag<Self>
+    where
+        Self: AeadCore<TagSize = F::TagSize>,
+    {
+        let mut mac = self.mac.clone();
+        F::update_mac(&mut mac, nonce, associated_data, ciphertext);
+        let output = mac.finalize().into_bytes();
+        Array::try_from(&output[..F::TagSize::USIZE]).expect("tag size is checked")
+    }
+}
+
+impl<C, M, F> KeySizeUser for EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    F: MacFormat,
+    F::MacKeySize: Add<C::KeySize>,
+    Sum<F::MacKeySize, C::KeySize>: ArraySize,
+{
+    type KeySize = Sum<F::MacKeySize, C::KeySize>;
+}
+
+impl<C, M, F> KeyInit for EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    M: Mac + KeyInit,
+    F: MacFormat,
+    F::MacKeySize: Add<C::KeySize>,
+    Sum<F::MacKeySize, C::KeySize>: ArraySize,
+{
+    fn new(key: &Key<Self>) -> Self {
+        let (mac_key, enc_key) = key.split_at(F::MacKeySize::USIZE);
+        Self {
+            enc_key: Array::try_from(enc_key).expect("key size mismatch"),
+            mac: <M as KeyInit>::new_from_slice(mac_key).expect("MAC must accept the MAC key size"),
+            _format: PhantomData,
+        }
+    }
+}
+
+impl<C, M, F> AeadCore for EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    F: MacFormat,
+{
+    type NonceSize = C::IvSize;
+    type TagSize = F::TagSize;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+impl<C, M, F> AeadInOut for EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    M: Mac + Clone,
+    F: MacFormat,
+    F::TagSize: IsLessOrEqual<M::OutputSize, Output = True>,
+{
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        mut buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        C::encrypt_inout(&self.enc_key, nonce, buffer.reborrow())?;
+        Ok(self.compute_tag(nonce, associated_data, buffer.get_out()))
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        let expected_tag = self.compute_tag(nonce, associated_data, buffer.get_in());
+        if !bool::from(expected_tag.ct_eq(tag)) {
+            return Err(Error);
+        }
+        C::decrypt_inout(&self.enc_key, nonce, buffer)
+    }
+}
+
+#[cfg(feature = "truncated")]
+impl<C, M, F> DecryptUnverified for EncryptThenMac<C, M, F>
+where
+    C: EtmCipher,
+    M: Mac + Clone,
+    F: MacFormat,
+    F::TagSize: IsLessOrEqual<M::OutputSize, Output = True>,
+{
+    fn decrypt_inout_unverified(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        let tag = self.compute_tag(nonce, associated_data, buffer.get_in());
+        C::decrypt_inout(&self.enc_key, nonce, buffer)?;
+        Ok(tag)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<C: EtmCipher, M, F> Drop for EncryptThenMac<C, M, F> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self.enc_key.as_mut_slice());
+    }
+}
+
+/// Requires the MAC to zeroize its own key on drop.
+#[cfg(feature = "zeroize")]
+impl<C: EtmCipher, M: zeroize::ZeroizeOnDrop, F> zeroize::ZeroizeOnDrop
+    for EncryptThenMac<C, M, F>
+{
+}
+
+impl<C: EtmCipher, M, F> fmt::Debug for EncryptThenMac<C, M, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptThenMac").finish_non_exhaustive()
+    }
+}
+
+/// [`EncryptThenMac`] over the [`BlockMode`] `E`/`D` which pads messages with
+/// PKCS#7 padding, so messages of any length can be encrypted.
+///
+/// Padding changes the ciphertext length, so this type does not implement
+/// [`AeadInOut`]: use [`Pkcs7EncryptThenMac::encrypt_in_place`] and
+/// [`Pkcs7EncryptThenMac::decrypt_in_place`], or the [`Aead`][crate::Aead]
+/// trait with the `alloc` feature. The padding is only checked after the tag
+/// has been verified, so it can't be used as a padding oracle.
+pub struct Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+{
+    inner: EncryptThenMac<BlockMode<E, D>, M, F>,
+}
+
+impl<E, D, M, F> Pkcs7EncryptThenMac<E, D, M, F>
+where
+    E: BlockSizeUser,
+    BlockMode<E, D>: EtmCipher,
+    M: Mac + Clone,
+    F: MacFormat,
+    F::TagSize: IsLessOrEqual<M::OutputSize, Output = True>,
+{
+    /// Pad the given buffer containing a plaintext message and encrypt it
+    /// in-place, appending the tag.
+    ///
+    /// The buffer must have room for up to a block of padding in addition to
+    /// the tag.
+    pub fn encrypt_in_place(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut dyn Buffer,
+    ) -> Result<()> {
+        let block_size = E::BlockSize::USIZE;
+        let pad_len = block_size - buffer.len() % block_size;
+        let pad_byte = u8::try_from(pad_len).map_err(|_| Error)?;
+        for _ in 0..pad_len {
+            buffer.extend_from_slice(&[pad_byte])?;
+        }
+        self.inner.encrypt_in_place(nonce, associated_data, buffer)
+    }
+
+    /// Verify the tag and decrypt the given buffer in-place, then remove the
+    /// padding, leaving only the plaintext message.
+    pub fn decrypt_in_place(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut dyn Buffer,
+    ) -> Result<()> {
+        self.inner
+            .decrypt_in_place(nonce, associated_data, buffer)?;
+
+        let msg = buffer.as_ref();
+        let pad_byte = *msg.last().ok_or(Error)?;
+        let pad_len = usize::from(pad_byte);
+        if pad_len == 0 || pad_len > E::BlockSize::USIZE || pad_len > msg.len() {
+            return Err(Error);
+        }
+        let msg_len = msg.len() - pad_len;
+        if msg[msg_len..].iter().any(|&b| b != pad_byte) {
+            return Err(Error);
+        }
+        buffer.truncate(msg_len);
+        Ok(())
+    }
+}
+
+impl<E, D, M, F> KeySizeUser for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+    EncryptThenMac<BlockMode<E, D>, M, F>: KeySizeUser,
+{
+    type KeySize = <EncryptThenMac<BlockMode<E, D>, M, F> as KeySizeUser>::KeySize;
+}
+
+impl<E, D, M, F> KeyInit for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+    EncryptThenMac<BlockMode<E, D>, M, F>: KeyInit,
+{
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            inner: EncryptThenMac::new(key),
+        }
+    }
+}
+
+impl<E, D, M, F> AeadCore for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+    F: MacFormat,
+{
+    type NonceSize = <BlockMode<E, D> as IvSizeUser>::IvSize;
+    type TagSize = F::TagSize;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+#[cfg(feature = "alloc")]
+impl<E, D, M, F> Aead for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    E: BlockSizeUser,
+    BlockMode<E, D>: EtmCipher,
+    M: Mac + Clone,
+    F: MacFormat,
+    F::TagSize: IsLessOrEqual<M::OutputSize, Output = True>,
+{
+    fn encrypt<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<Self>,
+        plaintext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = plaintext.into();
+        let mut buffer =
+            Vec::with_capacity(payload.msg.len() + E::BlockSize::USIZE + F::TagSize::USIZE);
+        buffer.extend_from_slice(payload.msg);
+        self.encrypt_in_place(nonce, payload.aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn decrypt<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<Self>,
+        ciphertext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = ciphertext.into();
+        let mut buffer = Vec::from(payload.msg);
+        self.decrypt_in_place(nonce, payload.aad, &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<E, D, M, F> zeroize::ZeroizeOnDrop for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+    M: zeroize::ZeroizeOnDrop,
+{
+}
+
+impl<E, D, M, F> fmt::Debug for Pkcs7EncryptThenMac<E, D, M, F>
+where
+    BlockMode<E, D>: EtmCipher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs7EncryptThenMac")
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,397 @@
+//! Low-level online AEAD traits.
+//!
+//! Some AEADs, such as GCM, OCB and Ascon, can process a message
+//! incrementally and produce the tag at the end. [`AeadOnline`] exposes this
+//! capability: [`AeadOnline::begin_encrypt`] and [`AeadOnline::begin_decrypt`]
+//! return an [`OnlineEncryptor`] or [`OnlineDecryptor`] for the given nonce,
+//! which are fed the associated data using `update_aad` and the message
+//! using `update_inout`, and finally produce or verify the tag using
+//! [`OnlineEncryptor::finish_tag`] or [`OnlineDecryptor::verify_finish`].
+//!
+//! Input must be fed in chunks whose length is a multiple of the
+//! [`BlockSize`](BlockSizeUser::BlockSize) of the AEAD, except for the last
+//! associated data chunk and the last message chunk. All associated data must
+//! be processed before the message. Implementations return [`Error`] when
+//! these rules are violated.
+//!
+//! [`BufferedEncryptor`] and [`BufferedDecryptor`] lift this restriction for
+//! any implementation by buffering partial blocks, at the cost of releasing
+//! output up to a block later than the corresponding input.
+//!
+//! # ⚠️ Security Warning: Hazmat!
+//!
+//! [`OnlineDecryptor::update_inout`] releases plaintext **before** the tag is
+//! verified. An attacker can modify the ciphertext at will, so this
+//! plaintext must not be acted upon, e.g. parsed, forwarded or written to
+//! persistent storage, until [`OnlineDecryptor::verify_finish`] returns
+//! `Ok`. When verification fails, all plaintext released by the decryptor
+//! must be discarded.
+//!
+//! Prefer the STREAM construction of the `stream` module, which
+//! authenticates every chunk of a message separately, unless the format of
+//! the message is fixed by a protocol.
+//!
+//! [`Error`]: crate::Error
+
+use crate::{AeadCore, Error, Nonce, Result, Tag};
+use core::fmt;
+use crypto_common::{
+    BlockSizeUser,
+    array::{Array, ArraySize},
+};
+use inout::InOutBuf;
+
+/// Block size of the [`AeadOnline`] algorithm `A`.
+type BlockSize<A> = <A as BlockSizeUser>::BlockSize;
+
+/// AEAD algorithm which can process messages incrementally.
+pub trait AeadOnline: AeadCore + BlockSizeUser {
+    /// Online encryption state.
+    type Encryptor<'a>: OnlineEncryptor<Aead = Self>
+    where
+        Self: 'a;
+
+    /// Online decryption state.
+    type Decryptor<'a>: OnlineDecryptor<Aead = Self>
+    where
+        Self: 'a;
+
+    /// Begin encrypting a message with the given nonce.
+    fn begin_encrypt(&self, nonce: &Nonce<Self>) -> Self::Encryptor<'_>;
+
+    /// Begin decrypting a message with the given nonce.
+    fn begin_decrypt(&self, nonce: &Nonce<Self>) -> Self::Decryptor<'_>;
+}
+
+/// Online encryption state of an [`AeadOnline`] algorithm.
+pub trait OnlineEncryptor: Sized {
+    /// AEAD algorithm.
+    type Aead: AeadOnline;
+
+    /// Authenticate the next chunk of associated data.
+    ///
+    /// Returns [`Error`](crate::Error) if the previous chunk was not a
+    /// multiple of the block size or if message data was already processed.
+    fn update_aad(&mut self, associated_data: &[u8]) -> Result<()>;
+
+    /// Encrypt the next chunk of the message in the provided [`InOutBuf`].
+    ///
+    /// Returns [`Error`](crate::Error) if the previous chunk was not a
+    /// multiple of the block size.
+    fn update_inout(&mut self, buffer: InOutBuf<'_, '_, u8>) -> Result<()>;
+
+    /// Finish encryption and return the authentication tag.
+    fn finish_tag(self) -> Result<Tag<Self::Aead>>;
+}
+
+/// Online decryption state of an [`AeadOnline`] algorithm.
+///
+/// # ⚠️ Security Warning
+///
+/// The plaintext produced by [`OnlineDecryptor::update_inout`] is
+/// unverified, see the [module-level documentation](self).
+pub trait OnlineDecryptor: Sized {
+    /// AEAD algorithm.
+    type Aead: AeadOnline;
+
+    /// Authenticate the next chunk of associated data.
+    ///
+    /// Returns [`Error`](crate::Error) if the previous chunk was not a
+    /// multiple of the block size or if message data was already processed.
+    fn update_aad(&mut self, associated_data: &[u8]) -> Result<()>;
+
+    /// Decrypt the next chunk of the message in the provided [`InOutBuf`]
+    /// **without verifying it**.
+    ///
+    /// Returns [`Error`](crate::Error) if the previous chunk was not a
+    /// multiple of the block size.
+    fn update_inout(&mut self, buffer: InOutBuf<'_, '_, u8>) -> Result<()>;
+
+    /// Finish decryption and verify the authentication tag.
+    ///
+    /// Returns [`Error`](crate::Error) if verification fails, in which case
+    /// all plaintext produced by this decryptor must be discarded.
+    fn verify_finish(self, tag: &Tag<Self::Aead>) -> Result<()>;
+}
+
+/// Adapter for an [`OnlineEncryptor`] accepting associated data and message
+/// chunks of any length.
+///
+/// Partial blocks are buffered until they are completed by the next chunk or
+/// until [`BufferedEncryptor::finish`] is called, so
+/// [`BufferedEncryptor::update`] may output less ciphertext than it was given
+/// plaintext.
+pub struct BufferedEncryptor<E: OnlineEncryptor> {
+    inner: E,
+    state: BufferState<BlockSize<E::Aead>>,
+}
+
+impl<E: OnlineEncryptor> BufferedEncryptor<E> {
+    /// Wrap the given encryptor, which must not have processed any input yet.
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            state: BufferState::default(),
+        }
+    }
+
+    /// Authenticate the next chunk of associated data.
+    ///
+    /// Returns [`Error`] if message data was already processed.
+    pub fn update_aad(&mut self, associated_data: &[u8]) -> Result<()> {
+        self.state
+            .update_aad(associated_data, &mut self.inner, E::update_aad)
+    }
+
+    /// Encrypt the next chunk of the message from `input` into `output`,
+    /// returning the number of ciphertext bytes written.
+    ///
+    /// Every complete block is written, so `output` must be at least as long
+    /// as the buffered plaintext and `input` together, rounded down to a
+    /// multiple of the block size. Returns [`Error`] otherwise.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize> {
+        self.state.update(
+            input,
+            output,
+            &mut self.inner,
+            E::update_aad,
+            E::update_inout,
+        )
+    }
+
+    /// Encrypt the buffered plaintext into `output` and finish encryption,
+    /// returning the number of ciphertext bytes written and the
+    /// authentication tag.
+    ///
+    /// `output` must be at least as long as the buffered plaintext, which is
+    /// shorter than a block. Returns [`Error`] otherwise.
+    pub fn finish(mut self, output: &mut [u8]) -> Result<(usize, Tag<E::Aead>)> {
+        let written = self
+            .state
+            .finish(output, &mut self.inner, E::update_aad, E::update_inout)?;
+        Ok((written, self.inner.finish_tag()?))
+    }
+}
+
+impl<E: OnlineEncryptor> fmt::Debug for BufferedEncryptor<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferedEncryptor").finish_non_exhaustive()
+    }
+}
+
+/// Adapter for an [`OnlineDecryptor`] accepting associated data and message
+/// chunks of any length.
+///
+/// Partial blocks are buffered until they are completed by the next chunk or
+/// until [`BufferedDecryptor::verify_finish`] is called, so
+/// [`BufferedDecryptor::update`] may output less plaintext than it was given
+/// ciphertext.
+///
+/// # ⚠️ Security Warning
+///
+/// The plaintext produced by [`BufferedDecryptor::update`] and
+/// [`BufferedDecryptor::verify_finish`] is unverified until the latter
+/// returns `Ok`, see the [module-level documentation](self).
+pub struct BufferedDecryptor<D: OnlineDecryptor> {
+    inner: D,
+    state: BufferState<BlockSize<D::Aead>>,
+}
+
+impl<D: OnlineDecryptor> BufferedDecryptor<D> {
+    /// Wrap the given decryptor, which must not have processed any input yet.
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            state: BufferState::default(),
+        }
+    }
+
+    /// Authenticate the next chunk of associated data.
+    ///
+    /// Returns [`Error`] if message data was already processed.
+    pub fn update_aad(&mut self, associated_data: &[u8]) -> Result<()> {
+        self.state
+            .update_aad(associated_data, &mut self.inner, D::update_aad)
+    }
+
+    /// Decrypt the next chunk of the message from `input` into `output`
+    /// **without verifying it**, returning the number of plaintext bytes
+    /// written.
+    ///
+    /// Every complete block is written, so `output` must be at least as long
+    /// as the buffered ciphertext and `input` together, rounded down to a
+    /// multiple of the block size. Returns [`Error`] otherwise.
+    pub fn update(&mut self, input: &[u8], output: &mut [u8]) -> Result<usize> {
+        self.state.update(
+            input,
+            output,
+            &mut self.inner,
+            D::update_aad,
+            D::update_inout,
+        )
+    }
+
+    /// Decrypt the buffered ciphertext into `output`, then finish decryption
+    /// and verify the authentication tag, returning the number of plaintext
+    /// bytes written.
+    ///
+    /// `output` must be at least as long as the buffered ciphertext, which is
+    /// shorter than a block. Returns [`Error`] otherwise or if verification
+    /// fails, in which case all plaintext produced by this decryptor must be
+    /// discarded.
+    pub fn verify_finish(mut self, output: &mut [u8], tag: &Tag<D::Aead>) -> Result<usize> {
+        let written = self
+            .state
+            .finish(output, &mut self.inner, D::update_aad, D::update_inout)?;
+        self.inner.verify_finish(tag)?;
+        Ok(written)
+    }
+}
+
+impl<D: OnlineDecryptor> fmt::Debug for BufferedDecryptor<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferedDecryptor").finish_non_exhaustive()
+    }
+}
+
+/// Partial block buffering shared by [`BufferedEncryptor`] and
+/// [`BufferedDecryptor`].
+struct BufferState<B: ArraySize> {
+    block: Array<u8, B>,
+    pos: usize,
+    in_message: bool,
+}
+
+impl<B: ArraySize> Default for BufferState<B> {
+    fn default() -> Self {
+        Self {
+            block: Array::default(),
+            pos: 0,
+            in_message: false,
+        }
+    }
+}
+
+impl<B: ArraySize> BufferState<B> {
+    fn update_aad<S>(
+        &mut self,
+        mut aad: &[u8],
+        inner: &mut S,
+        update_aad: impl Fn(&mut S, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        if self.in_message {
+            return Err(Error);
+        }
+
+        if self.pos != 0 {
+            aad = self.fill(aad);
+            if self.pos < B::USIZE {
+                return Ok(());
+            }
+            update_aad(inner, &self.block)?;
+            self.pos = 0;
+        }
+
+        let (blocks, tail) = aad.split_at(aad.len() - aad.len() % B::USIZE);
+        if !blocks.is_empty() {
+            update_aad(inner, blocks)?;
+        }
+        self.fill(tail);
+        Ok(())
+    }
+
+    fn update<S>(
+        &mut self,
+        mut input: &[u8],
+        output: &mut [u8],
+        inner: &mut S,
+        update_aad: impl Fn(&mut S, &[u8]) -> Result<()>,
+        update_inout: impl Fn(&mut S, InOutBuf<'_, '_, u8>) -> Result<()>,
+    ) -> Result<usize> {
+        let pending = if self.in_message { self.pos } else { 0 };
+        if output.len() < (pending + input.len()) / B::USIZE * B::USIZE {
+            return Err(Error);
+        }
+        self.start_message(inner, update_aad)?;
+
+        let mut written = 0;
+        if self.pos != 0 {
+            input = self.fill(input);
+            if self.pos < B::USIZE {
+                return Ok(0);
+            }
+            update_inout(inner, self.block.as_mut_slice().into())?;
+            output[..B::USIZE].copy_from_slice(&self.block);
+            self.pos = 0;
+            written = B::USIZE;
+        }
+
+        let (blocks, tail) = input.split_at(input.len() - input.len() % B::USIZE);
+        if !blocks.is_empty() {
+            let out = &mut output[written..][..blocks.len()];
+            update_inout(
+                inner,
+                InOutBuf::new(blocks, out).expect("lengths are equal"),
+            )?;
+            written += blocks.len();
+        }
+        self.fill(tail);
+        Ok(written)
+    }
+
+    fn finish<S>(
+        &mut self,
+        output: &mut [u8],
+        inner: &mut S,
+        update_aad: impl Fn(&mut S, &[u8]) -> Result<()>,
+        update_inout: impl Fn(&mut S, InOutBuf<'_, '_, u8>) -> Result<()>,
+    ) -> Result<usize> {
+        let pending = if self.in_message { self.pos } else { 0 };
+        if output.len() < pending {
+            return Err(Error);
+        }
+        self.start_message(inner, update_aad)?;
+
+        if pending != 0 {
+            update_inout(inner, self.block[..pending].as_mut().into())?;
+            output[..pending].copy_from_slice(&self.block[..pending]);
+            self.pos = 0;
+        }
+        Ok(pending)
+    }
+
+    /// Flush the buffered associated data before the first message chunk.
+    fn start_message<S>(
+        &mut self,
+        inner: &mut S,
+        update_aad: impl Fn(&mut S, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        if !self.in_message {
+            if self.pos != 0 {
+                update_aad(inner, &self.block[..self.pos])?;
+                self.pos = 0;
+            }
+            self.in_message = true;
+        }
+        Ok(())
+    }
+
+    /// Append as much of `data` as fits to the buffered block, returning the
+    /// rest.
+    fn fill<'a>(&mut self, data: &'a [u8]) -> &'a [u8] {
+        let (head, rest) = data.split_at(data.len().min(B::USIZE - self.pos));
+        self.block[self.pos..][..head.len()].copy_from_slice(head);
+        self.pos += head.len();
+        rest
+    }
+}
+
+impl<B: ArraySize> Drop for BufferState<B> {
+    fn drop(&mut self) {
+        // The buffered block may hold unprocessed plaintext
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(self.block.as_mut_slice());
+        #[cfg(not(feature = "zeroize"))]
+        self.block.as_mut_slice().fill(0);
+    }
+}
@@ -0,0 +1,199 @@
+//! Key rotation.
+//!
+//! A [`Keyring`] holds multiple keys of an AEAD, each identified by a
+//! [`KeyId`]. Messages are encrypted with the primary key and prefixed with a
+//! header containing its ID, which is used to select the key during
+//! decryption. This allows rotating keys by inserting a new primary key while
+//! keeping the previous ones around to decrypt existing messages, and
+//! re-encrypting those messages under the new primary key with
+//! [`Keyring::reencrypt`] before removing the old keys.
+//!
+//! The header consists of the key ID encoded as a 32-bit big endian integer
+//! and is authenticated by prepending it to the associated data.
+//!
+//! # Zeroization
+//!
+//! Keys are only stored as boxed instances of the AEAD, which is required to
+//! implement [`ZeroizeOnDrop`], so key material is zeroized when a key is
+//! removed or the keyring is dropped. Keyrings can't be cloned, so keys are
+//! never copied. Plaintexts decrypted by [`Keyring::reencrypt`] are zeroized
+//! once they have been encrypted again.
+
+use crate::{Aead, AeadInOut, Error, Key, KeyInit, Nonce, Payload, Result};
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Key identifier.
+pub type KeyId = u32;
+
+/// Size of the key ID header prefixed to ciphertexts.
+pub const HEADER_SIZE: usize = 4;
+
+/// Read the ID of the key used to encrypt the given ciphertext from its
+/// header.
+///
+/// Returns [`Error`] if the ciphertext is too short to contain a header.
+pub fn key_id(ciphertext: &[u8]) -> Result<KeyId> {
+    let header = ciphertext.get(..HEADER_SIZE).ok_or(Error)?;
+    let header = header.try_into().expect("header size is checked");
+    Ok(KeyId::from_be_bytes(header))
+}
+
+/// Set of keys of the AEAD `A` identified by [`KeyId`]s, one of which is the
+/// primary key used for encryption.
+pub struct Keyring<A: ZeroizeOnDrop> {
+    primary: KeyId,
+    // Boxed so that keys are not copied around when the keyring is modified.
+    keys: Vec<(KeyId, Box<A>)>,
+}
+
+impl<A: AeadInOut + KeyInit + ZeroizeOnDrop> Keyring<A> {
+    /// Create a keyring with the given primary key.
+    pub fn new(id: KeyId, key: &Key<A>) -> Self {
+        Self {
+            primary: id,
+            keys: alloc::vec![(id, Box::new(A::new(key)))],
+        }
+    }
+
+    /// Add a key to the keyring.
+    ///
+    /// Returns [`Error`] if the keyring already contains a key with the
+    /// given ID.
+    pub fn insert(&mut self, id: KeyId, key: &Key<A>) -> Result<()> {
+        if self.contains(id) {
+            return Err(Error);
+        }
+        self.keys.push((id, Box::new(A::new(key))));
+        Ok(())
+    }
+
+    /// Remove the key with the given ID from the keyring.
+    ///
+    /// Returns [`Error`] if there is no such key or if it is the primary key.
+    pub fn remove(&mut self, id: KeyId) -> Result<()> {
+        if id == self.primary {
+            return Err(Error);
+        }
+        let pos = self.position(id)?;
+        self.keys.swap_remove(pos);
+        Ok(())
+    }
+
+    /// Use the key with the given ID as the primary key.
+    ///
+    /// Returns [`Error`] if there is no such key.
+    pub fn set_primary(&mut self, id: KeyId) -> Result<()> {
+        self.position(id)?;
+        self.primary = id;
+        Ok(())
+    }
+
+    /// ID of the primary key.
+    pub fn primary_id(&self) -> KeyId {
+        self.primary
+    }
+
+    /// Whether the keyring contains a key with the given ID.
+    pub fn contains(&self, id: KeyId) -> bool {
+        self.position(id).is_ok()
+    }
+
+    /// IDs of the keys in the keyring, in no particular order.
+    pub fn key_ids(&self) -> impl Iterator<Item = KeyId> + '_ {
+        self.keys.iter().map(|(id, _)| *id)
+    }
+
+    /// Encrypt the given plaintext payload with the primary key, and return
+    /// the key ID header followed by the resulting ciphertext.
+    pub fn encrypt<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<A>,
+        plaintext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = plaintext.into();
+        let header = self.primary.to_be_bytes();
+        let aead = self.get(self.primary)?;
+
+        let ciphertext = aead.encrypt(
+            nonce,
+            Payload {
+                msg: payload.msg,
+                aad: &[&header, payload.aad].concat(),
+            },
+        )?;
+        Ok([&header, ciphertext.as_slice()].concat())
+    }
+
+    /// Decrypt the given ciphertext payload, prefixed with its key ID header,
+    /// with the key it names, and return the resulting plaintext.
+    ///
+    /// Returns [`Error`] if the keyring does not contain the key or if
+    /// decryption fails.
+    pub fn decrypt<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<A>,
+        ciphertext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = ciphertext.into();
+        let aead = self.get(key_id(payload.msg)?)?;
+        let (header, msg) = payload.msg.split_at(HEADER_SIZE);
+
+        aead.decrypt(
+            nonce,
+            Payload {
+                msg,
+                aad: &[header, payload.aad].concat(),
+            },
+        )
+    }
+
+    /// Decrypt the given ciphertext payload and encrypt the result with the
+    /// primary key using the new nonce.
+    ///
+    /// The associated data is the same for both operations. The intermediate
+    /// plaintext is zeroized before returning.
+    pub fn reencrypt<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<A>,
+        new_nonce: &Nonce<A>,
+        ciphertext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = ciphertext.into();
+        let mut plaintext = self.decrypt(
+            nonce,
+            Payload {
+                msg: payload.msg,
+                aad: payload.aad,
+            },
+        )?;
+        let ciphertext = self.encrypt(
+            new_nonce,
+            Payload {
+                msg: &plaintext,
+                aad: payload.aad,
+            },
+        );
+        plaintext.as_mut_slice().zeroize();
+        ciphertext
+    }
+
+    fn position(&self, id: KeyId) -> Result<usize> {
+        self.keys.iter().position(|(i, _)| *i == id).ok_or(Error)
+    }
+
+    fn get(&self, id: KeyId) -> Result<&A> {
+        Ok(&self.keys[self.position(id)?].1)
+    }
+}
+
+impl<A: ZeroizeOnDrop> fmt::Debug for Keyring<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("primary", &self.primary)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: ZeroizeOnDrop> ZeroizeOnDrop for Keyring<A> {}
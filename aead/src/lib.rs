@@ -15,9 +15,29 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(feature = "std")]
+pub mod chunked;
+#[cfg(feature = "committing")]
+pub mod committing;
 #[cfg(feature = "dev")]
 pub mod dev;
+#[cfg(feature = "etm")]
+pub mod etm;
+pub mod hazmat;
+#[cfg(all(feature = "alloc", feature = "zeroize"))]
+pub mod keyring;
+pub mod nonce;
+#[cfg(feature = "siv")]
+pub mod siv;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "truncated")]
+pub mod truncated;
+#[cfg(feature = "uhf")]
+pub mod uhf;
 
 pub use crypto_common::{
     Key, KeyInit, KeySizeUser,
@@ -28,11 +48,19 @@ pub use crypto_common::{
 pub use arrayvec;
 #[cfg(feature = "bytes")]
 pub use bytes;
+#[cfg(any(feature = "etm", feature = "siv", feature = "uhf"))]
+pub use cipher;
 #[cfg(feature = "rand_core")]
 pub use crypto_common::rand_core;
+#[cfg(any(feature = "committing", feature = "etm", feature = "siv"))]
+pub use digest;
 #[cfg(feature = "heapless")]
 pub use heapless;
 pub use inout;
+#[cfg(feature = "uhf")]
+pub use universal_hash;
+#[cfg(feature = "zeroize")]
+pub use zeroize;
 
 use core::fmt;
 use crypto_common::array::{Array, ArraySize, typenum::Unsigned};
@@ -85,7 +113,11 @@ pub trait AeadCore {
     /// The length of a nonce.
     type NonceSize: ArraySize;
 
-    /// The maximum length of the tag.
+    /// The length of the tag.
+    ///
+    /// AEADs using shorter tags than the native ones of an algorithm should
+    /// use a smaller `TagSize`, e.g. using the `Truncated` wrapper from the
+    /// `truncated` module (enabled by the `truncated` feature).
     type TagSize: ArraySize;
 
     /// The AEAD tag position.
@@ -105,7 +137,7 @@ pub trait AeadCore {
     /// # ⚠️Security Warning
     ///
     /// AEAD algorithms often fail catastrophically if nonces are ever repeated
-    /// (with SIV modes being an exception).
+    /// (with SIV modes being an exception, see [`NonceMisuseResistant`]).
     ///
     /// Using random nonces runs the risk of repeating them unless the nonce
     /// size is particularly large (e.g. 192-bit extended nonces used by the
@@ -123,10 +155,11 @@ pub trait AeadCore {
     /// reach it should consider alternatives to purely random nonces, like
     /// a counter or a combination of a random nonce + counter.
     ///
-    /// See the [`aead-stream`] crate for a ready-made implementation of the latter.
+    /// See the [`nonce`] module for nonce sequences implementing these
+    /// strategies, and the `stream` module (enabled by the `stream` feature)
+    /// for a ready-made implementation of the latter.
     ///
     /// [NIST SP 800-38D]: https://csrc.nist.gov/publications/detail/sp/800-38d/final
-    /// [`aead-stream`]: https://docs.rs/aead-stream
     #[cfg(feature = "os_rng")]
     fn generate_nonce() -> core::result::Result<Nonce<Self>, OsError> {
         let mut nonce = Nonce::<Self>::default();
@@ -327,8 +360,120 @@ pub trait AeadInOut: AeadCore {
         buffer.truncate(tagless_len);
         Ok(())
     }
+
+    /// Encrypt in-place a message spread across multiple segments, with
+    /// associated data also spread across multiple segments, returning the
+    /// authentication tag.
+    ///
+    /// The result is the same as encrypting the concatenation of the message
+    /// segments with the concatenation of the associated data segments.
+    ///
+    /// The default implementation passes a single segment directly to
+    /// [`AeadInOut::encrypt_inout_detached`]. Several segments are copied
+    /// into temporary buffers, which are zeroized afterwards, and are only
+    /// supported with the `alloc` feature: without it an error is returned.
+    /// Implementations are encouraged to override it with one processing the
+    /// segments directly.
+    fn encrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+    ) -> Result<Tag<Self>> {
+        #[cfg(feature = "alloc")]
+        let associated_data = &*gather_aad(associated_data)?;
+        #[cfg(not(feature = "alloc"))]
+        let associated_data = gather_aad(associated_data)?;
+        match message {
+            [] => self.encrypt_inout_detached(nonce, associated_data, (&mut [][..]).into()),
+            [msg] => self.encrypt_inout_detached(nonce, associated_data, (&mut **msg).into()),
+            #[cfg(feature = "alloc")]
+            _ => {
+                let mut buffer = message.concat();
+                let result = self.encrypt_inout_detached(
+                    nonce,
+                    associated_data,
+                    buffer.as_mut_slice().into(),
+                );
+                if result.is_ok() {
+                    scatter(&buffer, message);
+                }
+                #[cfg(feature = "zeroize")]
+                zeroize::Zeroize::zeroize(buffer.as_mut_slice());
+                #[cfg(not(feature = "zeroize"))]
+                buffer.as_mut_slice().fill(0);
+                result
+            }
+            #[cfg(not(feature = "alloc"))]
+            _ => Err(Error),
+        }
+    }
+
+    /// Decrypt in-place a message spread across multiple segments, with
+    /// associated data also spread across multiple segments, returning an
+    /// error in the event the provided authentication tag is invalid.
+    ///
+    /// The result is the same as decrypting the concatenation of the message
+    /// segments with the concatenation of the associated data segments.
+    ///
+    /// The default implementation passes a single segment directly to
+    /// [`AeadInOut::decrypt_inout_detached`], so its contents after a failed
+    /// decryption are whatever that method leaves in the buffer. Several
+    /// segments are copied into temporary buffers, which are zeroized
+    /// afterwards, and are left unmodified if decryption fails. They are only
+    /// supported with the `alloc` feature: without it an error is returned.
+    /// Implementations are encouraged to override it with one processing the
+    /// segments directly.
+    fn decrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        #[cfg(feature = "alloc")]
+        let associated_data = &*gather_aad(associated_data)?;
+        #[cfg(not(feature = "alloc"))]
+        let associated_data = gather_aad(associated_data)?;
+        match message {
+            [] => self.decrypt_inout_detached(nonce, associated_data, (&mut [][..]).into(), tag),
+            [msg] => self.decrypt_inout_detached(nonce, associated_data, (&mut **msg).into(), tag),
+            #[cfg(feature = "alloc")]
+            _ => {
+                let mut buffer = message.concat();
+                let result = self.decrypt_inout_detached(
+                    nonce,
+                    associated_data,
+                    buffer.as_mut_slice().into(),
+                    tag,
+                );
+                if result.is_ok() {
+                    scatter(&buffer, message);
+                }
+                #[cfg(feature = "zeroize")]
+                zeroize::Zeroize::zeroize(buffer.as_mut_slice());
+                #[cfg(not(feature = "zeroize"))]
+                buffer.as_mut_slice().fill(0);
+                result
+            }
+            #[cfg(not(feature = "alloc"))]
+            _ => Err(Error),
+        }
+    }
 }
 
+/// Marker trait for nonce misuse-resistant AEAD algorithms.
+///
+/// Encrypting several messages with the same key and nonce using such an
+/// algorithm only reveals whether the same message and associated data were
+/// encrypted more than once. With other algorithms, nonce reuse typically
+/// breaks confidentiality of the affected messages and may allow forgeries.
+///
+/// Nonces should still be unique whenever possible: this trait only bounds
+/// the damage done by accidental reuse, e.g. after a failure of the random
+/// number generator.
+pub trait NonceMisuseResistant: AeadCore {}
+
 /// Legacy in-place stateless AEAD trait.
 ///
 /// NOTE: deprecated! Please migrate to [`AeadInOut`].
@@ -523,6 +668,36 @@ impl<const N: usize> Buffer for heapless::Vec<u8, N> {
     }
 }
 
+/// Concatenate associated data segments, avoiding a copy if there is only one.
+#[cfg(feature = "alloc")]
+fn gather_aad<'a>(segments: &[&'a [u8]]) -> Result<alloc::borrow::Cow<'a, [u8]>> {
+    Ok(match segments {
+        [segment] => alloc::borrow::Cow::Borrowed(segment),
+        _ => alloc::borrow::Cow::Owned(segments.concat()),
+    })
+}
+
+/// Get the associated data if there is at most one segment, as concatenating
+/// several of them requires `alloc`.
+#[cfg(not(feature = "alloc"))]
+fn gather_aad<'a>(segments: &[&'a [u8]]) -> Result<&'a [u8]> {
+    match segments {
+        [] => Ok(&[]),
+        [segment] => Ok(segment),
+        _ => Err(Error),
+    }
+}
+
+/// Copy the contents of `buffer` back into the given segments.
+#[cfg(feature = "alloc")]
+fn scatter(mut buffer: &[u8], segments: &mut [&mut [u8]]) {
+    for segment in segments {
+        let (head, tail) = buffer.split_at(segment.len());
+        segment.copy_from_slice(head);
+        buffer = tail;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
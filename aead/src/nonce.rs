@@ -0,0 +1,567 @@
+//! Nonce sequences.
+//!
+//! A [`NonceSequence`] produces the nonces used to encrypt successive messages
+//! under the same key, and returns an error instead of repeating a nonce once
+//! it is exhausted. [`SealingKey`] ties a sequence to an AEAD so that callers
+//! never have to supply nonces themselves.
+//!
+//! The following sequences are provided:
+//!
+//! - [`CounterBE`] and [`CounterLE`]: counters encoded over the whole nonce in
+//!   big and little endian byte order respectively.
+//! - [`PrefixedCounter`]: a fixed (e.g. random) prefix followed by a 32-bit big
+//!   endian counter, which allows multiple senders to use the same key as long
+//!   as they use distinct prefixes.
+//! - [`Limited`]: a wrapper which refuses to produce more than a configured
+//!   number of nonces, e.g. to enforce the invocation limit of an algorithm.
+//!
+//! Nonce reuse in tests and debug builds can be detected by wrapping an AEAD
+//! in a `NonceReuseGuard` (requires the `alloc` feature).
+
+use crate::{AeadInOut, Buffer, Error, Nonce, Result, Tag};
+use core::{fmt, ops::Sub};
+use crypto_common::array::{Array, ArraySize, typenum::U4};
+use inout::InOutBuf;
+
+#[cfg(feature = "rand_core")]
+use crate::rand_core::{CryptoRng, TryCryptoRng};
+#[cfg(feature = "alloc")]
+use {
+    crate::{Key, KeyInit, KeySizeUser, Payload, TagPosition},
+    alloc::{collections::BTreeSet, vec::Vec},
+    crypto_common::array::typenum::Unsigned,
+};
+
+/// Sequence of unique nonces.
+pub trait NonceSequence<N: ArraySize> {
+    /// Return the next nonce of the sequence.
+    ///
+    /// Returns [`Error`] once the sequence is exhausted. Implementations must
+    /// never return the same nonce twice.
+    fn advance(&mut self) -> Result<Array<u8, N>>;
+}
+
+/// Counter encoded over the whole nonce in big endian byte order, starting
+/// from zero.
+#[derive(Clone, Debug)]
+pub struct CounterBE<N: ArraySize> {
+    next: Option<Array<u8, N>>,
+}
+
+impl<N: ArraySize> CounterBE<N> {
+    /// Create a new counter starting from zero.
+    pub fn new() -> Self {
+        Self {
+            next: Some(Array::default()),
+        }
+    }
+}
+
+impl<N: ArraySize> Default for CounterBE<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: ArraySize> NonceSequence<N> for CounterBE<N> {
+    fn advance(&mut self) -> Result<Array<u8, N>> {
+        let mut next = self.next.take().ok_or(Error)?;
+        let nonce = next.clone();
+        if increment(next.iter_mut().rev()) {
+            self.next = Some(next);
+        }
+        Ok(nonce)
+    }
+}
+
+/// Counter encoded over the whole nonce in little endian byte order, starting
+/// from zero.
+#[derive(Clone, Debug)]
+pub struct CounterLE<N: ArraySize> {
+    next: Option<Array<u8, N>>,
+}
+
+impl<N: ArraySize> CounterLE<N> {
+    /// Create a new counter starting from zero.
+    pub fn new() -> Self {
+        Self {
+            next: Some(Array::default()),
+        }
+    }
+}
+
+impl<N: ArraySize> Default for CounterLE<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: ArraySize> NonceSequence<N> for CounterLE<N> {
+    fn advance(&mut self) -> Result<Array<u8, N>> {
+        let mut next = self.next.take().ok_or(Error)?;
+        let nonce = next.clone();
+        if increment(next.iter_mut()) {
+            self.next = Some(next);
+        }
+        Ok(nonce)
+    }
+}
+
+/// Fixed prefix followed by a 32-bit big endian counter starting from zero.
+///
+/// The sequence is exhausted after 2^32 nonces.
+#[derive(Clone, Debug)]
+pub struct PrefixedCounter<N>
+where
+    N: ArraySize + Sub<U4>,
+    <N as Sub<U4>>::Output: ArraySize,
+{
+    prefix: Array<u8, <N as Sub<U4>>::Output>,
+    counter: Option<u32>,
+}
+
+impl<N> PrefixedCounter<N>
+where
+    N: ArraySize + Sub<U4>,
+    <N as Sub<U4>>::Output: ArraySize,
+{
+    /// Create a new sequence with the given prefix.
+    ///
+    /// The prefix must be unique for every sequence used with the same key.
+    pub fn new(prefix: Array<u8, <N as Sub<U4>>::Output>) -> Self {
+        Self {
+            prefix,
+            counter: Some(0),
+        }
+    }
+
+    /// Create a new sequence with a random prefix generated using the
+    /// specified [`CryptoRng`].
+    ///
+    /// See [`AeadCore::generate_nonce_with_rng`][crate::AeadCore::generate_nonce_with_rng]
+    /// documentation for the limits of random nonces, which also apply to
+    /// random prefixes.
+    #[cfg(feature = "rand_core")]
+    pub fn generate_with_rng<R: CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut prefix = Array::default();
+        rng.fill_bytes(&mut prefix);
+        Self::new(prefix)
+    }
+
+    /// Create a new sequence with a random prefix generated using the
+    /// specified [`TryCryptoRng`].
+    #[cfg(feature = "rand_core")]
+    pub fn try_generate_with_rng<R: TryCryptoRng + ?Sized>(
+        rng: &mut R,
+    ) -> core::result::Result<Self, R::Error> {
+        let mut prefix = Array::default();
+        rng.try_fill_bytes(&mut prefix)?;
+        Ok(Self::new(prefix))
+    }
+
+    /// Nonce prefix.
+    pub fn prefix(&self) -> &Array<u8, <N as Sub<U4>>::Output> {
+        &self.prefix
+    }
+}
+
+impl<N> NonceSequence<N> for PrefixedCounter<N>
+where
+    N: ArraySize + Sub<U4>,
+    <N as Sub<U4>>::Output: ArraySize,
+{
+    fn advance(&mut self) -> Result<Array<u8, N>> {
+        let counter = self.counter.ok_or(Error)?;
+        self.counter = counter.checked_add(1);
+
+        let mut nonce = Array::<u8, N>::default();
+        let (prefix, tail) = nonce.split_at_mut(self.prefix.len());
+        prefix.copy_from_slice(&self.prefix);
+        tail.copy_from_slice(&counter.to_be_bytes());
+        Ok(nonce)
+    }
+}
+
+/// Wrapper which limits the number of nonces produced by a sequence.
+#[derive(Clone, Debug)]
+pub struct Limited<S> {
+    inner: S,
+    remaining: u64,
+}
+
+impl<S> Limited<S> {
+    /// Limit `inner` to produce at most `limit` nonces.
+    pub fn new(inner: S, limit: u64) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+        }
+    }
+
+    /// Number of nonces which can still be produced.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+impl<N: ArraySize, S: NonceSequence<N>> NonceSequence<N> for Limited<S> {
+    fn advance(&mut self) -> Result<Array<u8, N>> {
+        let remaining = self.remaining.checked_sub(1).ok_or(Error)?;
+        let nonce = self.inner.advance()?;
+        self.remaining = remaining;
+        Ok(nonce)
+    }
+}
+
+/// AEAD key which encrypts messages using nonces drawn from a
+/// [`NonceSequence`].
+///
+/// Callers can't supply nonces for encryption, preventing accidental nonce
+/// reuse. The nonce used for each message is returned alongside the
+/// ciphertext and must be transmitted to the recipient.
+pub struct SealingKey<A, N> {
+    aead: A,
+    nonces: N,
+}
+
+impl<A, N> SealingKey<A, N>
+where
+    A: AeadInOut,
+    N: NonceSequence<A::NonceSize>,
+{
+    /// Create a new sealing key from the given AEAD and nonce sequence.
+    pub fn new(aead: A, nonces: N) -> Self {
+        Self { aead, nonces }
+    }
+
+    /// Encrypt the data in the provided [`InOutBuf`] under the next nonce,
+    /// returning the nonce and the authentication tag.
+    pub fn seal_inout_detached(
+        &mut self,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<(Nonce<A>, Tag<A>)> {
+        let nonce = self.nonces.advance()?;
+        let tag = self
+            .aead
+            .encrypt_inout_detached(&nonce, associated_data, buffer)?;
+        Ok((nonce, tag))
+    }
+
+    /// Encrypt the given buffer in-place under the next nonce, returning the
+    /// nonce.
+    pub fn seal_in_place(
+        &mut self,
+        associated_data: &[u8],
+        buffer: &mut dyn Buffer,
+    ) -> Result<Nonce<A>> {
+        let nonce = self.nonces.advance()?;
+        self.aead
+            .encrypt_in_place(&nonce, associated_data, buffer)?;
+        Ok(nonce)
+    }
+
+    /// Encrypt the given plaintext payload under the next nonce, returning the
+    /// nonce and the resulting ciphertext as a vector of bytes.
+    #[cfg(feature = "alloc")]
+    pub fn seal<'msg, 'aad>(
+        &mut self,
+        plaintext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<(Nonce<A>, Vec<u8>)> {
+        let payload = plaintext.into();
+        let mut buffer = Vec::with_capacity(payload.msg.len() + A::TagSize::USIZE);
+        buffer.extend_from_slice(payload.msg);
+        let nonce = self.seal_in_place(payload.aad, &mut buffer)?;
+        Ok((nonce, buffer))
+    }
+
+    /// Decrypt the given buffer in-place using the nonce it was sealed with.
+    pub fn open_in_place(
+        &self,
+        nonce: &Nonce<A>,
+        associated_data: &[u8],
+        buffer: &mut dyn Buffer,
+    ) -> Result<()> {
+        self.aead.decrypt_in_place(nonce, associated_data, buffer)
+    }
+
+    /// Decrypt the given ciphertext payload using the nonce it was sealed
+    /// with, returning the plaintext as a vector of bytes.
+    #[cfg(feature = "alloc")]
+    pub fn open<'msg, 'aad>(
+        &self,
+        nonce: &Nonce<A>,
+        ciphertext: impl Into<Payload<'msg, 'aad>>,
+    ) -> Result<Vec<u8>> {
+        let payload = ciphertext.into();
+        let mut buffer = Vec::from(payload.msg);
+        self.open_in_place(nonce, payload.aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Nonce sequence used by this key.
+    pub fn nonces(&self) -> &N {
+        &self.nonces
+    }
+}
+
+impl<A, N: fmt::Debug> fmt::Debug for SealingKey<A, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealingKey")
+            .field("nonces", &self.nonces)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Default number of nonces recorded exactly by a [`NonceReuseGuard`].
+#[cfg(feature = "alloc")]
+pub const DEFAULT_GUARD_CAPACITY: usize = 1 << 16;
+
+/// Default size in bits of the Bloom filter of a [`NonceReuseGuard`].
+#[cfg(feature = "alloc")]
+pub const DEFAULT_GUARD_FILTER_BITS: usize = 1 << 24;
+
+/// Number of hash functions of the Bloom filter of a [`NonceReuseGuard`].
+#[cfg(feature = "alloc")]
+const GUARD_FILTER_HASHES: u64 = 7;
+
+/// AEAD wrapper which detects nonce reuse, intended for tests and debug
+/// builds.
+///
+/// The nonces used for encryption are recorded, and encryption returns
+/// [`Error`] when one of them is used again. Since a guard holds a single key,
+/// every key gets its own set of nonces. Decryption is not affected.
+///
+/// The first `capacity` nonces are recorded exactly. Further nonces are
+/// recorded in a Bloom filter of `filter_bits` bits, which bounds the memory
+/// use of long-running tests. The filter has no false negatives, but it may
+/// report a fresh nonce as used, in which case encryption fails spuriously.
+/// The probability of this grows with the number of nonces beyond `capacity`.
+/// Without a filter, i.e. with `filter_bits == 0`, encryption fails with
+/// [`Error`] once `capacity` nonces are recorded.
+///
+/// With the `std` feature the recorded nonces are kept behind a mutex, so
+/// the guard is [`Sync`] if the wrapped AEAD is. Otherwise they are kept in a
+/// [`RefCell`](core::cell::RefCell) and the guard is not [`Sync`].
+#[cfg(feature = "alloc")]
+pub struct NonceReuseGuard<A: AeadInOut> {
+    aead: A,
+    used: GuardLock<UsedNonces<Nonce<A>>>,
+}
+
+#[cfg(all(feature = "alloc", feature = "std"))]
+type GuardLock<T> = std::sync::Mutex<T>;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+type GuardLock<T> = core::cell::RefCell<T>;
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut> NonceReuseGuard<A> {
+    /// Wrap the given AEAD, recording up to [`DEFAULT_GUARD_CAPACITY`]
+    /// nonces exactly and further ones in a Bloom filter of
+    /// [`DEFAULT_GUARD_FILTER_BITS`] bits.
+    pub fn new(aead: A) -> Self {
+        Self::with_capacity(aead, DEFAULT_GUARD_CAPACITY, DEFAULT_GUARD_FILTER_BITS)
+    }
+
+    /// Wrap the given AEAD, recording up to `capacity` nonces exactly and
+    /// further ones in a Bloom filter of `filter_bits` bits.
+    ///
+    /// The filter is only allocated once `capacity` is exceeded.
+    pub fn with_capacity(aead: A, capacity: usize, filter_bits: usize) -> Self {
+        Self {
+            aead,
+            used: GuardLock::new(UsedNonces {
+                exact: BTreeSet::new(),
+                filter: Vec::new(),
+                capacity,
+                filter_words: filter_bits.div_ceil(64),
+                count: 0,
+            }),
+        }
+    }
+
+    /// Wrapped AEAD.
+    pub fn inner(&self) -> &A {
+        &self.aead
+    }
+
+    /// Whether the given nonce was already used for encryption.
+    ///
+    /// May return `true` for unused nonces once more than `capacity` nonces
+    /// are recorded.
+    pub fn is_used(&self, nonce: &Nonce<A>) -> bool {
+        self.used().contains(nonce)
+    }
+
+    /// Number of nonces used for encryption so far.
+    pub fn used_count(&self) -> usize {
+        self.used().count
+    }
+
+    /// Record the given nonce, returning [`Error`] if it was (possibly)
+    /// already used or if it can't be recorded.
+    fn record(&self, nonce: &Nonce<A>) -> Result<()> {
+        self.used().insert(nonce)
+    }
+
+    #[cfg(feature = "std")]
+    fn used(&self) -> std::sync::MutexGuard<'_, UsedNonces<Nonce<A>>> {
+        self.used
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn used(&self) -> core::cell::RefMut<'_, UsedNonces<Nonce<A>>> {
+        self.used.borrow_mut()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut + KeySizeUser> KeySizeUser for NonceReuseGuard<A> {
+    type KeySize = A::KeySize;
+}
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut + KeyInit> KeyInit for NonceReuseGuard<A> {
+    fn new(key: &Key<Self>) -> Self {
+        Self::new(A::new(key))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut> crate::AeadCore for NonceReuseGuard<A> {
+    type NonceSize = A::NonceSize;
+    type TagSize = A::TagSize;
+    const TAG_POSITION: TagPosition = A::TAG_POSITION;
+}
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut> AeadInOut for NonceReuseGuard<A> {
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        self.record(nonce)?;
+        self.aead
+            .encrypt_inout_detached(nonce, associated_data, buffer)
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        self.aead
+            .decrypt_inout_detached(nonce, associated_data, buffer, tag)
+    }
+
+    fn encrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+    ) -> Result<Tag<Self>> {
+        self.record(nonce)?;
+        self.aead
+            .encrypt_vectored_detached(nonce, associated_data, message)
+    }
+
+    fn decrypt_vectored_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[&[u8]],
+        message: &mut [&mut [u8]],
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        self.aead
+            .decrypt_vectored_detached(nonce, associated_data, message, tag)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<A: AeadInOut> fmt::Debug for NonceReuseGuard<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let used = self.used();
+        f.debug_struct("NonceReuseGuard")
+            .field("used_count", &used.count)
+            .field("capacity", &used.capacity)
+            .field("filter_bits", &(64 * used.filter_words))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Nonces recorded by a [`NonceReuseGuard`]: the first `capacity` ones in an
+/// exact set, further ones in a Bloom filter.
+#[cfg(feature = "alloc")]
+struct UsedNonces<N> {
+    exact: BTreeSet<N>,
+    filter: Vec<u64>,
+    capacity: usize,
+    filter_words: usize,
+    count: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<N: AsRef<[u8]> + Clone + Ord> UsedNonces<N> {
+    fn contains(&self, nonce: &N) -> bool {
+        self.exact.contains(nonce)
+            || (!self.filter.is_empty()
+                && Self::filter_bits(nonce, self.filter_words)
+                    .all(|(word, bit)| self.filter[word] & bit != 0))
+    }
+
+    fn insert(&mut self, nonce: &N) -> Result<()> {
+        if self.contains(nonce) {
+            return Err(Error);
+        }
+        if self.exact.len() < self.capacity {
+            self.exact.insert(nonce.clone());
+        } else if self.filter_words != 0 {
+            if self.filter.is_empty() {
+                self.filter = alloc::vec![0; self.filter_words];
+            }
+            for (word, bit) in Self::filter_bits(nonce, self.filter_words) {
+                self.filter[word] |= bit;
+            }
+        } else {
+            return Err(Error);
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Word indexes and masks of the filter bits of `nonce`, derived from two
+    /// FNV-1a hashes using double hashing.
+    fn filter_bits(nonce: &N, filter_words: usize) -> impl Iterator<Item = (usize, u64)> {
+        let fnv = |basis: u64| {
+            nonce.as_ref().iter().fold(basis, |hash, &byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+        };
+        let h1 = fnv(0xcbf2_9ce4_8422_2325);
+        let h2 = fnv(0x6c62_272e_07bb_0142) | 1;
+        let bits = 64 * filter_words as u64;
+        (0..GUARD_FILTER_HASHES).map(move |i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % bits;
+            ((bit / 64) as usize, 1 << (bit % 64))
+        })
+    }
+}
+
+/// Increment a counter whose bytes are yielded from the least significant
+/// one, returning `false` on overflow.
+fn increment<'a>(bytes: impl Iterator<Item = &'a mut u8>) -> bool {
+    for byte in bytes {
+        let (value, carry) = byte.overflowing_add(1);
+        *byte = value;
+        if !carry {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,286 @@
+//! Generic SIV (Synthetic Initialization Vector) AEAD construction.
+//!
+//! [`Siv`] implements SIV mode as specified by [RFC 5297] on top of any MAC
+//! with a 128-bit output, such as CMAC or PMAC, and any CTR mode stream
+//! cipher with a 128-bit IV:
+//!
+//! 1. The synthetic IV `V` is computed by S2V ("string to vector") from the
+//!    associated data components and the plaintext using the MAC.
+//! 2. The plaintext is encrypted in CTR mode with `V` as the initial counter
+//!    block, after clearing its 32nd and 64th rightmost bits.
+//! 3. `V` is used as the tag and prepended to the ciphertext.
+//!
+//! Since the IV depends on the whole input, SIV is deterministic and
+//! [`NonceMisuseResistant`]. It accepts a vector of associated data
+//! components ("headers"), of which the nonce is the last one when used
+//! through [`AeadInOut`]. The `*_with_headers` methods can be used without a
+//! nonce for deterministic authenticated encryption, e.g. key wrapping.
+//!
+//! For example AES-CMAC-SIV with a 256-bit key corresponds to:
+//!
+//! ```ignore
+//! type Aes128Siv = Siv<ctr::Ctr128BE<Aes128>, Cmac<Aes128>>;
+//! ```
+//!
+//! [RFC 5297]: https://www.rfc-editor.org/rfc/rfc5297
+
+use crate::{
+    AeadCore, AeadInOut, Error, Key, KeyInit, KeySizeUser, Nonce, NonceMisuseResistant, Result,
+    Tag, TagPosition,
+};
+use cipher::{KeyIvInit, StreamCipher};
+use core::{fmt, marker::PhantomData, ops::Add};
+use crypto_common::array::{
+    Array, ArraySize,
+    typenum::{Sum, U16, Unsigned},
+};
+use digest::Mac;
+use inout::InOutBuf;
+use subtle::ConstantTimeEq;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Maximum number of associated data components accepted by S2V.
+///
+/// S2V takes at most 127 components, the last of which is the plaintext.
+pub const MAX_HEADERS: usize = 126;
+
+/// SIV AEAD composed of the CTR mode cipher `C` and the MAC `M`, with the
+/// nonce size `N`.
+///
+/// The key is the concatenation of the MAC key and the encryption key, in
+/// that order.
+pub struct Siv<C: KeySizeUser, M, N = U16> {
+    mac: M,
+    enc_key: Key<C>,
+    _nonce: PhantomData<N>,
+}
+
+impl<C, M, N> Siv<C, M, N>
+where
+    C: KeyIvInit<IvSize = U16> + StreamCipher,
+    M: Mac<OutputSize = U16> + Clone,
+{
+    /// Encrypt the data in the provided [`InOutBuf`] authenticating the given
+    /// associated data components, returning the synthetic IV.
+    ///
+    /// Returns [`Error`] if more than [`MAX_HEADERS`] components are given.
+    pub fn encrypt_inout_detached_with_headers(
+        &self,
+        headers: &[impl AsRef<[u8]>],
+        mut buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Array<u8, U16>> {
+        let siv = self.s2v(headers, buffer.get_in())?;
+        self.apply_keystream(&siv, buffer.reborrow())?;
+        Ok(siv)
+    }
+
+    /// Decrypt the data in the provided [`InOutBuf`] and verify it against
+    /// the synthetic IV and the given associated data components.
+    ///
+    /// The output buffer is zeroed if verification fails.
+    pub fn decrypt_inout_detached_with_headers(
+        &self,
+        headers: &[impl AsRef<[u8]>],
+        mut buffer: InOutBuf<'_, '_, u8>,
+        siv: &Array<u8, U16>,
+    ) -> Result<()> {
+        if headers.len() > MAX_HEADERS {
+            return Err(Error);
+        }
+        self.apply_keystream(siv, buffer.reborrow())?;
+        let expected = self.s2v(headers, buffer.get_out())?;
+        if !bool::from(expected.ct_eq(siv)) {
+            buffer.get_out().fill(0);
+            return Err(Error);
+        }
+        Ok(())
+    }
+
+    /// Encrypt the given plaintext authenticating the given associated data
+    /// components, returning the synthetic IV followed by the ciphertext.
+    #[cfg(feature = "alloc")]
+    pub fn encrypt_with_headers(
+        &self,
+        headers: &[impl AsRef<[u8]>],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(U16::USIZE + plaintext.len());
+        buffer.resize(U16::USIZE, 0);
+        buffer.extend_from_slice(plaintext);
+        let (siv, msg) = buffer.split_at_mut(U16::USIZE);
+        let tag = self.encrypt_inout_detached_with_headers(headers, msg.into())?;
+        siv.copy_from_slice(&tag);
+        Ok(buffer)
+    }
+
+    /// Decrypt the given synthetic IV and ciphertext, verifying them against
+    /// the given associated data components, and return the plaintext.
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_with_headers(
+        &self,
+        headers: &[impl AsRef<[u8]>],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>> {
+        if ciphertext.len() < U16::USIZE {
+            return Err(Error);
+        }
+        let (siv, msg) = ciphertext.split_at(U16::USIZE);
+        let siv = Array::try_from(siv).expect("SIV length mismatch");
+        let mut buffer = Vec::from(msg);
+        self.decrypt_inout_detached_with_headers(headers, buffer.as_mut_slice().into(), &siv)?;
+        Ok(buffer)
+    }
+
+    /// S2V as specified by [RFC 5297 § 2.4].
+    ///
+    /// [RFC 5297 § 2.4]: https://www.rfc-editor.org/rfc/rfc5297#section-2.4
+    fn s2v(&self, headers: &[impl AsRef<[u8]>], plaintext: &[u8]) -> Result<Array<u8, U16>> {
+        if headers.len() > MAX_HEADERS {
+            return Err(Error);
+        }
+
+        let mut d = self
+            .mac
+            .clone()
+            .chain_update([0u8; 16])
+            .finalize()
+            .into_bytes();
+        for header in headers {
+            let mac = self
+                .mac
+                .clone()
+                .chain_update(header)
+                .finalize()
+                .into_bytes();
+            dbl(&mut d);
+            xor(&mut d, &mac);
+        }
+
+        let mut mac = self.mac.clone();
+        if let Some(head_len) = plaintext.len().checked_sub(U16::USIZE) {
+            let (head, tail) = plaintext.split_at(head_len);
+            mac.update(head);
+            xor(&mut d, tail);
+        } else {
+            dbl(&mut d);
+            xor(&mut d, plaintext);
+            d[plaintext.len()] ^= 0x80;
+        }
+        mac.update(&d);
+        Ok(mac.finalize().into_bytes())
+    }
+
+    fn apply_keystream(&self, siv: &Array<u8, U16>, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        let mut iv = *siv;
+        iv[8] &= 0x7f;
+        iv[12] &= 0x7f;
+        C::new(&self.enc_key, &iv)
+            .try_apply_keystream_inout(buffer)
+            .map_err(|_| Error)
+    }
+}
+
+impl<C, M, N> KeySizeUser for Siv<C, M, N>
+where
+    C: KeySizeUser,
+    M: KeySizeUser,
+    M::KeySize: Add<C::KeySize>,
+    Sum<M::KeySize, C::KeySize>: ArraySize,
+{
+    type KeySize = Sum<M::KeySize, C::KeySize>;
+}
+
+impl<C, M, N> KeyInit for Siv<C, M, N>
+where
+    C: KeySizeUser,
+    M: KeyInit,
+    M::KeySize: Add<C::KeySize>,
+    Sum<M::KeySize, C::KeySize>: ArraySize,
+{
+    fn new(key: &Key<Self>) -> Self {
+        let (mac_key, enc_key) = key.split_at(M::KeySize::USIZE);
+        Self {
+            mac: M::new_from_slice(mac_key).expect("key size mismatch"),
+            enc_key: Array::try_from(enc_key).expect("key size mismatch"),
+            _nonce: PhantomData,
+        }
+    }
+}
+
+impl<C: KeySizeUser, M, N: ArraySize> AeadCore for Siv<C, M, N> {
+    type NonceSize = N;
+    type TagSize = U16;
+    const TAG_POSITION: TagPosition = TagPosition::Prefix;
+}
+
+impl<C, M, N> AeadInOut for Siv<C, M, N>
+where
+    C: KeyIvInit<IvSize = U16> + StreamCipher,
+    M: Mac<OutputSize = U16> + Clone,
+    N: ArraySize,
+{
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        self.encrypt_inout_detached_with_headers(&[associated_data, nonce.as_slice()], buffer)
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        self.decrypt_inout_detached_with_headers(&[associated_data, nonce.as_slice()], buffer, tag)
+    }
+}
+
+impl<C: KeySizeUser, M, N: ArraySize> NonceMisuseResistant for Siv<C, M, N> {}
+
+impl<C: KeySizeUser, M: Clone, N> Clone for Siv<C, M, N> {
+    fn clone(&self) -> Self {
+        Self {
+            mac: self.mac.clone(),
+            enc_key: self.enc_key.clone(),
+            _nonce: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<C: KeySizeUser, M, N> Drop for Siv<C, M, N> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self.enc_key.as_mut_slice());
+    }
+}
+
+/// Requires the MAC to zeroize its own key on drop.
+#[cfg(feature = "zeroize")]
+impl<C: KeySizeUser, M: zeroize::ZeroizeOnDrop, N> zeroize::ZeroizeOnDrop for Siv<C, M, N> {}
+
+impl<C: KeySizeUser, M, N> fmt::Debug for Siv<C, M, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Siv").finish_non_exhaustive()
+    }
+}
+
+/// Doubling in GF(2^128) as specified by [RFC 5297 § 2.3].
+///
+/// [RFC 5297 § 2.3]: https://www.rfc-editor.org/rfc/rfc5297#section-2.3
+fn dbl(block: &mut Array<u8, U16>) {
+    let value = u128::from_be_bytes(block.0);
+    let doubled = (value << 1) ^ ((value >> 127) * 0x87);
+    *block = doubled.to_be_bytes().into();
+}
+
+fn xor(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
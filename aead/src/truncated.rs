@@ -0,0 +1,150 @@
+//! Truncated authentication tags.
+//!
+//! Some protocols use AEADs with tags shorter than their native size, e.g.
+//! AES-GCM with 96, 104 or 112-bit tags, or CCM-style variable tag lengths.
+//! [`Truncated`] wraps an AEAD and only keeps the first `N` bytes of its tags,
+//! which are compared in constant time during decryption.
+//!
+//! Verifying a truncated tag requires the full expected tag, so the wrapped
+//! AEAD must implement [`DecryptUnverified`].
+//!
+//! # ⚠️ Security Warning
+//!
+//! Shorter tags make forgeries proportionally easier: an attacker succeeds
+//! with probability about `2^-(8 * N)` per attempt. Only truncate tags when
+//! required by a protocol, and follow its limits on the number of failed
+//! decryptions.
+
+use crate::{
+    AeadCore, AeadInOut, Error, Key, KeyInit, KeySizeUser, Nonce, Result, Tag, TagPosition,
+};
+use core::{fmt, marker::PhantomData};
+use crypto_common::array::{
+    Array, ArraySize,
+    typenum::{IsLessOrEqual, True},
+};
+use inout::InOutBuf;
+use subtle::ConstantTimeEq;
+
+/// AEAD algorithms which can decrypt a message without verifying it,
+/// returning the expected authentication tag instead.
+///
+/// # ⚠️ Security Warning
+///
+/// This is a low-level API: the decrypted data must be discarded unless the
+/// returned tag was successfully compared with the received one in constant
+/// time.
+pub trait DecryptUnverified: AeadInOut {
+    /// Decrypt the data in the provided [`InOutBuf`] without verifying it,
+    /// returning the expected authentication tag.
+    fn decrypt_inout_unverified(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>>;
+}
+
+/// AEAD wrapping `A` with tags truncated to `N` bytes.
+///
+/// The tag position is the same as the one of `A`.
+pub struct Truncated<A, N> {
+    aead: A,
+    _tag_size: PhantomData<N>,
+}
+
+impl<A, N> Truncated<A, N>
+where
+    A: AeadCore,
+    N: ArraySize + IsLessOrEqual<A::TagSize, Output = True>,
+{
+    /// Wrap the given AEAD.
+    pub fn new(aead: A) -> Self {
+        Self {
+            aead,
+            _tag_size: PhantomData,
+        }
+    }
+
+    /// Wrapped AEAD.
+    pub fn inner(&self) -> &A {
+        &self.aead
+    }
+
+    fn truncate(tag: &Tag<A>) -> Array<u8, N> {
+        Array::try_from(&tag[..N::USIZE]).expect("tag size is checked")
+    }
+}
+
+impl<A: KeySizeUser, N> KeySizeUser for Truncated<A, N> {
+    type KeySize = A::KeySize;
+}
+
+impl<A: KeyInit, N> KeyInit for Truncated<A, N> {
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            aead: A::new(key),
+            _tag_size: PhantomData,
+        }
+    }
+}
+
+impl<A, N> AeadCore for Truncated<A, N>
+where
+    A: AeadCore,
+    N: ArraySize + IsLessOrEqual<A::TagSize, Output = True>,
+{
+    type NonceSize = A::NonceSize;
+    type TagSize = N;
+    const TAG_POSITION: TagPosition = A::TAG_POSITION;
+}
+
+impl<A, N> AeadInOut for Truncated<A, N>
+where
+    A: DecryptUnverified,
+    N: ArraySize + IsLessOrEqual<A::TagSize, Output = True>,
+{
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        let tag = self
+            .aead
+            .encrypt_inout_detached(nonce, associated_data, buffer)?;
+        Ok(Self::truncate(&tag))
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        mut buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        let expected_tag =
+            self.aead
+                .decrypt_inout_unverified(nonce, associated_data, buffer.reborrow())?;
+        if !bool::from(Self::truncate(&expected_tag).ct_eq(tag)) {
+            buffer.get_out().fill(0);
+            return Err(Error);
+        }
+        Ok(())
+    }
+}
+
+impl<A: Clone, N> Clone for Truncated<A, N> {
+    fn clone(&self) -> Self {
+        Self {
+            aead: self.aead.clone(),
+            _tag_size: PhantomData,
+        }
+    }
+}
+
+impl<A, N> fmt::Debug for Truncated<A, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Truncated").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,278 @@
+//! Generic stream cipher + universal hash AEAD construction.
+//!
+//! [`StreamUhf`] implements the construction of ChaCha20Poly1305 on top of any
+//! seekable stream cipher core and universal hash function (UHF):
+//!
+//! 1. The first keystream bytes for the given nonce are used as a one-time UHF
+//!    key, followed by a tag mask if the [`UhfFormat`] requires one.
+//! 2. The message is encrypted using the keystream starting at the next block.
+//! 3. The UHF is computed over the zero-padded associated data, the
+//!    zero-padded ciphertext and their lengths encoded as specified by the
+//!    [`UhfFormat`].
+//! 4. The UHF output, masked if required, is used as the tag.
+//!
+//! For example ChaCha20Poly1305 as specified by [RFC 8439] corresponds to the
+//! following, where `ChaCha20Core` is the [`StreamCipherCore`] of ChaCha20
+//! with a 96-bit nonce:
+//!
+//! ```ignore
+//! type ChaCha20Poly1305 = StreamUhf<ChaCha20Core, Poly1305, Rfc8439>;
+//! ```
+//!
+//! Only this shape is supported: the tag is always appended to the ciphertext,
+//! the UHF key is always taken from the start of the keystream and the
+//! associated data is always authenticated. AEADs which deviate from it, such
+//! as XSalsa20Poly1305 (keystream offset within the first block, prefix tag,
+//! no associated data or length block) or GCM (UHF key and tag mask derived
+//! from the block cipher rather than the keystream), can't be expressed with
+//! [`StreamUhf`].
+//!
+//! [RFC 8439]: https://www.rfc-editor.org/rfc/rfc8439#section-2.8
+
+use crate::{
+    AeadCore, AeadInOut, Error, Key, KeyInit, KeySizeUser, Nonce, Result, Tag, TagPosition,
+};
+use cipher::{KeyIvInit, StreamCipherCore, StreamCipherSeekCore};
+use core::{fmt, marker::PhantomData};
+use crypto_common::{Block, array::typenum::Unsigned};
+use inout::InOutBuf;
+use subtle::ConstantTimeEq;
+use universal_hash::UniversalHash;
+
+#[cfg(feature = "truncated")]
+use crate::truncated::DecryptUnverified;
+
+/// Tag masking and length encoding used by [`StreamUhf`].
+pub trait UhfFormat {
+    /// Whether the UHF output is XORed with the keystream bytes following the
+    /// one-time UHF key.
+    ///
+    /// UHFs such as Poly1305 mask their output themselves using a part of
+    /// their key, while UHFs such as GHASH and POLYVAL need a separate mask.
+    const MASK_TAG: bool;
+
+    /// Feed the lengths in bytes of the associated data and ciphertext into
+    /// the UHF.
+    fn update_lengths(uhf: &mut impl UniversalHash, aad_len: u64, msg_len: u64);
+}
+
+/// Format of [RFC 8439 § 2.8]: unmasked tag and lengths in bytes encoded as
+/// 64-bit little endian integers.
+///
+/// [RFC 8439 § 2.8]: https://www.rfc-editor.org/rfc/rfc8439#section-2.8
+#[derive(Clone, Copy, Debug)]
+pub struct Rfc8439;
+
+impl UhfFormat for Rfc8439 {
+    const MASK_TAG: bool = false;
+
+    fn update_lengths(uhf: &mut impl UniversalHash, aad_len: u64, msg_len: u64) {
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&aad_len.to_le_bytes());
+        lengths[8..].copy_from_slice(&msg_len.to_le_bytes());
+        uhf.update_padded(&lengths);
+    }
+}
+
+/// Format for UHFs without built-in masking such as GHASH and POLYVAL: tag
+/// masked with keystream and lengths in bits encoded as 64-bit big endian
+/// integers.
+///
+/// Note that this is *not* GCM, which derives the UHF key and tag mask from
+/// the block cipher directly; see the [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct MaskedBitLengths;
+
+impl UhfFormat for MaskedBitLengths {
+    const MASK_TAG: bool = true;
+
+    fn update_lengths(uhf: &mut impl UniversalHash, aad_len: u64, msg_len: u64) {
+        let aad_bits = aad_len.checked_mul(8).expect("associated data is too long");
+        let msg_bits = msg_len.checked_mul(8).expect("message is too long");
+        let mut lengths = [0u8; 16];
+        lengths[..8].copy_from_slice(&aad_bits.to_be_bytes());
+        lengths[8..].copy_from_slice(&msg_bits.to_be_bytes());
+        uhf.update_padded(&lengths);
+    }
+}
+
+/// AEAD composed of the stream cipher core `C` and the universal hash
+/// function `M`, with the tag masking and length encoding `F`.
+///
+/// The key and nonce are the key and IV of the stream cipher, and the tag
+/// has the block size of the UHF.
+pub struct StreamUhf<C: KeySizeUser, M, F = Rfc8439> {
+    key: Key<C>,
+    _pd: PhantomData<(M, F)>,
+}
+
+impl<C, M, F> StreamUhf<C, M, F>
+where
+    C: StreamCipherCore + StreamCipherSeekCore + KeyIvInit,
+    M: UniversalHash + KeyInit,
+    F: UhfFormat,
+{
+    /// Initialize the stream cipher for the given nonce and derive the
+    /// one-time UHF and tag mask from its first keystream blocks.
+    ///
+    /// The returned cipher is positioned at the first block following the
+    /// ones used for the UHF key and tag mask.
+    fn init(&self, nonce: &Nonce<Self>) -> Result<(C, M, Block<M>)> {
+        let mut cipher = C::new(&self.key, nonce);
+        let mut uhf_key = Key::<M>::default();
+        let mut mask = Block::<M>::default();
+        let mask_len = if F::MASK_TAG { mask.len() } else { 0 };
+
+        let mut block = Block::<C>::default();
+        let mut blocks = 0usize;
+        for (i, byte) in uhf_key.iter_mut().chain(&mut mask[..mask_len]).enumerate() {
+            let pos = i % C::BlockSize::USIZE;
+            if pos == 0 {
+                cipher.write_keystream_block(&mut block);
+                blocks += 1;
+            }
+            *byte = block[pos];
+        }
+        cipher.set_block_pos(blocks.try_into().map_err(|_| Error)?);
+
+        Ok((cipher, M::new(&uhf_key), mask))
+    }
+
+    fn compute_tag(
+        mut uhf: M,
+        mask: &Block<M>,
+        associated_data: &[u8],
+        ciphertext: &[u8],
+    ) -> Tag<Self> {
+        uhf.update_padded(associated_data);
+        uhf.update_padded(ciphertext);
+        F::update_lengths(
+            &mut uhf,
+            associated_data.len() as u64,
+            ciphertext.len() as u64,
+        );
+
+        let mut tag = uhf.finalize();
+        for (t, m) in tag.iter_mut().zip(mask) {
+            *t ^= m;
+        }
+        tag
+    }
+
+    fn apply_keystream(cipher: C, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        if let Some(remaining) = cipher.remaining_blocks() {
+            if buffer.len().div_ceil(C::BlockSize::USIZE) > remaining {
+                return Err(Error);
+            }
+        }
+        cipher
+            .try_apply_keystream_partial(buffer)
+            .map_err(|_| Error)
+    }
+}
+
+impl<C: KeySizeUser, M, F> KeySizeUser for StreamUhf<C, M, F> {
+    type KeySize = C::KeySize;
+}
+
+impl<C: KeySizeUser, M, F> KeyInit for StreamUhf<C, M, F> {
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            key: key.clone(),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<C, M, F> AeadCore for StreamUhf<C, M, F>
+where
+    C: KeyIvInit,
+    M: UniversalHash,
+{
+    type NonceSize = C::IvSize;
+    type TagSize = M::BlockSize;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+impl<C, M, F> AeadInOut for StreamUhf<C, M, F>
+where
+    C: StreamCipherCore + StreamCipherSeekCore + KeyIvInit,
+    M: UniversalHash + KeyInit,
+    F: UhfFormat,
+{
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        mut buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        let (cipher, uhf, mask) = self.init(nonce)?;
+        Self::apply_keystream(cipher, buffer.reborrow())?;
+        Ok(Self::compute_tag(
+            uhf,
+            &mask,
+            associated_data,
+            buffer.get_out(),
+        ))
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<()> {
+        let (cipher, uhf, mask) = self.init(nonce)?;
+        let expected_tag = Self::compute_tag(uhf, &mask, associated_data, buffer.get_in());
+        if !bool::from(expected_tag.ct_eq(tag)) {
+            return Err(Error);
+        }
+        Self::apply_keystream(cipher, buffer)
+    }
+}
+
+#[cfg(feature = "truncated")]
+impl<C, M, F> DecryptUnverified for StreamUhf<C, M, F>
+where
+    C: StreamCipherCore + StreamCipherSeekCore + KeyIvInit,
+    M: UniversalHash + KeyInit,
+    F: UhfFormat,
+{
+    fn decrypt_inout_unverified(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        let (cipher, uhf, mask) = self.init(nonce)?;
+        let tag = Self::compute_tag(uhf, &mask, associated_data, buffer.get_in());
+        Self::apply_keystream(cipher, buffer)?;
+        Ok(tag)
+    }
+}
+
+impl<C: KeySizeUser, M, F> Clone for StreamUhf<C, M, F> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            _pd: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<C: KeySizeUser, M, F> Drop for StreamUhf<C, M, F> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self.key.as_mut_slice());
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<C: KeySizeUser, M, F> zeroize::ZeroizeOnDrop for StreamUhf<C, M, F> {}
+
+impl<C: KeySizeUser, M, F> fmt::Debug for StreamUhf<C, M, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamUhf").finish_non_exhaustive()
+    }
+}
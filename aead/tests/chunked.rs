@@ -0,0 +1,143 @@
+//! Tests of the chunked random access encryption format.
+#![cfg(all(feature = "dev", feature = "std"))]
+mod common;
+
+use aead::{
+    KeyInit,
+    chunked::{Header, Reader, Writer},
+};
+use common::PrefixDummyAead;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+const KEY: [u8; 8] = *b"chunkkey";
+
+fn aead() -> PrefixDummyAead {
+    PrefixDummyAead::new(&KEY.into())
+}
+
+fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+fn encrypt(plaintext: &[u8], nonce_prefix: [u8; 3]) -> Vec<u8> {
+    let header = Header {
+        algorithm_id: 7,
+        chunk_size: 16,
+        nonce_prefix: nonce_prefix.to_vec(),
+    };
+    let mut writer = Writer::new(aead(), header, Vec::new()).unwrap();
+    writer.write_all(plaintext).unwrap();
+    writer.finish().unwrap()
+}
+
+fn decrypt(container: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut reader = Reader::new(aead(), Cursor::new(container))?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+#[test]
+fn round_trip() {
+    for len in [0, 1, 15, 16, 17, 32, 100] {
+        let plaintext = plaintext(len);
+        let container = encrypt(&plaintext, [1, 2, 3]);
+        assert_eq!(decrypt(&container).unwrap(), plaintext, "length {len}");
+    }
+}
+
+#[test]
+fn random_access() {
+    let plaintext = plaintext(100);
+    let container = encrypt(&plaintext, [1, 2, 3]);
+    let mut reader = Reader::new(aead(), Cursor::new(container)).unwrap();
+    assert_eq!(reader.header().algorithm_id, 7);
+    assert_eq!(reader.len(), 100);
+
+    let mut buf = [0u8; 20];
+    reader.seek(SeekFrom::Start(40)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, plaintext[40..60]);
+
+    reader.seek(SeekFrom::End(-20)).unwrap();
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, plaintext[80..]);
+
+    reader.seek(SeekFrom::Current(-50)).unwrap();
+    reader.read_exact(&mut buf[..10]).unwrap();
+    assert_eq!(buf[..10], plaintext[50..60]);
+}
+
+#[test]
+fn tampering() {
+    let container = encrypt(&plaintext(100), [1, 2, 3]);
+    let header_len = 19;
+    let chunk_len = 16 + 8;
+
+    // Truncation at a chunk boundary
+    assert!(decrypt(&container[..header_len + 2 * chunk_len]).is_err());
+    // Truncation within a chunk
+    assert!(decrypt(&container[..container.len() - 1]).is_err());
+
+    // Reordering chunks
+    let mut reordered = container.clone();
+    let (first, rest) = reordered[header_len..].split_at_mut(chunk_len);
+    first.swap_with_slice(&mut rest[..chunk_len]);
+    assert!(decrypt(&reordered).is_err());
+
+    // Swapping in a chunk from another container
+    let other = encrypt(&plaintext(100), [4, 5, 6]);
+    let mut swapped = container.clone();
+    swapped[header_len..][..chunk_len].copy_from_slice(&other[header_len..][..chunk_len]);
+    assert!(decrypt(&swapped).is_err());
+
+    // Modifying the header
+    let mut modified = container.clone();
+    modified[10] ^= 1;
+    assert!(decrypt(&modified).is_err());
+}
+
+/// Writer which fails once more than `capacity` bytes are written to it.
+struct FailingWriter {
+    capacity: usize,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.capacity {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        self.capacity -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn inner_writer_failure() {
+    let header = Header {
+        algorithm_id: 7,
+        chunk_size: 16,
+        nonce_prefix: vec![1, 2, 3],
+    };
+    // Room for the header and the start of the first chunk only
+    let inner = FailingWriter { capacity: 19 + 10 };
+    let mut writer = Writer::new(aead(), header, inner).unwrap();
+    assert!(writer.write_all(&plaintext(40)).is_err());
+
+    // The writer is poisoned from now on
+    assert!(writer.write(&[0]).is_err());
+    assert!(writer.flush().is_err());
+    assert!(writer.finish().is_err());
+}
+
+#[test]
+fn oversized_chunk_size() {
+    // A forged chunk size must not be trusted before authentication
+    let mut container = encrypt(&plaintext(10), [1, 2, 3]);
+    container[11..15].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(decrypt(&container).is_err());
+}
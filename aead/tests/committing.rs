@@ -0,0 +1,64 @@
+//! Tests of the key-committing AEAD wrapper.
+#![cfg(all(feature = "dev", feature = "committing"))]
+mod common;
+
+use aead::{Aead, AeadInOut, Error, KeyInit, committing::KeyCommitting};
+use common::PostfixDummyAead;
+use sha2::Sha256;
+
+type Committing = KeyCommitting<PostfixDummyAead, Sha256>;
+
+#[test]
+fn round_trip() {
+    let cipher = Committing::new(b"commitky".into());
+    let nonce = [7; 8].into();
+    let ciphertext = cipher.encrypt(&nonce, b"message".as_slice()).unwrap();
+    // SHA-256 commitment followed by the 8-byte inner tag
+    assert_eq!(ciphertext.len(), 7 + 32 + 8);
+    assert_eq!(
+        cipher.decrypt(&nonce, ciphertext.as_slice()).unwrap(),
+        b"message"
+    );
+}
+
+#[test]
+fn rejects_wrong_key_and_nonce() {
+    let cipher = Committing::new(b"commitky".into());
+    let nonce = [7; 8].into();
+    let ciphertext = cipher.encrypt(&nonce, b"message".as_slice()).unwrap();
+
+    let other = Committing::new(b"otherkey".into());
+    assert_eq!(other.decrypt(&nonce, ciphertext.as_slice()), Err(Error));
+    assert_eq!(
+        cipher.decrypt(&[8; 8].into(), ciphertext.as_slice()),
+        Err(Error)
+    );
+
+    let mut tampered = ciphertext.clone();
+    tampered[7] ^= 1;
+    assert_eq!(cipher.decrypt(&nonce, tampered.as_slice()), Err(Error));
+}
+
+#[test]
+fn vectored_matches_contiguous() {
+    let cipher = Committing::new(b"commitky".into());
+    let nonce = [7; 8].into();
+    let mut buf = *b"message";
+    let tag = cipher
+        .encrypt_inout_detached(&nonce, b"header", buf.as_mut_slice().into())
+        .unwrap();
+
+    let (mut head, mut tail) = (*b"mes", *b"sage");
+    let mut segments = [head.as_mut_slice(), tail.as_mut_slice()];
+    let vectored_tag = cipher
+        .encrypt_vectored_detached(&nonce, &[b"head", b"er"], &mut segments)
+        .unwrap();
+    assert_eq!(vectored_tag, tag);
+    assert_eq!([head.as_slice(), &tail].concat(), buf);
+
+    let mut segments = [head.as_mut_slice(), tail.as_mut_slice()];
+    cipher
+        .decrypt_vectored_detached(&nonce, &[b"header"], &mut segments, &tag)
+        .unwrap();
+    assert_eq!((&head, &tail), (b"mes", b"sage"));
+}
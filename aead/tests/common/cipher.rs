@@ -0,0 +1,215 @@
+//! Dummy stream ciphers and block modes.
+use aead::cipher::{
+    BlockModeDecBackend, BlockModeDecClosure, BlockModeDecrypt, BlockModeEncBackend,
+    BlockModeEncClosure, BlockModeEncrypt, BlockSizeUser, InOut, InOutBuf, Iv, IvSizeUser,
+    KeyIvInit, KeySizeUser, ParBlocksSizeUser, StreamCipher, StreamCipherBackend,
+    StreamCipherClosure, StreamCipherCore, StreamCipherError, StreamCipherSeekCore,
+    array::Array,
+    consts::{U1, U8, U16},
+};
+
+/// Insecure dummy CTR mode "cipher" XORing data with the key and counter.
+pub struct DummyCtr {
+    key: u128,
+    counter: u128,
+    pos: usize,
+}
+
+impl KeySizeUser for DummyCtr {
+    type KeySize = U16;
+}
+
+impl IvSizeUser for DummyCtr {
+    type IvSize = U16;
+}
+
+impl KeyIvInit for DummyCtr {
+    fn new(key: &Array<u8, U16>, iv: &Iv<Self>) -> Self {
+        Self {
+            key: u128::from_be_bytes(key.0),
+            counter: u128::from_be_bytes(iv.0),
+            pos: 0,
+        }
+    }
+}
+
+impl StreamCipher for DummyCtr {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: InOutBuf<'_, '_, u8>,
+    ) -> Result<(), StreamCipherError> {
+        for i in 0..buf.len() {
+            let block = self.counter.wrapping_add((self.pos / 16) as u128) ^ self.key;
+            let mut byte = buf.get(i);
+            *byte.get_out() = byte.get_in() ^ block.to_be_bytes()[self.pos % 16];
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Insecure dummy stream cipher XORing data with the key and IV.
+pub struct DummyStream {
+    keystream: [u8; 8],
+    pos: usize,
+}
+
+impl KeySizeUser for DummyStream {
+    type KeySize = U8;
+}
+
+impl IvSizeUser for DummyStream {
+    type IvSize = U8;
+}
+
+impl KeyIvInit for DummyStream {
+    fn new(key: &Array<u8, U8>, iv: &Iv<Self>) -> Self {
+        Self {
+            keystream: core::array::from_fn(|i| key[i] ^ iv[i]),
+            pos: 0,
+        }
+    }
+}
+
+impl StreamCipher for DummyStream {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: InOutBuf<'_, '_, u8>,
+    ) -> Result<(), StreamCipherError> {
+        for i in 0..buf.len() {
+            let mut byte = buf.get(i);
+            *byte.get_out() = byte.get_in() ^ self.keystream[self.pos % 8];
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Insecure dummy CBC-like block mode over an XOR "block cipher".
+pub struct DummyCbc {
+    key: [u8; 8],
+    prev: [u8; 8],
+}
+
+impl KeySizeUser for DummyCbc {
+    type KeySize = U8;
+}
+
+impl IvSizeUser for DummyCbc {
+    type IvSize = U8;
+}
+
+impl KeyIvInit for DummyCbc {
+    fn new(key: &Array<u8, U8>, iv: &Iv<Self>) -> Self {
+        Self {
+            key: key.0,
+            prev: iv.0,
+        }
+    }
+}
+
+impl BlockSizeUser for DummyCbc {
+    type BlockSize = U8;
+}
+
+impl ParBlocksSizeUser for DummyCbc {
+    type ParBlocksSize = U1;
+}
+
+impl BlockModeEncBackend for DummyCbc {
+    fn encrypt_block(&mut self, mut block: InOut<'_, '_, Array<u8, U8>>) {
+        let ct: [u8; 8] = core::array::from_fn(|i| block.get_in()[i] ^ self.prev[i] ^ self.key[i]);
+        *block.get_out() = ct.into();
+        self.prev = ct;
+    }
+}
+
+impl BlockModeDecBackend for DummyCbc {
+    fn decrypt_block(&mut self, mut block: InOut<'_, '_, Array<u8, U8>>) {
+        let ct: [u8; 8] = block.get_in().0;
+        *block.get_out() = core::array::from_fn(|i| ct[i] ^ self.prev[i] ^ self.key[i]).into();
+        self.prev = ct;
+    }
+}
+
+impl BlockModeEncrypt for DummyCbc {
+    fn encrypt_with_backend(&mut self, f: impl BlockModeEncClosure<BlockSize = U8>) {
+        f.call(self)
+    }
+}
+
+impl BlockModeDecrypt for DummyCbc {
+    fn decrypt_with_backend(&mut self, f: impl BlockModeDecClosure<BlockSize = U8>) {
+        f.call(self)
+    }
+}
+
+/// Number of keystream blocks produced by [`DummyCore`].
+pub const MAX_BLOCKS: u32 = 16;
+
+/// Insecure dummy stream cipher core whose keystream blocks are the key
+/// XORed with the IV, offset by the block position.
+pub struct DummyCore {
+    block: [u8; 8],
+    pos: u32,
+}
+
+impl DummyCore {
+    pub fn keystream_block(key: &[u8; 8], iv: &[u8; 8], pos: u32) -> [u8; 8] {
+        core::array::from_fn(|i| (key[i] ^ iv[i]).wrapping_add(pos as u8))
+    }
+}
+
+impl KeySizeUser for DummyCore {
+    type KeySize = U8;
+}
+
+impl IvSizeUser for DummyCore {
+    type IvSize = U8;
+}
+
+impl KeyIvInit for DummyCore {
+    fn new(key: &Array<u8, U8>, iv: &Iv<Self>) -> Self {
+        Self {
+            block: core::array::from_fn(|i| key[i] ^ iv[i]),
+            pos: 0,
+        }
+    }
+}
+
+impl BlockSizeUser for DummyCore {
+    type BlockSize = U8;
+}
+
+impl ParBlocksSizeUser for DummyCore {
+    type ParBlocksSize = U1;
+}
+
+impl StreamCipherBackend for DummyCore {
+    fn gen_ks_block(&mut self, block: &mut Array<u8, U8>) {
+        *block = self.block.map(|b| b.wrapping_add(self.pos as u8)).into();
+        self.pos += 1;
+    }
+}
+
+impl StreamCipherCore for DummyCore {
+    fn remaining_blocks(&self) -> Option<usize> {
+        Some((MAX_BLOCKS - self.pos) as usize)
+    }
+
+    fn process_with_backend(&mut self, f: impl StreamCipherClosure<BlockSize = U8>) {
+        f.call(self)
+    }
+}
+
+impl StreamCipherSeekCore for DummyCore {
+    type Counter = u32;
+
+    fn get_block_pos(&self) -> u32 {
+        self.pos
+    }
+
+    fn set_block_pos(&mut self, pos: u32) {
+        self.pos = pos;
+    }
+}
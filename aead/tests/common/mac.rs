@@ -0,0 +1,60 @@
+//! Dummy MAC.
+use aead::{
+    KeyInit, KeySizeUser,
+    array::Array,
+    consts::U16,
+    digest::{FixedOutput, MacMarker, Output, OutputSizeUser, Update},
+};
+
+/// Insecure dummy MAC with a 128-bit output.
+#[derive(Clone)]
+pub struct DummyMac {
+    key: [u8; 16],
+    state: [u8; 16],
+    len: usize,
+}
+
+impl KeySizeUser for DummyMac {
+    type KeySize = U16;
+}
+
+impl KeyInit for DummyMac {
+    fn new(key: &Array<u8, U16>) -> Self {
+        Self {
+            key: key.0,
+            state: [0; 16],
+            len: 0,
+        }
+    }
+}
+
+impl Update for DummyMac {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            let i = self.len % 16;
+            self.state[i] = self.state[i].rotate_left(3) ^ byte ^ self.key[i];
+            self.len += 1;
+        }
+    }
+}
+
+impl OutputSizeUser for DummyMac {
+    type OutputSize = U16;
+}
+
+impl FixedOutput for DummyMac {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        let mut state = self.state;
+        state[0] ^= self.len as u8;
+        for round in 0..32 {
+            let i = round % 16;
+            state[(i + 1) % 16] ^= state[i].rotate_left(1).wrapping_add(self.key[i]);
+        }
+        *out = state.into();
+    }
+}
+
+impl MacMarker for DummyMac {}
+
+#[cfg(feature = "zeroize")]
+impl aead::zeroize::ZeroizeOnDrop for DummyMac {}
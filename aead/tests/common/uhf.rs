@@ -0,0 +1,55 @@
+//! Dummy universal hash function.
+use aead::{
+    KeyInit,
+    cipher::{
+        BlockSizeUser, KeySizeUser, ParBlocksSizeUser,
+        array::Array,
+        consts::{U1, U8},
+    },
+    universal_hash::{Block, UhfBackend, UhfClosure, UniversalHash},
+};
+
+/// Insecure dummy universal hash function.
+pub struct DummyUhf {
+    key: [u8; 8],
+    acc: [u8; 8],
+}
+
+impl KeySizeUser for DummyUhf {
+    type KeySize = U8;
+}
+
+impl KeyInit for DummyUhf {
+    fn new(key: &Array<u8, U8>) -> Self {
+        Self {
+            key: key.0,
+            acc: [0; 8],
+        }
+    }
+}
+
+impl BlockSizeUser for DummyUhf {
+    type BlockSize = U8;
+}
+
+impl ParBlocksSizeUser for DummyUhf {
+    type ParBlocksSize = U1;
+}
+
+impl UhfBackend for DummyUhf {
+    fn proc_block(&mut self, block: &Block<Self>) {
+        for i in 0..8 {
+            self.acc[i] = (self.acc[i] ^ block[i]).wrapping_mul(self.key[i] | 1);
+        }
+    }
+}
+
+impl UniversalHash for DummyUhf {
+    fn update_with_backend(&mut self, f: impl UhfClosure<BlockSize = U8>) {
+        f.call(self)
+    }
+
+    fn finalize(self) -> Block<Self> {
+        self.acc.into()
+    }
+}
@@ -0,0 +1,160 @@
+//! Tests of the generic Encrypt-then-MAC construction.
+#![cfg(all(feature = "dev", feature = "etm"))]
+mod common;
+
+use aead::{
+    Aead, AeadInOut, Error, KeyInit, Payload,
+    consts::U16,
+    etm::{BlockMode, EncryptThenMac, Lengths, Pkcs7EncryptThenMac, Rfc7518, StreamMode},
+};
+use common::cipher::{DummyCbc, DummyStream};
+use hmac::Hmac;
+use sha2::Sha256;
+
+type CbcHs256 = EncryptThenMac<BlockMode<DummyCbc, DummyCbc>, Hmac<Sha256>, Rfc7518<U16, U16>>;
+type PaddedCbcHs256 = Pkcs7EncryptThenMac<DummyCbc, DummyCbc, Hmac<Sha256>, Rfc7518<U16, U16>>;
+type CtrHs256 = EncryptThenMac<StreamMode<DummyStream>, Hmac<Sha256>, Lengths<U16, U16>>;
+
+const KEY: [u8; 24] = *b"mac key 16 bytes enc key";
+const NONCE: [u8; 8] = *b"noncenon";
+const PAYLOAD: Payload<'_, '_> = Payload {
+    msg: b"attack at dawn",
+    aad: b"header",
+};
+
+#[test]
+fn padded_cbc_round_trip() {
+    let cipher = PaddedCbcHs256::new(&KEY.into());
+    let ciphertext = cipher.encrypt(&NONCE.into(), PAYLOAD).unwrap();
+    // 14-byte message padded to 16 bytes, followed by a 16-byte tag
+    assert_eq!(ciphertext.len(), 32);
+
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: PAYLOAD.aad,
+    };
+    assert_eq!(cipher.decrypt(&NONCE.into(), payload).unwrap(), PAYLOAD.msg);
+
+    // The padded ciphertext is the unpadded encryption of the padded message
+    let mut padded = PAYLOAD.msg.to_vec();
+    padded.extend_from_slice(&[2, 2]);
+    let unpadded = CbcHs256::new(&KEY.into())
+        .encrypt(
+            &NONCE.into(),
+            Payload {
+                msg: &padded,
+                aad: PAYLOAD.aad,
+            },
+        )
+        .unwrap();
+    assert_eq!(ciphertext, unpadded);
+}
+
+#[test]
+fn padded_cbc_rejects_bad_padding() {
+    // Valid tag over a ciphertext whose plaintext ends with a zero byte
+    let ciphertext = CbcHs256::new(&KEY.into())
+        .encrypt(&NONCE.into(), &[0u8; 16][..])
+        .unwrap();
+    let cipher = PaddedCbcHs256::new(&KEY.into());
+    assert_eq!(
+        cipher.decrypt(&NONCE.into(), ciphertext.as_slice()),
+        Err(Error)
+    );
+}
+
+#[test]
+fn cbc_detached_and_buffer_agree() {
+    let cipher = CbcHs256::new(&KEY.into());
+
+    let mut buf = *b"0123456789abcdef";
+    let tag = cipher
+        .encrypt_inout_detached(&NONCE.into(), b"aad", buf.as_mut_slice().into())
+        .unwrap();
+    let ciphertext = cipher
+        .encrypt(
+            &NONCE.into(),
+            Payload {
+                msg: b"0123456789abcdef",
+                aad: b"aad",
+            },
+        )
+        .unwrap();
+    assert_eq!(ciphertext, [buf.as_slice(), &tag].concat());
+
+    // Both paths reject messages which aren't a multiple of the block size
+    let mut buf = *b"0123456789";
+    assert_eq!(
+        cipher.encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into()),
+        Err(Error)
+    );
+    assert_eq!(cipher.encrypt(&NONCE.into(), &buf[..]), Err(Error));
+}
+
+#[test]
+fn cbc_detached_requires_full_blocks() {
+    let cipher = CbcHs256::new(&KEY.into());
+    let mut buf = *b"0123456789abcdef";
+    let tag = cipher
+        .encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into())
+        .unwrap();
+    cipher
+        .decrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into(), &tag)
+        .unwrap();
+    assert_eq!(&buf, b"0123456789abcdef");
+
+    let mut buf = *b"0123456789";
+    assert_eq!(
+        cipher.encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into()),
+        Err(Error)
+    );
+}
+
+#[test]
+fn ctr_detached_and_buffer_agree() {
+    let cipher = CtrHs256::new(&KEY.into());
+    let mut buf = *b"attack at dawn";
+    let tag = cipher
+        .encrypt_inout_detached(&NONCE.into(), PAYLOAD.aad, buf.as_mut_slice().into())
+        .unwrap();
+    let ciphertext = cipher.encrypt(&NONCE.into(), PAYLOAD).unwrap();
+    assert_eq!(ciphertext, [buf.as_slice(), &tag].concat());
+}
+
+#[test]
+fn ctr_round_trip_and_tampering() {
+    let cipher = CtrHs256::new(&KEY.into());
+    let ciphertext = cipher.encrypt(&NONCE.into(), PAYLOAD).unwrap();
+    assert_eq!(ciphertext.len(), PAYLOAD.msg.len() + 16);
+
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: PAYLOAD.aad,
+    };
+    assert_eq!(cipher.decrypt(&NONCE.into(), payload).unwrap(), PAYLOAD.msg);
+
+    let wrong_aad = Payload {
+        msg: &ciphertext,
+        aad: b"other",
+    };
+    assert_eq!(cipher.decrypt(&NONCE.into(), wrong_aad), Err(Error));
+
+    let mut tampered = ciphertext.clone();
+    tampered[0] ^= 1;
+    assert_eq!(
+        cipher.decrypt(&NONCE.into(), tampered.as_slice()),
+        Err(Error)
+    );
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zeroize_on_drop() {
+    use common::mac::DummyMac;
+
+    fn assert_zeroize_on_drop<T: aead::zeroize::ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<EncryptThenMac<StreamMode<DummyStream>, DummyMac, Lengths<U16, U16>>>(
+    );
+    assert_zeroize_on_drop::<Pkcs7EncryptThenMac<DummyCbc, DummyCbc, DummyMac, Rfc7518<U16, U16>>>(
+    );
+}
@@ -0,0 +1,109 @@
+//! Tests of the key rotation keyring.
+#![cfg(all(feature = "dev", feature = "zeroize"))]
+mod common;
+
+use aead::{
+    AeadCore, AeadInOut, Error, KeyInit, KeySizeUser, Payload,
+    consts::U8,
+    keyring::{HEADER_SIZE, Keyring, key_id},
+    zeroize::ZeroizeOnDrop,
+};
+use common::{PostfixDummyAead, PrefixDummyAead};
+
+#[cfg(feature = "uhf")]
+use {
+    aead::uhf::StreamUhf,
+    common::{cipher::DummyCore, uhf::DummyUhf},
+};
+
+const MSG: &[u8] = b"rotate me";
+const AAD: &[u8] = b"record 42";
+
+fn check<A>()
+where
+    A: AeadInOut + AeadCore<NonceSize = U8> + KeyInit + KeySizeUser<KeySize = U8> + ZeroizeOnDrop,
+{
+    let nonce = Default::default();
+    let mut keyring = Keyring::<A>::new(1, b"old key!".into());
+    let old = keyring
+        .encrypt(&nonce, Payload { msg: MSG, aad: AAD })
+        .unwrap();
+    assert_eq!(key_id(&old), Ok(1));
+    assert_eq!(old.len(), HEADER_SIZE + MSG.len() + 8);
+
+    keyring.insert(2, b"new key!".into()).unwrap();
+    assert_eq!(keyring.insert(2, b"new key!".into()), Err(Error));
+    keyring.set_primary(2).unwrap();
+    assert_eq!(keyring.primary_id(), 2);
+
+    let new_nonce = [1; 8].into();
+    let new = keyring
+        .reencrypt(
+            &nonce,
+            &new_nonce,
+            Payload {
+                msg: &old,
+                aad: AAD,
+            },
+        )
+        .unwrap();
+    assert_eq!(key_id(&new), Ok(2));
+    let decrypted = keyring.decrypt(
+        &new_nonce,
+        Payload {
+            msg: &new,
+            aad: AAD,
+        },
+    );
+    assert_eq!(decrypted.as_deref(), Ok(MSG));
+    let decrypted = keyring.decrypt(
+        &nonce,
+        Payload {
+            msg: &old,
+            aad: AAD,
+        },
+    );
+    assert_eq!(decrypted.as_deref(), Ok(MSG));
+
+    assert_eq!(keyring.remove(2), Err(Error));
+    keyring.remove(1).unwrap();
+    assert!(!keyring.contains(1));
+    assert_eq!(keyring.key_ids().collect::<Vec<_>>(), [2]);
+    assert_eq!(
+        keyring.decrypt(
+            &nonce,
+            Payload {
+                msg: &old,
+                aad: AAD
+            }
+        ),
+        Err(Error)
+    );
+    assert_eq!(keyring.set_primary(1), Err(Error));
+}
+
+#[test]
+fn rotation() {
+    check::<PrefixDummyAead>();
+    check::<PostfixDummyAead>();
+    #[cfg(feature = "uhf")]
+    check::<StreamUhf<DummyCore, DummyUhf>>();
+}
+
+#[test]
+fn zeroize_on_drop() {
+    fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<Keyring<PostfixDummyAead>>();
+}
+
+#[test]
+fn header_is_authenticated() {
+    let nonce = Default::default();
+    let mut keyring = Keyring::<PostfixDummyAead>::new(1, b"samekey!".into());
+    keyring.insert(2, b"samekey!".into()).unwrap();
+    let mut ct = keyring.encrypt(&nonce, MSG).unwrap();
+    ct[HEADER_SIZE - 1] = 2;
+    assert_eq!(key_id(&ct), Ok(2));
+    assert_eq!(keyring.decrypt(&nonce, ct.as_slice()), Err(Error));
+    assert_eq!(key_id(&ct[..HEADER_SIZE - 1]), Err(Error));
+}
@@ -0,0 +1,149 @@
+//! Tests of the nonce sequences and the nonce reuse guard.
+#![cfg(feature = "dev")]
+mod common;
+
+use aead::{
+    Aead, AeadInOut, Error, KeyInit,
+    array::Array,
+    consts::{U2, U8},
+    nonce::{
+        CounterBE, CounterLE, Limited, NonceReuseGuard, NonceSequence, PrefixedCounter, SealingKey,
+    },
+};
+use common::PostfixDummyAead;
+
+#[test]
+fn counters() {
+    let mut be = CounterBE::<U2>::new();
+    let mut le = CounterLE::<U2>::new();
+    for i in 0..=u16::MAX {
+        assert_eq!(be.advance().unwrap().0, i.to_be_bytes());
+        assert_eq!(le.advance().unwrap().0, i.to_le_bytes());
+    }
+    assert_eq!(be.advance(), Err(Error));
+    assert_eq!(le.advance(), Err(Error));
+}
+
+#[test]
+fn prefixed_counter() {
+    let mut nonces = PrefixedCounter::<U8>::new(Array([0xAA; 4]));
+    assert_eq!(
+        nonces.advance().unwrap().0,
+        [0xAA, 0xAA, 0xAA, 0xAA, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        nonces.advance().unwrap().0,
+        [0xAA, 0xAA, 0xAA, 0xAA, 0, 0, 0, 1]
+    );
+}
+
+#[test]
+fn limited() {
+    let mut nonces = Limited::new(CounterBE::<U8>::new(), 2);
+    assert!(nonces.advance().is_ok());
+    assert_eq!(nonces.remaining(), 1);
+    assert!(nonces.advance().is_ok());
+    assert_eq!(nonces.advance(), Err(Error));
+}
+
+#[test]
+fn sealing_key() {
+    let aead = PostfixDummyAead::new(b"sealkey!".into());
+    let mut key = SealingKey::new(aead, Limited::new(CounterBE::new(), 2));
+
+    let (nonce1, ciphertext1) = key.seal(b"first".as_slice()).unwrap();
+    let (nonce2, ciphertext2) = key.seal(b"second".as_slice()).unwrap();
+    assert_ne!(nonce1, nonce2);
+    assert_eq!(key.seal(b"third".as_slice()), Err(Error));
+
+    assert_eq!(key.open(&nonce1, ciphertext1.as_slice()).unwrap(), b"first");
+    assert_eq!(
+        key.open(&nonce2, ciphertext2.as_slice()).unwrap(),
+        b"second"
+    );
+    assert_eq!(key.open(&nonce1, ciphertext2.as_slice()), Err(Error));
+}
+
+#[test]
+fn nonce_reuse_guard() {
+    let guard = NonceReuseGuard::<PostfixDummyAead>::new_from_slice(b"guarded!").unwrap();
+    let nonce1 = Array([1; 8]);
+    let nonce2 = Array([2; 8]);
+
+    let ciphertext = guard.encrypt(&nonce1, b"first".as_slice()).unwrap();
+    assert!(guard.is_used(&nonce1));
+    assert!(!guard.is_used(&nonce2));
+    assert_eq!(guard.encrypt(&nonce1, b"second".as_slice()), Err(Error));
+    let mut buf = *b"second";
+    assert_eq!(
+        guard.encrypt_inout_detached(&nonce1, b"", buf.as_mut_slice().into()),
+        Err(Error)
+    );
+    assert!(guard.encrypt(&nonce2, b"second".as_slice()).is_ok());
+    assert_eq!(guard.used_count(), 2);
+
+    // decryption may reuse nonces
+    for _ in 0..2 {
+        let plaintext = guard.decrypt(&nonce1, ciphertext.as_slice()).unwrap();
+        assert_eq!(plaintext, b"first");
+    }
+
+    // every key has its own set of nonces
+    let other = NonceReuseGuard::<PostfixDummyAead>::new_from_slice(b"another!").unwrap();
+    assert!(other.encrypt(&nonce1, b"first".as_slice()).is_ok());
+}
+
+#[test]
+fn nonce_reuse_guard_capacity() {
+    let aead = PostfixDummyAead::new(b"guarded!".into());
+    let guard = NonceReuseGuard::with_capacity(aead, 2, 0);
+    assert!(guard.encrypt(&Array([1; 8]), b"".as_slice()).is_ok());
+    assert!(guard.encrypt(&Array([2; 8]), b"".as_slice()).is_ok());
+    assert_eq!(guard.encrypt(&Array([3; 8]), b"".as_slice()), Err(Error));
+    assert!(!guard.is_used(&Array([3; 8])));
+}
+
+#[test]
+fn nonce_reuse_guard_filter() {
+    let aead = PostfixDummyAead::new(b"guarded!".into());
+    let guard = NonceReuseGuard::with_capacity(aead, 2, 1 << 16);
+    for i in 0..1000u64 {
+        let nonce = Array(i.to_le_bytes());
+        assert!(!guard.is_used(&nonce));
+        assert!(guard.encrypt(&nonce, b"".as_slice()).is_ok());
+    }
+    assert_eq!(guard.used_count(), 1000);
+    for i in [0u64, 1, 2, 999] {
+        let nonce = Array(i.to_le_bytes());
+        assert!(guard.is_used(&nonce));
+        assert_eq!(guard.encrypt(&nonce, b"".as_slice()), Err(Error));
+    }
+    assert_eq!(guard.used_count(), 1000);
+}
+
+#[test]
+fn nonce_reuse_guard_vectored() {
+    let guard = NonceReuseGuard::<PostfixDummyAead>::new_from_slice(b"guarded!").unwrap();
+    let nonce = Array([1; 8]);
+    let (mut a, mut b) = (*b"attack ", *b"at dawn");
+    let tag = guard
+        .encrypt_vectored_detached(&nonce, &[b"header"], &mut [&mut a, &mut b])
+        .unwrap();
+    assert!(guard.is_used(&nonce));
+    assert_eq!(
+        guard.encrypt_vectored_detached(&nonce, &[], &mut [&mut a]),
+        Err(Error)
+    );
+
+    guard
+        .decrypt_vectored_detached(&nonce, &[b"header"], &mut [&mut a, &mut b], &tag)
+        .unwrap();
+    assert_eq!((&a, &b), (b"attack ", b"at dawn"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn nonce_reuse_guard_is_sync() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<NonceReuseGuard<PostfixDummyAead>>();
+}
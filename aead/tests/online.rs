@@ -0,0 +1,250 @@
+//! Tests of the online AEAD traits and buffering adapters.
+#![cfg(feature = "dev")]
+mod common;
+
+use aead::{
+    AeadInOut, Error, KeyInit, Nonce, Result, Tag,
+    consts::U8,
+    hazmat::{AeadOnline, BufferedDecryptor, BufferedEncryptor, OnlineDecryptor, OnlineEncryptor},
+};
+use common::{DummyAead, PostfixDummyAead};
+use crypto_common::BlockSizeUser;
+use inout::InOutBuf;
+
+struct State<'a> {
+    aead: &'a DummyAead,
+    tag: u64,
+    aad_done: bool,
+    msg_done: bool,
+}
+
+impl<'a> State<'a> {
+    fn new(aead: &'a DummyAead, nonce: &Nonce<PostfixDummyAead>) -> Self {
+        Self {
+            aead,
+            tag: u64::from_le_bytes(nonce.0),
+            aad_done: false,
+            msg_done: false,
+        }
+    }
+
+    fn update_aad(&mut self, aad: &[u8]) -> Result<()> {
+        if self.aad_done {
+            return Err(Error);
+        }
+        self.aad_done = aad.len() % 8 != 0;
+        // `process_aad` resumes from the state passed in place of the nonce
+        self.tag = self.aead.process_aad(&self.tag.to_le_bytes(), aad);
+        Ok(())
+    }
+
+    fn update_inout(&mut self, buffer: InOutBuf<'_, '_, u8>, encrypt: bool) -> Result<()> {
+        if self.msg_done {
+            return Err(Error);
+        }
+        self.aad_done = true;
+        self.msg_done = buffer.len() % 8 != 0;
+
+        let tag = if encrypt {
+            self.aead.encrypt_inner(&[0; 8], &[], buffer)?
+        } else {
+            self.aead.decrypt_unverified(&[0; 8], &[], buffer)
+        };
+        self.tag ^= u64::from_le_bytes(tag);
+        Ok(())
+    }
+}
+
+pub struct Encryptor<'a>(State<'a>);
+
+impl OnlineEncryptor for Encryptor<'_> {
+    type Aead = PostfixDummyAead;
+
+    fn update_aad(&mut self, aad: &[u8]) -> Result<()> {
+        self.0.update_aad(aad)
+    }
+
+    fn update_inout(&mut self, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        self.0.update_inout(buffer, true)
+    }
+
+    fn finish_tag(self) -> Result<Tag<PostfixDummyAead>> {
+        Ok(self.0.tag.to_le_bytes().into())
+    }
+}
+
+pub struct Decryptor<'a>(State<'a>);
+
+impl OnlineDecryptor for Decryptor<'_> {
+    type Aead = PostfixDummyAead;
+
+    fn update_aad(&mut self, aad: &[u8]) -> Result<()> {
+        self.0.update_aad(aad)
+    }
+
+    fn update_inout(&mut self, buffer: InOutBuf<'_, '_, u8>) -> Result<()> {
+        self.0.update_inout(buffer, false)
+    }
+
+    fn verify_finish(self, tag: &Tag<PostfixDummyAead>) -> Result<()> {
+        if self.0.tag.to_le_bytes() == tag.0 {
+            Ok(())
+        } else {
+            Err(Error)
+        }
+    }
+}
+
+impl BlockSizeUser for PostfixDummyAead {
+    type BlockSize = U8;
+}
+
+impl AeadOnline for PostfixDummyAead {
+    type Encryptor<'a> = Encryptor<'a>;
+    type Decryptor<'a> = Decryptor<'a>;
+
+    fn begin_encrypt(&self, nonce: &Nonce<Self>) -> Encryptor<'_> {
+        Encryptor(State::new(&self.0, nonce))
+    }
+
+    fn begin_decrypt(&self, nonce: &Nonce<Self>) -> Decryptor<'_> {
+        Decryptor(State::new(&self.0, nonce))
+    }
+}
+
+const KEY: [u8; 8] = *b"onlineky";
+const AAD: &[u8; 20] = b"online header data!!";
+const MSG: &[u8; 45] = b"streamed through a proxy, one chunk at a time";
+
+#[test]
+fn matches_one_shot() {
+    let cipher = PostfixDummyAead::new(&KEY.into());
+    let nonce = Nonce::<PostfixDummyAead>::from([7; 8]);
+    let mut expected = *MSG;
+    let expected_tag = cipher
+        .encrypt_inout_detached(&nonce, AAD, expected.as_mut_slice().into())
+        .unwrap();
+
+    let mut buf = *MSG;
+    let mut encryptor = cipher.begin_encrypt(&nonce);
+    encryptor.update_aad(&AAD[..8]).unwrap();
+    encryptor.update_aad(&AAD[8..]).unwrap();
+    let (head, tail) = buf.split_at_mut(24);
+    encryptor.update_inout(head[..16].as_mut().into()).unwrap();
+    encryptor.update_inout(head[16..].as_mut().into()).unwrap();
+    encryptor.update_inout(tail.into()).unwrap();
+    assert_eq!(encryptor.finish_tag().unwrap(), expected_tag);
+    assert_eq!(buf, expected);
+
+    let mut decryptor = cipher.begin_decrypt(&nonce);
+    decryptor.update_aad(AAD).unwrap();
+    let (head, tail) = buf.split_at_mut(8);
+    decryptor.update_inout(head.into()).unwrap();
+    decryptor.update_inout(tail.into()).unwrap();
+    decryptor.verify_finish(&expected_tag).unwrap();
+    assert_eq!(&buf, MSG);
+}
+
+#[test]
+fn buffered_matches_one_shot() {
+    let cipher = PostfixDummyAead::new(&KEY.into());
+    let nonce = Nonce::<PostfixDummyAead>::from([7; 8]);
+    let mut expected = *MSG;
+    let expected_tag = cipher
+        .encrypt_inout_detached(&nonce, AAD, expected.as_mut_slice().into())
+        .unwrap();
+
+    for chunk_len in 1..=MSG.len() {
+        let mut encryptor = BufferedEncryptor::new(cipher.begin_encrypt(&nonce));
+        for chunk in AAD.chunks(chunk_len) {
+            encryptor.update_aad(chunk).unwrap();
+        }
+        let mut ciphertext = [0u8; 45];
+        let mut pos = 0;
+        for chunk in MSG.chunks(chunk_len) {
+            pos += encryptor.update(chunk, &mut ciphertext[pos..]).unwrap();
+            assert_eq!(pos % 8, 0);
+        }
+        let (written, tag) = encryptor.finish(&mut ciphertext[pos..]).unwrap();
+        assert_eq!(pos + written, MSG.len());
+        assert_eq!(ciphertext, expected);
+        assert_eq!(tag, expected_tag);
+
+        let mut decryptor = BufferedDecryptor::new(cipher.begin_decrypt(&nonce));
+        for chunk in AAD.chunks(chunk_len) {
+            decryptor.update_aad(chunk).unwrap();
+        }
+        let mut plaintext = [0u8; 45];
+        let mut pos = 0;
+        for chunk in ciphertext.chunks(chunk_len) {
+            pos += decryptor.update(chunk, &mut plaintext[pos..]).unwrap();
+        }
+        pos += decryptor
+            .verify_finish(&mut plaintext[pos..], &expected_tag)
+            .unwrap();
+        assert_eq!(pos, MSG.len());
+        assert_eq!(&plaintext, MSG);
+    }
+}
+
+#[test]
+fn buffered_misuse() {
+    let cipher = PostfixDummyAead::new(&KEY.into());
+    let nonce = Nonce::<PostfixDummyAead>::default();
+
+    let mut encryptor = BufferedEncryptor::new(cipher.begin_encrypt(&nonce));
+    encryptor.update_aad(&AAD[..3]).unwrap();
+    encryptor.update_aad(&AAD[3..]).unwrap();
+    let mut out = [0u8; 16];
+    assert_eq!(encryptor.update(&MSG[..5], &mut out[..0]), Ok(0));
+    // 5 buffered bytes plus 11 new ones make two blocks
+    assert_eq!(encryptor.update(&MSG[5..16], &mut out[..8]), Err(Error));
+    assert_eq!(encryptor.update(&MSG[5..16], &mut out), Ok(16));
+    assert_eq!(encryptor.update_aad(AAD), Err(Error));
+    assert_eq!(encryptor.update(&MSG[16..19], &mut []), Ok(0));
+    assert!(encryptor.finish(&mut out[..2]).is_err());
+
+    let mut decryptor = BufferedDecryptor::new(cipher.begin_decrypt(&nonce));
+    let mut out = [0u8; 45];
+    decryptor.update(MSG, &mut out).unwrap();
+    assert_eq!(
+        decryptor.verify_finish(&mut out, &Default::default()),
+        Err(Error)
+    );
+}
+
+#[test]
+fn releases_unverified_plaintext() {
+    let cipher = PostfixDummyAead::new(&KEY.into());
+    let nonce = Nonce::<PostfixDummyAead>::default();
+    let mut buf = *MSG;
+    let mut tag = cipher
+        .encrypt_inout_detached(&nonce, AAD, buf.as_mut_slice().into())
+        .unwrap();
+    tag[0] ^= 1;
+
+    let mut decryptor = cipher.begin_decrypt(&nonce);
+    decryptor.update_aad(AAD).unwrap();
+    decryptor.update_inout(buf.as_mut_slice().into()).unwrap();
+    assert_eq!(&buf, MSG);
+    assert_eq!(decryptor.verify_finish(&tag), Err(Error));
+}
+
+#[test]
+fn misuse() {
+    let cipher = PostfixDummyAead::new(&KEY.into());
+    let nonce = Nonce::<PostfixDummyAead>::default();
+    let mut buf = *MSG;
+
+    let mut encryptor = cipher.begin_encrypt(&nonce);
+    encryptor.update_aad(&AAD[..3]).unwrap();
+    assert_eq!(encryptor.update_aad(&AAD[3..]), Err(Error));
+
+    let mut encryptor = cipher.begin_encrypt(&nonce);
+    encryptor.update_inout(buf[..8].as_mut().into()).unwrap();
+    assert_eq!(encryptor.update_aad(AAD), Err(Error));
+
+    let mut decryptor = cipher.begin_decrypt(&nonce);
+    decryptor.update_inout(buf[..5].as_mut().into()).unwrap();
+    assert_eq!(decryptor.update_inout(buf[5..].as_mut().into()), Err(Error));
+}
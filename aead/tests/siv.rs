@@ -0,0 +1,400 @@
+//! Tests of the generic SIV construction.
+#![cfg(all(feature = "dev", feature = "siv"))]
+mod common;
+
+use aead::{
+    Aead, AeadCore, AeadInOut, Error, Key, KeyInit, Nonce, NonceMisuseResistant, Payload, Tag,
+    TagPosition,
+    cipher::{
+        InOutBuf, Iv, IvSizeUser, KeyIvInit, KeySizeUser, StreamCipher, StreamCipherError,
+        array::Array,
+        consts::{U8, U16},
+    },
+    digest::{FixedOutput, MacMarker, Output, OutputSizeUser, Update},
+    siv::{MAX_HEADERS, Siv},
+};
+use common::PostfixDummyAead;
+use common::{cipher::DummyCtr, mac::DummyMac};
+
+type DummySiv = Siv<DummyCtr, DummyMac, U8>;
+
+const KEY: [u8; 32] = *b"mac key 16 bytesenc key 16 bytes";
+
+aead::new_nonce_reuse_test!(nonce_reuse, DummySiv);
+
+/// [`PostfixDummyAead`] deliberately mislabeled as nonce misuse resistant
+/// to check that the harness detects keystream reuse.
+struct MislabeledAead(PostfixDummyAead);
+
+impl KeySizeUser for MislabeledAead {
+    type KeySize = U8;
+}
+
+impl KeyInit for MislabeledAead {
+    fn new(key: &Key<Self>) -> Self {
+        Self(PostfixDummyAead::new(key))
+    }
+}
+
+impl AeadCore for MislabeledAead {
+    type NonceSize = U8;
+    type TagSize = U8;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+impl AeadInOut for MislabeledAead {
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>, Error> {
+        self.0.encrypt_inout_detached(nonce, aad, buffer)
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> Result<(), Error> {
+        self.0.decrypt_inout_detached(nonce, aad, buffer, tag)
+    }
+}
+
+impl NonceMisuseResistant for MislabeledAead {}
+
+#[test]
+fn nonce_reuse_test_detects_keystream_reuse() {
+    assert_eq!(
+        aead::dev::nonce_reuse_test::<MislabeledAead>(),
+        Err("keystream is reused for distinct messages")
+    );
+}
+
+#[test]
+fn round_trip_with_headers() {
+    let cipher = DummySiv::new(&KEY.into());
+    let headers: [&[u8]; 2] = [b"config", b"secret name"];
+    let msg = [0x42; 40];
+
+    // Covers both the short (padded) and long (xorend) final S2V input
+    for len in 0..msg.len() {
+        let ciphertext = cipher.encrypt_with_headers(&headers, &msg[..len]).unwrap();
+        assert_eq!(ciphertext.len(), 16 + len);
+        assert_eq!(
+            cipher.encrypt_with_headers(&headers, &msg[..len]).unwrap(),
+            ciphertext
+        );
+        assert_eq!(
+            cipher.decrypt_with_headers(&headers, &ciphertext).unwrap(),
+            &msg[..len]
+        );
+
+        let swapped = [headers[1], headers[0]];
+        assert_eq!(
+            cipher.decrypt_with_headers(&swapped, &ciphertext),
+            Err(Error)
+        );
+        assert_eq!(
+            cipher.decrypt_with_headers(&headers[..1], &ciphertext),
+            Err(Error)
+        );
+    }
+}
+
+#[test]
+fn nonce_is_last_header() {
+    let cipher = DummySiv::new(&KEY.into());
+    let nonce = *b"noncenon";
+    let (msg, aad) = (b"attack at dawn", b"header");
+    let ciphertext = cipher.encrypt(&nonce.into(), Payload { msg, aad }).unwrap();
+    let headers: [&[u8]; 2] = [aad, &nonce];
+    assert_eq!(
+        cipher.encrypt_with_headers(&headers, msg).unwrap(),
+        ciphertext
+    );
+}
+
+#[test]
+fn decryption_failure_zeroes_output() {
+    let cipher = DummySiv::new(&KEY.into());
+    let nonce = (*b"noncenon").into();
+    let mut buf = *b"attack at dawn";
+    let mut tag = cipher
+        .encrypt_inout_detached(&nonce, b"", buf.as_mut_slice().into())
+        .unwrap();
+
+    tag[0] ^= 1;
+    assert_eq!(
+        cipher.decrypt_inout_detached(&nonce, b"", buf.as_mut_slice().into(), &tag),
+        Err(Error)
+    );
+    assert_eq!(buf, [0; 14]);
+}
+
+#[test]
+fn too_many_headers() {
+    let cipher = DummySiv::new(&KEY.into());
+    let headers = [b"header"; MAX_HEADERS + 1];
+    assert!(
+        cipher
+            .encrypt_with_headers(&headers[..MAX_HEADERS], b"msg")
+            .is_ok()
+    );
+    assert_eq!(cipher.encrypt_with_headers(&headers, b"msg"), Err(Error));
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zeroize_on_drop() {
+    fn assert_zeroize_on_drop<T: aead::zeroize::ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<DummySiv>();
+}
+
+/// Minimal (slow and not constant time!) AES-128 block encryption as
+/// specified by FIPS 197, used to check the RFC 5297 test vectors.
+#[derive(Clone)]
+struct Aes128 {
+    round_keys: [[u8; 16]; 11],
+}
+
+impl Aes128 {
+    fn new(key: &[u8; 16]) -> Self {
+        let mut words = [[0u8; 4]; 44];
+        for (word, chunk) in words.iter_mut().zip(key.chunks_exact(4)) {
+            word.copy_from_slice(chunk);
+        }
+        let mut rcon = 1;
+        for i in 4..44 {
+            let mut temp = words[i - 1];
+            if i % 4 == 0 {
+                temp.rotate_left(1);
+                temp = temp.map(sbox);
+                temp[0] ^= rcon;
+                rcon = xtime(rcon);
+            }
+            for j in 0..4 {
+                words[i][j] = words[i - 4][j] ^ temp[j];
+            }
+        }
+
+        let mut round_keys = [[0; 16]; 11];
+        for (round_key, words) in round_keys.iter_mut().zip(words.chunks_exact(4)) {
+            round_key.copy_from_slice(words.as_flattened());
+        }
+        Self { round_keys }
+    }
+
+    fn encrypt(&self, block: [u8; 16]) -> [u8; 16] {
+        let xor = |a: [u8; 16], b: &[u8; 16]| core::array::from_fn(|i| a[i] ^ b[i]);
+        let mut state = xor(block, &self.round_keys[0]);
+        for (round, round_key) in self.round_keys.iter().enumerate().skip(1) {
+            // SubBytes and ShiftRows, with the state stored column by column
+            let shifted: [u8; 16] = core::array::from_fn(|i| sbox(state[(i + 4 * (i % 4)) % 16]));
+            state = shifted;
+            if round != 10 {
+                for column in state.chunks_exact_mut(4) {
+                    let a: [u8; 4] = column.try_into().unwrap();
+                    let t = a[0] ^ a[1] ^ a[2] ^ a[3];
+                    for i in 0..4 {
+                        column[i] = a[i] ^ t ^ xtime(a[i] ^ a[(i + 1) % 4]);
+                    }
+                }
+            }
+            state = xor(state, round_key);
+        }
+        state
+    }
+}
+
+/// Multiplication by `x` in GF(2^8).
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ ((b >> 7) * 0x1b)
+}
+
+/// AES S-box, computed as the affine transform of the inverse in GF(2^8).
+fn sbox(b: u8) -> u8 {
+    let mul = |mut a: u8, mut b: u8| {
+        let mut p = 0;
+        while b != 0 {
+            p ^= a * (b & 1);
+            a = xtime(a);
+            b >>= 1;
+        }
+        p
+    };
+    let inv = (0..254).fold(1, |p, _| mul(p, b));
+    inv ^ inv.rotate_left(1) ^ inv.rotate_left(2) ^ inv.rotate_left(3) ^ inv.rotate_left(4) ^ 0x63
+}
+
+/// AES-128 in CTR mode with a 128-bit big endian counter.
+struct Aes128Ctr {
+    cipher: Aes128,
+    counter: u128,
+    keystream: [u8; 16],
+    pos: usize,
+}
+
+impl KeySizeUser for Aes128Ctr {
+    type KeySize = U16;
+}
+
+impl IvSizeUser for Aes128Ctr {
+    type IvSize = U16;
+}
+
+impl KeyIvInit for Aes128Ctr {
+    fn new(key: &Array<u8, U16>, iv: &Iv<Self>) -> Self {
+        Self {
+            cipher: Aes128::new(&key.0),
+            counter: u128::from_be_bytes(iv.0),
+            keystream: [0; 16],
+            pos: 0,
+        }
+    }
+}
+
+impl StreamCipher for Aes128Ctr {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: InOutBuf<'_, '_, u8>,
+    ) -> Result<(), StreamCipherError> {
+        for i in 0..buf.len() {
+            if self.pos % 16 == 0 {
+                self.keystream = self.cipher.encrypt(self.counter.to_be_bytes());
+                self.counter = self.counter.wrapping_add(1);
+            }
+            let mut byte = buf.get(i);
+            *byte.get_out() = byte.get_in() ^ self.keystream[self.pos % 16];
+            self.pos += 1;
+        }
+        Ok(())
+    }
+}
+
+/// AES-128 CMAC as specified by NIST SP 800-38B.
+#[derive(Clone)]
+struct Aes128Cmac {
+    cipher: Aes128,
+    data: Vec<u8>,
+}
+
+impl KeySizeUser for Aes128Cmac {
+    type KeySize = U16;
+}
+
+impl KeyInit for Aes128Cmac {
+    fn new(key: &Array<u8, U16>) -> Self {
+        Self {
+            cipher: Aes128::new(&key.0),
+            data: Vec::new(),
+        }
+    }
+}
+
+impl Update for Aes128Cmac {
+    fn update(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+}
+
+impl OutputSizeUser for Aes128Cmac {
+    type OutputSize = U16;
+}
+
+impl FixedOutput for Aes128Cmac {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        let dbl = |block: u128| (block << 1) ^ ((block >> 127) * 0x87);
+        let encrypt = |block: u128| u128::from_be_bytes(self.cipher.encrypt(block.to_be_bytes()));
+        let k1 = dbl(encrypt(0));
+        let k2 = dbl(k1);
+
+        let (blocks, last) = match self.data.len() {
+            0 => (&[][..], &[][..]),
+            len => self.data.split_at(len - 1 - (len - 1) % 16),
+        };
+        let mut state = 0;
+        for block in blocks.chunks_exact(16) {
+            state = encrypt(state ^ u128::from_be_bytes(block.try_into().unwrap()));
+        }
+        let mut padded = [0; 16];
+        padded[..last.len()].copy_from_slice(last);
+        let subkey = if last.len() == 16 {
+            k1
+        } else {
+            padded[last.len()] = 0x80;
+            k2
+        };
+        state = encrypt(state ^ u128::from_be_bytes(padded) ^ subkey);
+        *out = state.to_be_bytes().into();
+    }
+}
+
+impl MacMarker for Aes128Cmac {}
+
+type Aes128Siv = Siv<Aes128Ctr, Aes128Cmac>;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// FIPS 197 Appendix C.1 AES-128 example vector.
+#[test]
+fn aes128_block() {
+    let cipher = Aes128::new(&hex("000102030405060708090a0b0c0d0e0f").try_into().unwrap());
+    assert_eq!(
+        cipher.encrypt(hex("00112233445566778899aabbccddeeff").try_into().unwrap()),
+        *hex("69c4e0d86a7b0430d8cdb78070b4c55a")
+    );
+}
+
+/// RFC 5297 Appendix A.1: deterministic authenticated encryption.
+#[test]
+fn rfc5297_deterministic() {
+    let key = hex("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    let ad = hex("101112131415161718191a1b1c1d1e1f2021222324252627");
+    let plaintext = hex("112233445566778899aabbccddee");
+    let output = hex("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c");
+
+    let cipher = Aes128Siv::new_from_slice(&key).unwrap();
+    assert_eq!(
+        cipher.encrypt_with_headers(&[&ad], &plaintext).unwrap(),
+        output
+    );
+    assert_eq!(
+        cipher.decrypt_with_headers(&[&ad], &output).unwrap(),
+        plaintext
+    );
+}
+
+/// RFC 5297 Appendix A.2: nonce-based authenticated encryption.
+#[test]
+fn rfc5297_nonce_based() {
+    let key = hex("7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f");
+    let ad1 =
+        hex("00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100");
+    let ad2 = hex("102030405060708090a0");
+    let nonce = hex("09f911029d74e35bd84156c5635688c0");
+    let plaintext = hex(
+        "7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553",
+    );
+    let output = hex(
+        "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d",
+    );
+
+    let cipher = Aes128Siv::new_from_slice(&key).unwrap();
+    let headers = [&ad1, &ad2, &nonce];
+    assert_eq!(
+        cipher.encrypt_with_headers(&headers, &plaintext).unwrap(),
+        output
+    );
+    assert_eq!(
+        cipher.decrypt_with_headers(&headers, &output).unwrap(),
+        plaintext
+    );
+}
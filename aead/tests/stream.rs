@@ -0,0 +1,105 @@
+//! Tests of the STREAM construction.
+#![cfg(all(feature = "dev", feature = "stream"))]
+mod common;
+
+use aead::stream::NewStream;
+use aead::{
+    Error,
+    array::Array,
+    stream::{DecryptorBE32, DecryptorLE31, EncryptorBE32, StreamLE31, StreamPrimitive},
+};
+use common::PostfixDummyAead;
+
+const KEY: [u8; 8] = *b"streamky";
+const SEGMENTS: [&[u8]; 3] = [b"first segment", b"second segment", b"last"];
+
+fn encrypt_be32() -> Vec<Vec<u8>> {
+    let mut encryptor = EncryptorBE32::<PostfixDummyAead>::new(&KEY.into(), &Array([1, 2, 3]));
+    let mut ciphertexts = vec![
+        encryptor.encrypt_next(SEGMENTS[0]).unwrap(),
+        encryptor.encrypt_next(SEGMENTS[1]).unwrap(),
+    ];
+    ciphertexts.push(encryptor.encrypt_last(SEGMENTS[2]).unwrap());
+    ciphertexts
+}
+
+fn decryptor_be32() -> DecryptorBE32<PostfixDummyAead> {
+    DecryptorBE32::new(&KEY.into(), &Array([1, 2, 3]))
+}
+
+#[test]
+fn round_trip() {
+    let ciphertexts = encrypt_be32();
+
+    let mut decryptor = decryptor_be32();
+    assert_eq!(
+        decryptor.decrypt_next(ciphertexts[0].as_slice()).unwrap(),
+        SEGMENTS[0]
+    );
+    assert_eq!(
+        decryptor.decrypt_next(ciphertexts[1].as_slice()).unwrap(),
+        SEGMENTS[1]
+    );
+    assert_eq!(
+        decryptor.decrypt_last(ciphertexts[2].as_slice()).unwrap(),
+        SEGMENTS[2]
+    );
+}
+
+#[test]
+fn reordering_and_truncation() {
+    let ciphertexts = encrypt_be32();
+
+    let mut decryptor = decryptor_be32();
+    assert_eq!(
+        decryptor.decrypt_next(ciphertexts[1].as_slice()),
+        Err(Error)
+    );
+
+    let mut decryptor = decryptor_be32();
+    decryptor.decrypt_next(ciphertexts[0].as_slice()).unwrap();
+    assert_eq!(
+        decryptor.decrypt_last(ciphertexts[1].as_slice()),
+        Err(Error)
+    );
+
+    let mut decryptor = decryptor_be32();
+    decryptor.decrypt_next(ciphertexts[0].as_slice()).unwrap();
+    decryptor.decrypt_next(ciphertexts[1].as_slice()).unwrap();
+    assert_eq!(
+        decryptor.decrypt_next(ciphertexts[2].as_slice()),
+        Err(Error)
+    );
+}
+
+#[test]
+fn inout_detached() {
+    let mut encryptor =
+        StreamLE31::<PostfixDummyAead>::new(&KEY.into(), &Array([4; 4])).encryptor();
+    let mut decryptor = DecryptorLE31::<PostfixDummyAead>::new(&KEY.into(), &Array([4; 4]));
+
+    let mut buf = *b"segment";
+    let tag = encryptor
+        .encrypt_next_inout_detached(b"aad", buf.as_mut_slice().into())
+        .unwrap();
+    decryptor
+        .decrypt_next_inout_detached(b"aad", buf.as_mut_slice().into(), &tag)
+        .unwrap();
+    assert_eq!(&buf, b"segment");
+
+    let tag = encryptor
+        .encrypt_last_inout_detached(b"aad", buf.as_mut_slice().into())
+        .unwrap();
+    decryptor
+        .decrypt_last_inout_detached(b"aad", buf.as_mut_slice().into(), &tag)
+        .unwrap();
+    assert_eq!(&buf, b"segment");
+}
+
+#[test]
+fn counter_overflow() {
+    let stream = StreamLE31::<PostfixDummyAead>::new(&KEY.into(), &Array([4; 4]));
+    let max = StreamLE31::<PostfixDummyAead>::COUNTER_MAX;
+    assert!(stream.aead_nonce(max, true).is_ok());
+    assert_eq!(stream.aead_nonce(max + 1, false), Err(Error));
+}
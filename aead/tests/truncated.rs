@@ -0,0 +1,110 @@
+//! Tests of the truncated tag wrapper.
+#![cfg(all(feature = "dev", feature = "truncated"))]
+mod common;
+
+use aead::{
+    Aead, AeadInOut, Error, KeyInit, Nonce, Result, Tag,
+    array::Array,
+    consts::U4,
+    inout::InOutBuf,
+    truncated::{DecryptUnverified, Truncated},
+};
+use common::{PostfixDummyAead, PrefixDummyAead};
+
+impl DecryptUnverified for PrefixDummyAead {
+    fn decrypt_inout_unverified(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        Ok(Array(self.0.decrypt_unverified(nonce.into(), aad, buffer)))
+    }
+}
+
+impl DecryptUnverified for PostfixDummyAead {
+    fn decrypt_inout_unverified(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<Self>> {
+        Ok(Array(self.0.decrypt_unverified(nonce.into(), aad, buffer)))
+    }
+}
+
+aead::new_test!(
+    dummy_prefix_truncated,
+    "prefix_truncated",
+    Truncated<PrefixDummyAead, U4>,
+);
+aead::new_test!(
+    dummy_postfix_truncated,
+    "postfix_truncated",
+    Truncated<PostfixDummyAead, U4>,
+);
+
+const KEY: [u8; 8] = *b"dummykey";
+const NONCE: [u8; 8] = *b"noncenon";
+
+#[test]
+fn tag_is_prefix_of_full_tag() {
+    let full = PostfixDummyAead::new(&KEY.into());
+    let truncated = Truncated::<_, U4>::new(PostfixDummyAead::new(&KEY.into()));
+
+    let mut buf = *b"attack at dawn";
+    let full_tag = full
+        .encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into())
+        .unwrap();
+    let mut buf = *b"attack at dawn";
+    let tag = truncated
+        .encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into())
+        .unwrap();
+    assert_eq!(tag.as_slice(), &full_tag[..4]);
+}
+
+#[test]
+fn respects_tag_position() {
+    let msg = b"attack at dawn";
+    let full = PrefixDummyAead::new(&KEY.into())
+        .encrypt(&NONCE.into(), &msg[..])
+        .unwrap();
+
+    let prefix = Truncated::<PrefixDummyAead, U4>::new_from_slice(&KEY).unwrap();
+    let ciphertext = prefix.encrypt(&NONCE.into(), &msg[..]).unwrap();
+    assert_eq!(ciphertext.len(), msg.len() + 4);
+    assert_eq!(ciphertext[..4], full[..4]);
+    assert_eq!(ciphertext[4..], full[8..]);
+    assert_eq!(
+        prefix
+            .decrypt(&NONCE.into(), ciphertext.as_slice())
+            .unwrap(),
+        msg
+    );
+
+    let postfix = Truncated::<PostfixDummyAead, U4>::new_from_slice(&KEY).unwrap();
+    let ciphertext = postfix.encrypt(&NONCE.into(), &msg[..]).unwrap();
+    assert_eq!(ciphertext.len(), msg.len() + 4);
+    assert_eq!(
+        postfix
+            .decrypt(&NONCE.into(), ciphertext.as_slice())
+            .unwrap(),
+        msg
+    );
+}
+
+#[test]
+fn decryption_failure_zeroes_output() {
+    let cipher = Truncated::<PostfixDummyAead, U4>::new_from_slice(&KEY).unwrap();
+    let mut buf = *b"attack at dawn";
+    let mut tag = cipher
+        .encrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into())
+        .unwrap();
+
+    tag[3] ^= 1;
+    assert_eq!(
+        cipher.decrypt_inout_detached(&NONCE.into(), b"", buf.as_mut_slice().into(), &tag),
+        Err(Error)
+    );
+    assert_eq!(buf, [0; 14]);
+}
@@ -0,0 +1,93 @@
+//! Tests of the generic stream cipher and universal hash construction.
+#![cfg(all(feature = "dev", feature = "uhf"))]
+mod common;
+
+use aead::{
+    Aead, AeadInOut, Error, KeyInit, Payload,
+    uhf::{MaskedBitLengths, Rfc8439, StreamUhf},
+};
+use common::{
+    cipher::{DummyCore, MAX_BLOCKS},
+    uhf::DummyUhf,
+};
+
+type DummyRfc8439 = StreamUhf<DummyCore, DummyUhf, Rfc8439>;
+type DummyMasked = StreamUhf<DummyCore, DummyUhf, MaskedBitLengths>;
+
+const KEY: [u8; 8] = *b"key bits";
+const NONCE: [u8; 8] = *b"noncenon";
+const PAYLOAD: Payload<'_, '_> = Payload {
+    msg: b"attack at dawn",
+    aad: b"header",
+};
+
+#[test]
+fn round_trip_and_tampering() {
+    let cipher = DummyRfc8439::new(&KEY.into());
+    let ciphertext = cipher.encrypt(&NONCE.into(), PAYLOAD).unwrap();
+    assert_eq!(ciphertext.len(), PAYLOAD.msg.len() + 8);
+
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: PAYLOAD.aad,
+    };
+    assert_eq!(cipher.decrypt(&NONCE.into(), payload).unwrap(), PAYLOAD.msg);
+
+    let wrong_aad = Payload {
+        msg: &ciphertext,
+        aad: b"other",
+    };
+    assert_eq!(cipher.decrypt(&NONCE.into(), wrong_aad), Err(Error));
+
+    for i in 0..ciphertext.len() {
+        let mut tampered = ciphertext.clone();
+        tampered[i] ^= 1;
+        let payload = Payload {
+            msg: &tampered,
+            aad: PAYLOAD.aad,
+        };
+        assert_eq!(cipher.decrypt(&NONCE.into(), payload), Err(Error));
+    }
+}
+
+#[test]
+fn keystream_layout() {
+    let msg = [0u8; 8];
+
+    // The UHF key uses the first keystream block
+    let ciphertext = DummyRfc8439::new(&KEY.into())
+        .encrypt(&NONCE.into(), &msg[..])
+        .unwrap();
+    assert_eq!(ciphertext[..8], DummyCore::keystream_block(&KEY, &NONCE, 1));
+
+    // The UHF key and tag mask use the first two keystream blocks
+    let masked = DummyMasked::new(&KEY.into())
+        .encrypt(&NONCE.into(), &msg[..])
+        .unwrap();
+    assert_eq!(masked[..8], DummyCore::keystream_block(&KEY, &NONCE, 2));
+    assert_ne!(ciphertext[8..], masked[8..]);
+
+    let cipher = DummyMasked::new(&KEY.into());
+    assert_eq!(
+        cipher.decrypt(&NONCE.into(), masked.as_slice()).unwrap(),
+        msg
+    );
+}
+
+#[test]
+fn keystream_exhaustion() {
+    let cipher = DummyRfc8439::new(&KEY.into());
+    let max_len = (MAX_BLOCKS as usize - 1) * 8;
+
+    let mut buf = [0u8; 128];
+    let tag = cipher
+        .encrypt_inout_detached(&NONCE.into(), b"", (&mut buf[..max_len]).into())
+        .unwrap();
+    cipher
+        .decrypt_inout_detached(&NONCE.into(), b"", (&mut buf[..max_len]).into(), &tag)
+        .unwrap();
+    assert_eq!(
+        cipher.encrypt_inout_detached(&NONCE.into(), b"", (&mut buf[..max_len + 1]).into()),
+        Err(Error)
+    );
+}
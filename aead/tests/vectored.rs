@@ -0,0 +1,89 @@
+//! Tests of the vectored encryption and decryption methods.
+#![cfg(feature = "dev")]
+mod common;
+
+use aead::{AeadInOut, Error, KeyInit};
+use common::{PostfixDummyAead, PrefixDummyAead};
+
+const MSG: &[u8; 28] = b"attack at dawn, bring snacks";
+
+fn check<A: AeadInOut + KeyInit>() {
+    let cipher = A::new_from_slice(b"dummykey").unwrap();
+    let nonce = Default::default();
+    let mut buf = *MSG;
+    let tag = cipher
+        .encrypt_inout_detached(&nonce, b"header", buf.as_mut_slice().into())
+        .unwrap();
+
+    let mut msg = *MSG;
+    let (a, rest) = msg.split_at_mut(3);
+    let (b, rest) = rest.split_at_mut(0);
+    let (c, d) = rest.split_at_mut(17);
+    let mut segments = [a, b, c, d];
+    let aad: [&[u8]; 3] = [b"he", b"", b"ader"];
+
+    let vectored_tag = cipher
+        .encrypt_vectored_detached(&nonce, &aad, &mut segments)
+        .unwrap();
+    assert_eq!(vectored_tag, tag);
+    assert_eq!(segments.concat(), buf);
+
+    let mut bad_tag = tag.clone();
+    bad_tag[0] ^= 1;
+    assert_eq!(
+        cipher.decrypt_vectored_detached(&nonce, &aad, &mut segments, &bad_tag),
+        Err(Error)
+    );
+    assert_eq!(segments.concat(), buf);
+
+    cipher
+        .decrypt_vectored_detached(&nonce, &aad, &mut segments, &tag)
+        .unwrap();
+    assert_eq!(segments.concat(), MSG);
+}
+
+#[test]
+fn single_segment_failure() {
+    let cipher = PostfixDummyAead::new_from_slice(b"dummykey").unwrap();
+    let nonce = Default::default();
+    let mut buf = *MSG;
+    let mut tag = cipher
+        .encrypt_vectored_detached(&nonce, &[b"header"], &mut [buf.as_mut_slice()])
+        .unwrap();
+    tag[0] ^= 1;
+
+    // A single segment is decrypted in place, so on failure it's left as
+    // `decrypt_inout_detached` leaves it: the dummy AEAD clears it.
+    assert_eq!(
+        cipher.decrypt_vectored_detached(&nonce, &[b"header"], &mut [buf.as_mut_slice()], &tag),
+        Err(Error)
+    );
+    assert_eq!(buf, [0; 28]);
+}
+
+#[test]
+fn empty_message() {
+    let cipher = PostfixDummyAead::new_from_slice(b"dummykey").unwrap();
+    let nonce = Default::default();
+    let tag = cipher
+        .encrypt_inout_detached(&nonce, b"", (&mut [][..]).into())
+        .unwrap();
+    assert_eq!(
+        cipher.encrypt_vectored_detached(&nonce, &[], &mut []),
+        Ok(tag)
+    );
+    assert_eq!(
+        cipher.decrypt_vectored_detached(&nonce, &[], &mut [], &tag),
+        Ok(())
+    );
+}
+
+#[test]
+fn prefix() {
+    check::<PrefixDummyAead>();
+}
+
+#[test]
+fn postfix() {
+    check::<PostfixDummyAead>();
+}
@@ -0,0 +1,119 @@
+//! Tests of the Wycheproof test harness in the `dev` module.
+#![cfg(feature = "dev")]
+mod common;
+
+use aead::dev::{WycheproofFlags, WycheproofVector, wycheproof_test, wycheproof_test_with_flags};
+use common::PostfixDummyAead;
+
+aead::new_wycheproof_test!(dummy_postfix, "wycheproof_postfix", PostfixDummyAead);
+aead::new_wycheproof_test!(
+    dummy_postfix_flags,
+    "wycheproof_postfix",
+    PostfixDummyAead,
+    WycheproofFlags {
+        skip: &["EmptyMessage"],
+        ..WycheproofFlags::NONE
+    },
+);
+
+const VALID: WycheproofVector = WycheproofVector {
+    tc_id: b"1",
+    key: b"\x01\x02\x03\x04\x05\x06\x07\x08",
+    nonce: b"\x10\x11\x12\x13\x14\x15\x16\x17",
+    aad: b"",
+    msg: b"",
+    ct: b"",
+    tag: b"\x10\x11\x12\x13\x14\x15\x16\x17",
+    result: b"valid",
+    flags: b"EmptyAad,EmptyMessage",
+};
+
+#[test]
+fn results() {
+    assert_eq!(wycheproof_test::<PostfixDummyAead>(&VALID), Ok(true));
+    let invalid = WycheproofVector {
+        result: b"invalid",
+        ..VALID
+    };
+    assert!(wycheproof_test::<PostfixDummyAead>(&invalid).is_err());
+    let acceptable = WycheproofVector {
+        result: b"acceptable",
+        ..VALID
+    };
+    assert_eq!(wycheproof_test::<PostfixDummyAead>(&acceptable), Ok(true));
+    let unknown = WycheproofVector {
+        result: b"unknown",
+        ..VALID
+    };
+    assert!(wycheproof_test::<PostfixDummyAead>(&unknown).is_err());
+}
+
+#[test]
+fn flags() {
+    let skip = WycheproofFlags {
+        skip: &["Unrelated", "EmptyAad"],
+        ..WycheproofFlags::NONE
+    };
+    assert_eq!(
+        wycheproof_test_with_flags::<PostfixDummyAead>(&VALID, &skip),
+        Ok(false)
+    );
+    let skip = WycheproofFlags {
+        skip: &["Empty"],
+        ..WycheproofFlags::NONE
+    };
+    assert_eq!(
+        wycheproof_test_with_flags::<PostfixDummyAead>(&VALID, &skip),
+        Ok(true)
+    );
+
+    let acceptable = WycheproofVector {
+        result: b"acceptable",
+        ..VALID
+    };
+    let valid = WycheproofFlags {
+        valid: &["EmptyMessage"],
+        ..WycheproofFlags::NONE
+    };
+    let invalid = WycheproofFlags {
+        invalid: &["EmptyMessage"],
+        ..WycheproofFlags::NONE
+    };
+    assert_eq!(
+        wycheproof_test_with_flags::<PostfixDummyAead>(&acceptable, &valid),
+        Ok(true)
+    );
+    assert!(wycheproof_test_with_flags::<PostfixDummyAead>(&acceptable, &invalid).is_err());
+    let both = WycheproofFlags {
+        valid: &["EmptyAad"],
+        invalid: &["EmptyMessage"],
+        ..WycheproofFlags::NONE
+    };
+    assert!(wycheproof_test_with_flags::<PostfixDummyAead>(&acceptable, &both).is_err());
+
+    // Expectations only apply to acceptable vectors
+    assert_eq!(
+        wycheproof_test_with_flags::<PostfixDummyAead>(&VALID, &invalid),
+        Ok(true)
+    );
+}
+
+#[test]
+fn unsupported_sizes() {
+    let key = WycheproofVector {
+        key: &[0; 16],
+        ..VALID
+    };
+    assert_eq!(wycheproof_test::<PostfixDummyAead>(&key), Ok(false));
+    let tag = WycheproofVector {
+        tag: &VALID.tag[..4],
+        ..VALID
+    };
+    assert_eq!(wycheproof_test::<PostfixDummyAead>(&tag), Ok(false));
+    let nonce = WycheproofVector {
+        nonce: &VALID.nonce[..7],
+        result: b"invalid",
+        ..VALID
+    };
+    assert_eq!(wycheproof_test::<PostfixDummyAead>(&nonce), Ok(true));
+}
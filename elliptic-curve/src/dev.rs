@@ -9,10 +9,12 @@ use crate::{
     bigint::{Limb, NonZero, U256},
     error::{Error, Result},
     ops::{Invert, LinearCombination, Reduce, ShrAssign},
-    point::{AffineCoordinates, NonIdentity},
-    rand_core::TryRngCore,
+    point::{
+        AffineCoordinates, DecompressPoint, DecompressProjective, NonIdentity, RandomizeCoordinates,
+    },
+    rand_core::{CryptoRng, RngCore, TryRngCore},
     scalar::{FromUintUnchecked, IsHigh},
-    sec1::{CompressedPoint, FromEncodedPoint, ToEncodedPoint},
+    sec1::{CompressedPoint, Coordinates, FromEncodedPoint, ToEncodedPoint},
     subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption},
     zeroize::DefaultIsZeroes,
 };
@@ -62,7 +64,10 @@ pub type ScalarBits = crate::scalar::ScalarBits<MockCurve>;
 /// curve type.
 ///
 /// Note: this type is roughly modeled off of NIST P-256, but does not provide
-/// an actual cure arithmetic implementation.
+/// an actual curve arithmetic implementation. Instead, points which are
+/// multiples of the generator are represented by their discrete logarithm,
+/// i.e. [`ProjectivePoint::FixedBaseOutput`], which gives generic code a
+/// working (but cryptographically worthless) group to be tested against.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
 pub struct MockCurve;
 
@@ -88,6 +93,38 @@ impl AssociatedOid for MockCurve {
     const OID: pkcs8::ObjectIdentifier = pkcs8::ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
 }
 
+/// Deterministic xorshift-based RNG for tests.
+///
+/// Note: this type is NOT cryptographically secure, and merely implements
+/// [`CryptoRng`] so it can be passed to APIs which require one.
+#[derive(Clone, Debug)]
+pub struct MockRng(pub u64);
+
+impl RngCore for MockRng {
+    fn next_u32(&mut self) -> u32 {
+        let [a, b, c, d, ..] = self.next_u64().to_le_bytes();
+        u32::from_le_bytes([a, b, c, d])
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // xorshift64*, with the state kept non-zero
+        let mut x = self.0 | 1;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+impl CryptoRng for MockRng {}
+
 /// Example scalar type
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
 pub struct Scalar(ScalarPrimitive);
@@ -112,7 +149,7 @@ impl Field for Scalar {
     }
 
     fn square(&self) -> Self {
-        unimplemented!();
+        *self * self
     }
 
     fn double(&self) -> Self {
@@ -255,60 +292,64 @@ impl SubAssign<&Scalar> for Scalar {
 impl Mul<Scalar> for Scalar {
     type Output = Scalar;
 
-    fn mul(self, _other: Scalar) -> Scalar {
-        unimplemented!();
+    fn mul(self, other: Scalar) -> Scalar {
+        self.mul(&other)
     }
 }
 
 impl Mul<&Scalar> for Scalar {
     type Output = Scalar;
 
-    fn mul(self, _other: &Scalar) -> Scalar {
-        unimplemented!();
+    fn mul(self, other: &Scalar) -> Scalar {
+        let product = self
+            .0
+            .as_uint()
+            .mul_mod(other.0.as_uint(), &MockCurve::ORDER);
+        Self(ScalarPrimitive::from_uint_unchecked(product))
     }
 }
 
 impl Mul<AffinePoint> for Scalar {
     type Output = ProjectivePoint;
 
-    fn mul(self, _other: AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn mul(self, other: AffinePoint) -> ProjectivePoint {
+        ProjectivePoint::from(other) * self
     }
 }
 
 impl Mul<&AffinePoint> for Scalar {
     type Output = ProjectivePoint;
 
-    fn mul(self, _other: &AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn mul(self, other: &AffinePoint) -> ProjectivePoint {
+        self * *other
     }
 }
 
 impl Mul<ProjectivePoint> for Scalar {
     type Output = ProjectivePoint;
 
-    fn mul(self, _other: ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn mul(self, other: ProjectivePoint) -> ProjectivePoint {
+        other * self
     }
 }
 
 impl Mul<&ProjectivePoint> for Scalar {
     type Output = ProjectivePoint;
 
-    fn mul(self, _other: &ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn mul(self, other: &ProjectivePoint) -> ProjectivePoint {
+        *other * self
     }
 }
 
 impl MulAssign<Scalar> for Scalar {
-    fn mul_assign(&mut self, _rhs: Scalar) {
-        unimplemented!();
+    fn mul_assign(&mut self, rhs: Scalar) {
+        *self = *self * rhs;
     }
 }
 
 impl MulAssign<&Scalar> for Scalar {
-    fn mul_assign(&mut self, _rhs: &Scalar) {
-        unimplemented!();
+    fn mul_assign(&mut self, rhs: &Scalar) {
+        *self = *self * rhs;
     }
 }
 
@@ -467,19 +508,31 @@ impl AffineCoordinates for AffinePoint {
     type FieldRepr = FieldBytes;
 
     fn x(&self) -> FieldBytes {
-        unimplemented!();
+        match self {
+            Self::FixedBaseOutput(scalar) => scalar.to_repr(),
+            _ => unimplemented!(),
+        }
     }
 
     fn y(&self) -> FieldBytes {
-        unimplemented!();
+        match self {
+            Self::FixedBaseOutput(_) => PSEUDO_COORDINATE_FIXED_BASE_MUL.into(),
+            _ => unimplemented!(),
+        }
     }
 
     fn x_is_odd(&self) -> Choice {
-        unimplemented!();
+        match self {
+            Self::FixedBaseOutput(scalar) => scalar.is_odd(),
+            _ => unimplemented!(),
+        }
     }
 
     fn y_is_odd(&self) -> Choice {
-        unimplemented!();
+        match self {
+            Self::FixedBaseOutput(_) => Choice::from(PSEUDO_COORDINATE_FIXED_BASE_MUL[31] & 1),
+            _ => unimplemented!(),
+        }
     }
 }
 
@@ -517,10 +570,33 @@ impl From<NonIdentity<AffinePoint>> for AffinePoint {
     }
 }
 
+impl DecompressPoint<MockCurve> for AffinePoint {
+    fn decompress(x: &FieldBytes, y_is_odd: Choice) -> CtOption<Self> {
+        let mut bytes = CompressedPoint::<MockCurve>::default();
+        bytes[0] = 0x02 | y_is_odd.unwrap_u8();
+        bytes[1..].copy_from_slice(x);
+
+        let point = EncodedPoint::from_bytes(bytes).map(Self::Other);
+        CtOption::new(
+            point.unwrap_or_default(),
+            Choice::from(u8::from(point.is_ok())),
+        )
+    }
+}
+
 impl FromEncodedPoint<MockCurve> for AffinePoint {
     fn from_encoded_point(encoded_point: &EncodedPoint) -> CtOption<Self> {
+        let fixed_base_output = match encoded_point.coordinates() {
+            Coordinates::Uncompressed { x, y } if y[..] == PSEUDO_COORDINATE_FIXED_BASE_MUL => {
+                Scalar::from_repr(*x).into_option()
+            }
+            _ => None,
+        };
+
         let point = if encoded_point.is_identity() {
             Self::Identity
+        } else if let Some(scalar) = fixed_base_output {
+            Self::FixedBaseOutput(scalar)
         } else {
             Self::Other(*encoded_point)
         };
@@ -552,8 +628,8 @@ impl ToEncodedPoint<MockCurve> for AffinePoint {
 impl Mul<NonZeroScalar> for AffinePoint {
     type Output = AffinePoint;
 
-    fn mul(self, _scalar: NonZeroScalar) -> Self {
-        unimplemented!();
+    fn mul(self, scalar: NonZeroScalar) -> Self {
+        (ProjectivePoint::from(self) * *scalar).to_affine()
     }
 }
 
@@ -581,6 +657,29 @@ pub enum ProjectivePoint {
     Other(AffinePoint),
 }
 
+impl ProjectivePoint {
+    /// Discrete logarithm of this point with respect to the generator.
+    ///
+    /// Panics for [`ProjectivePoint::Other`], whose logarithm is unknown.
+    fn log(&self) -> Scalar {
+        match self {
+            Self::FixedBaseOutput(scalar) => *scalar,
+            Self::Identity => Scalar::ZERO,
+            Self::Generator => Scalar::ONE,
+            Self::Other(_) => unimplemented!(),
+        }
+    }
+
+    /// Point with the given discrete logarithm with respect to the generator.
+    fn from_log(scalar: Scalar) -> Self {
+        if scalar.is_zero().into() {
+            Self::Identity
+        } else {
+            Self::FixedBaseOutput(scalar)
+        }
+    }
+}
+
 impl<const N: usize> BatchNormalize<[ProjectivePoint; N]> for ProjectivePoint {
     type Output = [AffinePoint; N];
 
@@ -598,6 +697,19 @@ impl BatchNormalize<[ProjectivePoint]> for ProjectivePoint {
     }
 }
 
+impl DecompressProjective<MockCurve> for ProjectivePoint {
+    fn decompress_projective(x: &FieldBytes, y_is_odd: Choice) -> CtOption<Self> {
+        AffinePoint::decompress(x, y_is_odd).map(Self::from)
+    }
+}
+
+impl RandomizeCoordinates for ProjectivePoint {
+    /// Mock points have no coordinates to randomize.
+    fn randomize_coordinates<R: CryptoRng + ?Sized>(&self, _rng: &mut R) -> Self {
+        *self
+    }
+}
+
 impl ConstantTimeEq for ProjectivePoint {
     fn ct_eq(&self, other: &Self) -> Choice {
         match (self, other) {
@@ -671,8 +783,8 @@ impl TryFrom<ProjectivePoint> for NonIdentity<ProjectivePoint> {
 impl group::Group for ProjectivePoint {
     type Scalar = Scalar;
 
-    fn try_from_rng<R: TryRngCore + ?Sized>(_rng: &mut R) -> core::result::Result<Self, R::Error> {
-        unimplemented!();
+    fn try_from_rng<R: TryRngCore + ?Sized>(rng: &mut R) -> core::result::Result<Self, R::Error> {
+        Scalar::try_from_rng(rng).map(Self::from_log)
     }
 
     fn identity() -> Self {
@@ -688,7 +800,7 @@ impl group::Group for ProjectivePoint {
     }
 
     fn double(&self) -> Self {
-        unimplemented!();
+        Self::from_log(self.log().double())
     }
 }
 
@@ -739,8 +851,9 @@ impl CurveGroup for ProjectivePoint {
     fn to_affine(&self) -> AffinePoint {
         match self {
             Self::FixedBaseOutput(scalar) => AffinePoint::FixedBaseOutput(*scalar),
+            Self::Identity => AffinePoint::Identity,
+            Self::Generator => AffinePoint::Generator,
             Self::Other(affine) => *affine,
-            _ => unimplemented!(),
         }
     }
 }
@@ -751,124 +864,124 @@ impl<const N: usize> LinearCombination<[(ProjectivePoint, Scalar); N]> for Proje
 impl Add<ProjectivePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn add(self, _other: ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn add(self, other: ProjectivePoint) -> ProjectivePoint {
+        Self::from_log(self.log() + other.log())
     }
 }
 
 impl Add<&ProjectivePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn add(self, _other: &ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn add(self, other: &ProjectivePoint) -> ProjectivePoint {
+        Self::from_log(self.log() + other.log())
     }
 }
 
 impl AddAssign<ProjectivePoint> for ProjectivePoint {
-    fn add_assign(&mut self, _rhs: ProjectivePoint) {
-        unimplemented!();
+    fn add_assign(&mut self, rhs: ProjectivePoint) {
+        *self = *self + rhs;
     }
 }
 
 impl AddAssign<&ProjectivePoint> for ProjectivePoint {
-    fn add_assign(&mut self, _rhs: &ProjectivePoint) {
-        unimplemented!();
+    fn add_assign(&mut self, rhs: &ProjectivePoint) {
+        *self = *self + rhs;
     }
 }
 
 impl Sub<ProjectivePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn sub(self, _other: ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn sub(self, other: ProjectivePoint) -> ProjectivePoint {
+        Self::from_log(self.log() - other.log())
     }
 }
 
 impl Sub<&ProjectivePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn sub(self, _other: &ProjectivePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn sub(self, other: &ProjectivePoint) -> ProjectivePoint {
+        Self::from_log(self.log() - other.log())
     }
 }
 
 impl SubAssign<ProjectivePoint> for ProjectivePoint {
-    fn sub_assign(&mut self, _rhs: ProjectivePoint) {
-        unimplemented!();
+    fn sub_assign(&mut self, rhs: ProjectivePoint) {
+        *self = *self - rhs;
     }
 }
 
 impl SubAssign<&ProjectivePoint> for ProjectivePoint {
-    fn sub_assign(&mut self, _rhs: &ProjectivePoint) {
-        unimplemented!();
+    fn sub_assign(&mut self, rhs: &ProjectivePoint) {
+        *self = *self - rhs;
     }
 }
 
 impl Add<AffinePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn add(self, _other: AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn add(self, other: AffinePoint) -> ProjectivePoint {
+        Self::from_log(self.log() + ProjectivePoint::from(other).log())
     }
 }
 
 impl Add<&AffinePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn add(self, _other: &AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn add(self, other: &AffinePoint) -> ProjectivePoint {
+        Self::from_log(self.log() + ProjectivePoint::from(*other).log())
     }
 }
 
 impl AddAssign<AffinePoint> for ProjectivePoint {
-    fn add_assign(&mut self, _rhs: AffinePoint) {
-        unimplemented!();
+    fn add_assign(&mut self, rhs: AffinePoint) {
+        *self = *self + rhs;
     }
 }
 
 impl AddAssign<&AffinePoint> for ProjectivePoint {
-    fn add_assign(&mut self, _rhs: &AffinePoint) {
-        unimplemented!();
+    fn add_assign(&mut self, rhs: &AffinePoint) {
+        *self = *self + rhs;
     }
 }
 
 impl Sum for ProjectivePoint {
-    fn sum<I: Iterator<Item = Self>>(_iter: I) -> Self {
-        unimplemented!();
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::Identity, |acc, point| acc + point)
     }
 }
 
 impl<'a> Sum<&'a ProjectivePoint> for ProjectivePoint {
-    fn sum<I: Iterator<Item = &'a ProjectivePoint>>(_iter: I) -> Self {
-        unimplemented!();
+    fn sum<I: Iterator<Item = &'a ProjectivePoint>>(iter: I) -> Self {
+        iter.fold(Self::Identity, |acc, point| acc + point)
     }
 }
 
 impl Sub<AffinePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn sub(self, _other: AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn sub(self, other: AffinePoint) -> ProjectivePoint {
+        Self::from_log(self.log() - ProjectivePoint::from(other).log())
     }
 }
 
 impl Sub<&AffinePoint> for ProjectivePoint {
     type Output = ProjectivePoint;
 
-    fn sub(self, _other: &AffinePoint) -> ProjectivePoint {
-        unimplemented!();
+    fn sub(self, other: &AffinePoint) -> ProjectivePoint {
+        Self::from_log(self.log() - ProjectivePoint::from(*other).log())
     }
 }
 
 impl SubAssign<AffinePoint> for ProjectivePoint {
-    fn sub_assign(&mut self, _rhs: AffinePoint) {
-        unimplemented!();
+    fn sub_assign(&mut self, rhs: AffinePoint) {
+        *self = *self - rhs;
     }
 }
 
 impl SubAssign<&AffinePoint> for ProjectivePoint {
-    fn sub_assign(&mut self, _rhs: &AffinePoint) {
-        unimplemented!();
+    fn sub_assign(&mut self, rhs: &AffinePoint) {
+        *self = *self - rhs;
     }
 }
 
@@ -876,10 +989,7 @@ impl Mul<Scalar> for ProjectivePoint {
     type Output = ProjectivePoint;
 
     fn mul(self, scalar: Scalar) -> ProjectivePoint {
-        match self {
-            Self::Generator => Self::FixedBaseOutput(scalar),
-            _ => unimplemented!(),
-        }
+        Self::from_log(self.log() * scalar)
     }
 }
 
@@ -892,14 +1002,14 @@ impl Mul<&Scalar> for ProjectivePoint {
 }
 
 impl MulAssign<Scalar> for ProjectivePoint {
-    fn mul_assign(&mut self, _rhs: Scalar) {
-        unimplemented!();
+    fn mul_assign(&mut self, rhs: Scalar) {
+        *self = *self * rhs;
     }
 }
 
 impl MulAssign<&Scalar> for ProjectivePoint {
-    fn mul_assign(&mut self, _rhs: &Scalar) {
-        unimplemented!();
+    fn mul_assign(&mut self, rhs: &Scalar) {
+        *self = *self * rhs;
     }
 }
 
@@ -907,7 +1017,7 @@ impl Neg for ProjectivePoint {
     type Output = ProjectivePoint;
 
     fn neg(self) -> ProjectivePoint {
-        unimplemented!();
+        Self::from_log(-self.log())
     }
 }
 
@@ -0,0 +1,747 @@
+//! Diffie-Hellman based Key Encapsulation Mechanism (DHKEM).
+//!
+//! This module contains a generic implementation of DHKEM(Group, KDF) as
+//! described in [RFC 9180 §4.1], usable with any elliptic curve which
+//! implements the [`CurveArithmetic`] trait, and any [`Digest`] to be used
+//! with HKDF.
+//!
+//! The [`kem`] traits are implemented as follows:
+//!
+//! - [`Encapsulate`] is impl'd on the recipient's [`PublicKey`]
+//! - [`Decapsulate`] is impl'd on the recipient's [`SecretKey`]
+//! - [`AuthEncap`] and [`AuthDecap`] bundle the recipient's key with the
+//!   sender's key to provide authenticated encapsulation.
+//! - [`KeyPairGen`] is impl'd on [`SecretKey`], using the seed as the
+//!   serialized secret scalar.
+//!
+//! [`DhKem`] ties these types together as a [`Kem`], which makes DHKEM usable
+//! with constructions which are generic over a KEM, e.g. HPKE (`kem::hpke`).
+//!
+//! Curve implementations opt into DHKEM by impl'ing [`DhKemId`] for each
+//! registered combination of curve and KDF hash function.
+//!
+//! [RFC 9180 §4.1]: https://www.rfc-editor.org/rfc/rfc9180.html#section-4.1
+
+use crate::{
+    AffinePoint, CurveArithmetic, Error, FieldBytes, FieldBytesSize, PrimeField, PublicKey, Result,
+    Scalar, SecretKey, ecdh,
+    sec1::{
+        FromEncodedPoint, ModulusSize, ToEncodedPoint, UncompressedPoint, UncompressedPointSize,
+    },
+};
+use core::{fmt, marker::PhantomData};
+use digest::{Digest, Output, OutputSizeUser, crypto_common::BlockSizeUser};
+use hybrid_array::typenum::Unsigned;
+use kem::{
+    AuthDecapsulate, AuthEncapsulate, Decapsulate, Decapsulator, EncappedKeySizeUser, Encapsulate,
+    Kem, KeyPairGen, Seed, SharedSecretSizeUser,
+    labeled_kdf::{labeled_expand, labeled_extract},
+};
+use rand_core::CryptoRng;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Elliptic curves which have a registered DHKEM identifier when combined
+/// with the KDF hash function `D`.
+///
+/// See the [HPKE KEM identifiers registry] for the list of identifiers.
+///
+/// [HPKE KEM identifiers registry]: https://www.iana.org/assignments/hpke/hpke.xhtml#hpke-kem-ids
+pub trait DhKemId<D>: CurveArithmetic {
+    /// KEM identifier, e.g. `0x0010` for DHKEM(P-256, HKDF-SHA256).
+    const KEM_ID: u16;
+}
+
+/// Encapsulated key: the serialized ephemeral public key of the sender.
+///
+/// This is the uncompressed SEC1 encoding of the public key, as described in
+/// [RFC 9180 §7.1.1].
+///
+/// [RFC 9180 §7.1.1]: https://www.rfc-editor.org/rfc/rfc9180.html#section-7.1.1
+pub type EncappedKey<C> = UncompressedPoint<C>;
+
+/// Shared secret produced by DHKEM.
+///
+/// Unlike [`ecdh::SharedSecret`], this value is the output of a KDF and is
+/// therefore uniformly random. Its size is the output size of `D`.
+pub struct SharedSecret<D: OutputSizeUser> {
+    /// Computed secret value
+    secret_bytes: Output<D>,
+}
+
+impl<D: OutputSizeUser> SharedSecret<D> {
+    /// Borrow the shared secret value as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.secret_bytes
+    }
+}
+
+impl<D: OutputSizeUser> AsRef<[u8]> for SharedSecret<D> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<D: OutputSizeUser> fmt::Debug for SharedSecret<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedSecret").finish_non_exhaustive()
+    }
+}
+
+impl<D: OutputSizeUser> ZeroizeOnDrop for SharedSecret<D> {}
+
+impl<D: OutputSizeUser> Drop for SharedSecret<D> {
+    fn drop(&mut self) {
+        self.secret_bytes.zeroize()
+    }
+}
+
+/// DHKEM(Group, KDF) using the curve `C` and the KDF hash function `D`.
+pub struct DhKem<C, D>(PhantomData<(C, D)>);
+
+impl<C, D> Kem for DhKem<C, D>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type EncappedKey = EncappedKey<C>;
+    type SharedSecret = SharedSecret<D>;
+    type EncapsulationKey = PublicKey<C>;
+    type DecapsulationKey = SecretKey<C>;
+}
+
+impl<C, D> EncappedKeySizeUser for DhKem<C, D>
+where
+    C: CurveArithmetic,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type EncappedKeySize = UncompressedPointSize<C>;
+}
+
+impl<C, D: OutputSizeUser> SharedSecretSizeUser for DhKem<C, D> {
+    type SharedSecretSize = D::OutputSize;
+}
+
+impl<C, D> fmt::Debug for DhKem<C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DhKem")
+    }
+}
+
+/// Authenticated encapsulation context: the recipient's public key along
+/// with the sender's secret key (`AuthEncap(pkR, skS)`).
+#[derive(Clone, Debug)]
+pub struct AuthEncap<C: CurveArithmetic> {
+    recipient: PublicKey<C>,
+    sender: SecretKey<C>,
+}
+
+impl<C: CurveArithmetic> AuthEncap<C> {
+    /// Create a new authenticated encapsulation context.
+    pub fn new(recipient: PublicKey<C>, sender: SecretKey<C>) -> Self {
+        Self { recipient, sender }
+    }
+}
+
+/// Authenticated decapsulation context: the recipient's secret key along
+/// with the sender's public key (`AuthDecap(enc, skR, pkS)`).
+#[derive(Clone, Debug)]
+pub struct AuthDecap<C: CurveArithmetic> {
+    recipient: SecretKey<C>,
+    sender: PublicKey<C>,
+}
+
+impl<C: CurveArithmetic> AuthDecap<C> {
+    /// Create a new authenticated decapsulation context.
+    pub fn new(recipient: SecretKey<C>, sender: PublicKey<C>) -> Self {
+        Self { recipient, sender }
+    }
+}
+
+impl<C, D> Encapsulate<EncappedKey<C>, SharedSecret<D>> for PublicKey<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Error = Error;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(EncappedKey<C>, SharedSecret<D>)> {
+        encap::<C, D>(&SecretKey::random(rng), self, None)
+    }
+}
+
+impl<C, D> Decapsulate<EncappedKey<C>, SharedSecret<D>> for SecretKey<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Error = Error;
+
+    fn decapsulate(&self, encapsulated_key: &EncappedKey<C>) -> Result<SharedSecret<D>> {
+        decap::<C, D>(encapsulated_key, self, None)
+    }
+}
+
+impl<C, D> Decapsulator<EncappedKey<C>, SharedSecret<D>> for SecretKey<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Encapsulator = PublicKey<C>;
+
+    fn encapsulator(&self) -> PublicKey<C> {
+        self.public_key()
+    }
+}
+
+impl<C, D> Encapsulate<EncappedKey<C>, SharedSecret<D>> for AuthEncap<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Error = Error;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(EncappedKey<C>, SharedSecret<D>)> {
+        encap::<C, D>(&SecretKey::random(rng), &self.recipient, Some(&self.sender))
+    }
+}
+
+impl<C, D> Decapsulate<EncappedKey<C>, SharedSecret<D>> for AuthDecap<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    type Error = Error;
+
+    fn decapsulate(&self, encapsulated_key: &EncappedKey<C>) -> Result<SharedSecret<D>> {
+        decap::<C, D>(encapsulated_key, &self.recipient, Some(&self.sender))
+    }
+}
+
+impl<C, D> AuthEncapsulate<EncappedKey<C>, SharedSecret<D>> for AuthEncap<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+}
+
+impl<C, D> AuthDecapsulate<EncappedKey<C>, SharedSecret<D>> for AuthDecap<C>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+}
+
+impl<C: CurveArithmetic> KeyPairGen for SecretKey<C> {
+    type Encapsulator = PublicKey<C>;
+    type SeedSize = FieldBytesSize<C>;
+    type Error = Error;
+
+    /// Use the seed as the serialized secret scalar, rejecting it if it's out
+    /// of range.
+    ///
+    /// To derive a key pair from arbitrary input keying material as specified
+    /// for DHKEM, use [`derive_key_pair`] instead.
+    fn from_seed(seed: &Seed<Self>) -> Result<(Self, PublicKey<C>)> {
+        let secret_key = SecretKey::from_bytes(seed)?;
+        let public_key = secret_key.public_key();
+        Ok((secret_key, public_key))
+    }
+
+    fn generate<R: CryptoRng + ?Sized>(rng: &mut R) -> (Self, PublicKey<C>) {
+        let secret_key = SecretKey::random(rng);
+        let public_key = secret_key.public_key();
+        (secret_key, public_key)
+    }
+}
+
+/// Deterministically derive a key pair from the input keying material `ikm`
+/// (`DeriveKeyPair(ikm)`), as described in [RFC 9180 §7.1.3].
+///
+/// `ikm` should have at least as many bytes of entropy as the secret key.
+///
+/// [RFC 9180 §7.1.3]: https://www.rfc-editor.org/rfc/rfc9180.html#section-7.1.3
+pub fn derive_key_pair<C, D>(ikm: &[u8]) -> Result<(SecretKey<C>, PublicKey<C>)>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+{
+    let suite_id = suite_id::<C, D>();
+    let (_, dkp_prk) = labeled_extract::<D>(&suite_id, &[], b"dkp_prk", &[ikm]);
+
+    // Mask off the bits of the first byte which exceed the bit length of the order
+    let excess_bits =
+        (FieldBytesSize::<C>::USIZE * 8).saturating_sub(Scalar::<C>::NUM_BITS as usize);
+    let bitmask = 0xFFu8 >> excess_bits;
+
+    for counter in 0..=u8::MAX {
+        let mut bytes = Zeroizing::new(FieldBytes::<C>::default());
+        labeled_expand::<D>(&dkp_prk, &suite_id, b"candidate", &[&[counter]], &mut bytes)
+            .map_err(|_| Error)?;
+        bytes[0] &= bitmask;
+
+        if let Ok(secret_key) = SecretKey::from_bytes(&bytes) {
+            let public_key = secret_key.public_key();
+            return Ok((secret_key, public_key));
+        }
+    }
+
+    Err(Error)
+}
+
+/// `Encap(pkR)` and `AuthEncap(pkR, skS)` using the given ephemeral secret.
+fn encap<C, D>(
+    ephemeral_secret: &SecretKey<C>,
+    recipient: &PublicKey<C>,
+    sender: Option<&SecretKey<C>>,
+) -> Result<(EncappedKey<C>, SharedSecret<D>)>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let enc = serialize_public_key(&ephemeral_secret.public_key())?;
+    let pk_rm = serialize_public_key(recipient)?;
+    let dh = diffie_hellman(ephemeral_secret, recipient);
+
+    let shared_secret = match sender {
+        Some(sender) => {
+            let pk_sm = serialize_public_key(&sender.public_key())?;
+            let dh_static = diffie_hellman(sender, recipient);
+            extract_and_expand::<C, D>(
+                &[dh.raw_secret_bytes(), dh_static.raw_secret_bytes()],
+                &[&enc, &pk_rm, &pk_sm],
+            )?
+        }
+        None => extract_and_expand::<C, D>(&[dh.raw_secret_bytes()], &[&enc, &pk_rm])?,
+    };
+
+    Ok((enc, shared_secret))
+}
+
+/// `Decap(enc, skR)` and `AuthDecap(enc, skR, pkS)`.
+fn decap<C, D>(
+    enc: &EncappedKey<C>,
+    recipient: &SecretKey<C>,
+    sender: Option<&PublicKey<C>>,
+) -> Result<SharedSecret<D>>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let ephemeral_public = PublicKey::<C>::from_sec1_bytes(enc)?;
+    let pk_rm = serialize_public_key(&recipient.public_key())?;
+    let dh = diffie_hellman(recipient, &ephemeral_public);
+
+    match sender {
+        Some(sender) => {
+            let pk_sm = serialize_public_key(sender)?;
+            let dh_static = diffie_hellman(recipient, sender);
+            extract_and_expand::<C, D>(
+                &[dh.raw_secret_bytes(), dh_static.raw_secret_bytes()],
+                &[enc, &pk_rm, &pk_sm],
+            )
+        }
+        None => extract_and_expand::<C, D>(&[dh.raw_secret_bytes()], &[enc, &pk_rm]),
+    }
+}
+
+/// `ExtractAndExpand(dh, kem_context)`.
+///
+/// Both `dh` and `kem_context` are passed as their concatenated components.
+fn extract_and_expand<C, D>(dh: &[&[u8]], kem_context: &[&[u8]]) -> Result<SharedSecret<D>>
+where
+    C: DhKemId<D>,
+    D: BlockSizeUser + Clone + Digest,
+{
+    let suite_id = suite_id::<C, D>();
+    let (_, eae_prk) = labeled_extract::<D>(&suite_id, &[], b"eae_prk", dh);
+
+    let mut secret_bytes = Output::<D>::default();
+    labeled_expand::<D>(
+        &eae_prk,
+        &suite_id,
+        b"shared_secret",
+        kem_context,
+        &mut secret_bytes,
+    )
+    .map_err(|_| Error)?;
+
+    Ok(SharedSecret { secret_bytes })
+}
+
+/// `suite_id = concat("KEM", I2OSP(kem_id, 2))`.
+fn suite_id<C, D>() -> [u8; 5]
+where
+    C: DhKemId<D>,
+{
+    let [hi, lo] = C::KEM_ID.to_be_bytes();
+    [b'K', b'E', b'M', hi, lo]
+}
+
+/// `SerializePublicKey(pk)`: uncompressed SEC1 encoding.
+fn serialize_public_key<C>(public_key: &PublicKey<C>) -> Result<EncappedKey<C>>
+where
+    C: CurveArithmetic,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    EncappedKey::<C>::try_from(public_key.to_encoded_point(false).as_bytes()).map_err(|_| Error)
+}
+
+/// `DH(sk, pk)`: the x-coordinate of the shared point.
+fn diffie_hellman<C>(secret_key: &SecretKey<C>, public_key: &PublicKey<C>) -> ecdh::SharedSecret<C>
+where
+    C: CurveArithmetic,
+{
+    ecdh::diffie_hellman(secret_key.to_nonzero_scalar(), public_key.as_affine())
+}
+
+#[cfg(all(test, feature = "dev"))]
+mod tests {
+    use super::{AuthDecap, AuthEncap, DhKem, DhKemId, EncappedKey, SharedSecret};
+    use super::{derive_key_pair, extract_and_expand};
+    use crate::dev::{MockCurve, MockRng, PublicKey, SecretKey};
+    use aead::{
+        AeadCore, AeadInOut, Key, KeyInit, KeySizeUser, Nonce, Tag, TagPosition,
+        consts::{U12, U16},
+        inout::InOutBuf,
+    };
+    use hex_literal::hex;
+    use kem::{
+        Decapsulate, Decapsulator, EncappedKeySizeUser, Encapsulate, KeyPairGen, Seed,
+        SharedSecretSizeUser,
+        hpke::{CipherSuite, RecipientContext, SenderContext},
+    };
+    use sha2::Sha256;
+
+    /// `MockCurve` shares its order with NIST P-256, so the DHKEM(P-256, HKDF-SHA256)
+    /// test vectors apply to scalar derivation.
+    impl DhKemId<Sha256> for MockCurve {
+        const KEM_ID: u16 = 0x0010;
+    }
+
+    type Encapped = EncappedKey<MockCurve>;
+    type Secret = SharedSecret<Sha256>;
+
+    kem::new_round_trip_test!(round_trip, DhKem<MockCurve, Sha256>);
+
+    /// Dummy (horribly insecure!) AEAD with the sizes of AES-128-GCM: the
+    /// ciphertext is the plaintext XORed with the repeated key, and the tag is
+    /// the key.
+    struct DummyAead([u8; 16]);
+
+    impl DummyAead {
+        fn apply_keystream(&self, mut buffer: InOutBuf<'_, '_, u8>) {
+            for i in 0..buffer.len() {
+                let mut byte = buffer.get(i);
+                *byte.get_out() = *byte.get_in() ^ self.0[i % 16];
+            }
+        }
+    }
+
+    impl KeySizeUser for DummyAead {
+        type KeySize = U16;
+    }
+
+    impl KeyInit for DummyAead {
+        fn new(key: &Key<Self>) -> Self {
+            Self(key.0)
+        }
+    }
+
+    impl AeadCore for DummyAead {
+        type NonceSize = U12;
+        type TagSize = U16;
+        const TAG_POSITION: TagPosition = TagPosition::Postfix;
+    }
+
+    impl AeadInOut for DummyAead {
+        fn encrypt_inout_detached(
+            &self,
+            _nonce: &Nonce<Self>,
+            _aad: &[u8],
+            buffer: InOutBuf<'_, '_, u8>,
+        ) -> aead::Result<Tag<Self>> {
+            self.apply_keystream(buffer);
+            Ok(self.0.into())
+        }
+
+        fn decrypt_inout_detached(
+            &self,
+            _nonce: &Nonce<Self>,
+            _aad: &[u8],
+            buffer: InOutBuf<'_, '_, u8>,
+            tag: &Tag<Self>,
+        ) -> aead::Result<()> {
+            if tag.0 != self.0 {
+                return Err(aead::Error);
+            }
+            self.apply_keystream(buffer);
+            Ok(())
+        }
+    }
+
+    struct TestSuite;
+
+    impl CipherSuite for TestSuite {
+        const KEM_ID: u16 = 0x0010;
+        const KDF_ID: u16 = 0x0001;
+        const AEAD_ID: u16 = 0x0001;
+        type Kem = DhKem<MockCurve, Sha256>;
+        type Kdf = Sha256;
+        type Aead = DummyAead;
+    }
+
+    // RFC 9180 Appendix A.3.1: DHKEM(P-256, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, base mode
+    const IKM_E: [u8; 32] =
+        hex!("4270e54ffd08d79d5928020af4686d8f6b7d35dbe470265f1f5aa22816ce860e");
+    const SK_EM: [u8; 32] =
+        hex!("4995788ef4b9d6132b249ce59a77281493eb39af373d236a1fe415cb0c2d7beb");
+    const PK_EM: [u8; 65] = hex!(
+        "04a92719c6195d5085104f469a8b9814d5838ff72b60501e2c4466e5e67b325ac9"
+        "8536d7b61a1af4b78e5b7f951c0900be863c403ce65c9bfcb9382657222d18c4"
+    );
+    const IKM_R: [u8; 32] =
+        hex!("668b37171f1072f3cf12ea8a236a45df23fc13b82af3609ad1e354f6ef817550");
+    const SK_RM: [u8; 32] =
+        hex!("f3ce7fdae57e1a310d87f1ebbde6f328be0a99cdbcadf4d6589cf29de4b8ffd2");
+    const PK_RM: [u8; 65] = hex!(
+        "04fe8c19ce0905191ebc298a9245792531f26f0cece2460639e8bc39cb7f706a82"
+        "6a779b4cf969b8a0e539c7f62fb3d30ad6aa8f80e30f1d128aafd68a2ce72ea0"
+    );
+    const SHARED_SECRET: [u8; 32] =
+        hex!("c0d26aeab536609a572b07695d933b589dcf363ff9d93c93adea537aeabb8cb8");
+
+    /// `DH(skEm, pkRm)`, which isn't listed in RFC 9180 but is needed since
+    /// `MockCurve` doesn't implement the P-256 group law.
+    const DH: [u8; 32] = hex!("13f918529458d2542531406888c8a6d4ea7ff473a6f4db452ac3c4ae1d01cea1");
+
+    // RFC 9180 Appendix A.3.3: DHKEM(P-256, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, auth mode
+    const AUTH_IKM_E: [u8; 32] =
+        hex!("798d82a8d9ea19dbc7f2c6dfa54e8a6706f7cdc119db0813dacf8440ab37c857");
+    const AUTH_SK_EM: [u8; 32] =
+        hex!("6b8de0873aed0c1b2d09b8c7ed54cbf24fdf1dfc7a47fa501f918810642d7b91");
+    const AUTH_PK_EM: [u8; 65] = hex!(
+        "042224f3ea800f7ec55c03f29fc9865f6ee27004f818fcbdc6dc68932c1e52e15b"
+        "79e264a98f2c535ef06745f3d308624414153b22c7332bc1e691cb4af4d53454"
+    );
+    const AUTH_IKM_R: [u8; 32] =
+        hex!("7bc93bde8890d1fb55220e7f3b0c107ae7e6eda35ca4040bb6651284bf0747ee");
+    const AUTH_SK_RM: [u8; 32] =
+        hex!("d929ab4be2e59f6954d6bedd93e638f02d4046cef21115b00cdda2acb2a4440e");
+    const AUTH_PK_RM: [u8; 65] = hex!(
+        "04423e363e1cd54ce7b7573110ac121399acbc9ed815fae03b72ffbd4c18b01836"
+        "835c5a09513f28fc971b7266cfde2e96afe84bb0f266920e82c4f53b36e1a78d"
+    );
+    const AUTH_IKM_S: [u8; 32] =
+        hex!("874baa0dcf93595a24a45a7f042e0d22d368747daaa7e19f80a802af19204ba8");
+    const AUTH_SK_SM: [u8; 32] =
+        hex!("1120ac99fb1fccc1e8230502d245719d1b217fe20505c7648795139d177f0de9");
+    const AUTH_PK_SM: [u8; 65] = hex!(
+        "04a817a0902bf28e036d66add5d544cc3a0457eab150f104285df1e293b5c10eef"
+        "8651213e43d9cd9086c80b309df22cf37609f58c1127f7607e85f210b2804f73"
+    );
+    const AUTH_SHARED_SECRET: [u8; 32] =
+        hex!("d4aea336439aadf68f9348880aa358086f1480e7c167b6ef15453ba69b94b44f");
+
+    /// `DH(skEm, pkRm)` and `DH(skSm, pkRm)` for the auth mode vector, computed
+    /// like [`DH`].
+    const AUTH_DH: [u8; 32] =
+        hex!("e1ebe65684e166f75b3a291812e4cedeed0a1704b5695029e65b0c3eb3338b4b");
+    const AUTH_DH_STATIC: [u8; 32] =
+        hex!("8e6240455f73a6879fcd4eb5e1d39e91518ab32318866f6dda24b483548f539a");
+
+    #[test]
+    fn derive_key_pair_vectors() {
+        for (ikm, sk) in [
+            (IKM_E, SK_EM),
+            (IKM_R, SK_RM),
+            (AUTH_IKM_E, AUTH_SK_EM),
+            (AUTH_IKM_R, AUTH_SK_RM),
+            (AUTH_IKM_S, AUTH_SK_SM),
+        ] {
+            let (secret_key, _) =
+                derive_key_pair::<MockCurve, Sha256>(&ikm).expect("key derivation failed");
+            assert_eq!(secret_key.to_bytes().as_slice(), &sk);
+        }
+    }
+
+    #[test]
+    fn extract_and_expand_vectors() {
+        let shared_secret = extract_and_expand::<MockCurve, Sha256>(&[&DH], &[&PK_EM, &PK_RM])
+            .expect("key derivation failed");
+        assert_eq!(shared_secret.as_bytes(), &SHARED_SECRET);
+    }
+
+    #[test]
+    fn auth_extract_and_expand_vectors() {
+        let shared_secret = extract_and_expand::<MockCurve, Sha256>(
+            &[&AUTH_DH, &AUTH_DH_STATIC],
+            &[&AUTH_PK_EM, &AUTH_PK_RM, &AUTH_PK_SM],
+        )
+        .expect("key derivation failed");
+        assert_eq!(shared_secret.as_bytes(), &AUTH_SHARED_SECRET);
+    }
+
+    #[test]
+    fn encapsulate_decapsulate() {
+        let mut rng = MockRng(1);
+        let recipient = SecretKey::random(&mut rng);
+
+        let (encapped_key, shared_secret): (Encapped, Secret) = recipient
+            .public_key()
+            .encapsulate(&mut rng)
+            .expect("encapsulation failed");
+        let decapsulated: Secret = recipient
+            .decapsulate(&encapped_key)
+            .expect("decapsulation failed");
+        assert_eq!(decapsulated.as_bytes(), shared_secret.as_bytes());
+
+        let other: Secret = SecretKey::random(&mut rng)
+            .decapsulate(&encapped_key)
+            .expect("decapsulation failed");
+        assert_ne!(other.as_bytes(), shared_secret.as_bytes());
+    }
+
+    #[test]
+    fn auth_encapsulate_decapsulate() {
+        let mut rng = MockRng(2);
+        let recipient = SecretKey::random(&mut rng);
+        let sender = SecretKey::random(&mut rng);
+
+        let (encapped_key, shared_secret): (Encapped, Secret) =
+            AuthEncap::new(recipient.public_key(), sender.clone())
+                .encapsulate(&mut rng)
+                .expect("encapsulation failed");
+        let decapsulated: Secret = AuthDecap::new(recipient.clone(), sender.public_key())
+            .decapsulate(&encapped_key)
+            .expect("decapsulation failed");
+        assert_eq!(decapsulated.as_bytes(), shared_secret.as_bytes());
+
+        // Decapsulating with the wrong sender key, or without authentication,
+        // yields a different shared secret
+        let impostor: PublicKey = SecretKey::random(&mut rng).public_key();
+        let wrong_sender: Secret = AuthDecap::new(recipient.clone(), impostor)
+            .decapsulate(&encapped_key)
+            .expect("decapsulation failed");
+        assert_ne!(wrong_sender.as_bytes(), shared_secret.as_bytes());
+
+        let unauthenticated: Secret = recipient
+            .decapsulate(&encapped_key)
+            .expect("decapsulation failed");
+        assert_ne!(unauthenticated.as_bytes(), shared_secret.as_bytes());
+    }
+
+    #[test]
+    fn key_pair_gen() {
+        let (secret_key, public_key) =
+            SecretKey::from_seed(&SK_RM.into()).expect("valid secret scalar rejected");
+        assert_eq!(secret_key.to_bytes().as_slice(), &SK_RM);
+        assert_eq!(public_key, secret_key.encapsulator());
+
+        // Zero and the group order are out of range
+        assert!(SecretKey::from_seed(&Seed::<SecretKey>::default()).is_err());
+        let order = hex!("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551");
+        assert!(SecretKey::from_seed(&order.into()).is_err());
+
+        let (secret_key, public_key) = SecretKey::generate(&mut MockRng(4));
+        assert_eq!(public_key, secret_key.public_key());
+    }
+
+    #[test]
+    fn kem_sizes() {
+        type Kem = DhKem<MockCurve, Sha256>;
+        assert_eq!(Kem::encapped_key_size(), 65);
+        assert_eq!(Kem::shared_secret_size(), 32);
+
+        let (encapped_key, shared_secret): (Encapped, Secret) = SecretKey::random(&mut MockRng(5))
+            .public_key()
+            .encapsulate(&mut MockRng(6))
+            .expect("encapsulation failed");
+        assert_eq!(encapped_key.len(), Kem::encapped_key_size());
+        assert_eq!(shared_secret.as_bytes().len(), Kem::shared_secret_size());
+    }
+
+    #[test]
+    fn decapsulate_rejects_invalid_encapped_key() {
+        let recipient = SecretKey::random(&mut MockRng(3));
+        let result: crate::Result<Secret> = recipient.decapsulate(&Encapped::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn hpke_round_trip() {
+        let mut rng = MockRng(7);
+        let recipient = SecretKey::random(&mut rng);
+        let sender = SecretKey::random(&mut rng);
+
+        let (encapped_key, mut sender_context) =
+            SenderContext::<TestSuite>::setup_base(&recipient.public_key(), b"info", &mut rng)
+                .expect("sender setup failed");
+        let mut recipient_context =
+            RecipientContext::<TestSuite>::setup_base(&encapped_key, &recipient, b"info")
+                .expect("recipient setup failed");
+
+        let mut buffer = *b"hello";
+        let tag = sender_context
+            .seal_inout_detached(b"aad", (&mut buffer[..]).into())
+            .expect("seal failed");
+        recipient_context
+            .open_inout_detached(b"aad", (&mut buffer[..]).into(), &tag)
+            .expect("open failed");
+        assert_eq!(&buffer, b"hello");
+
+        // In the auth mode, the recipient only derives the sender's secrets
+        // when using the sender's actual public key
+        let (encapped_key, sender_context) = SenderContext::<TestSuite>::setup_auth(
+            &AuthEncap::new(recipient.public_key(), sender.clone()),
+            b"info",
+            &mut rng,
+        )
+        .expect("sender setup failed");
+        let impostor = SecretKey::random(&mut rng).public_key();
+
+        for (sender_public_key, matches) in [(sender.public_key(), true), (impostor, false)] {
+            let recipient_context = RecipientContext::<TestSuite>::setup_auth(
+                &encapped_key,
+                &AuthDecap::new(recipient.clone(), sender_public_key),
+                b"info",
+            )
+            .expect("recipient setup failed");
+
+            let (mut expected, mut exported) = ([0u8; 32], [0u8; 32]);
+            sender_context
+                .export(b"context", &mut expected)
+                .expect("export failed");
+            recipient_context
+                .export(b"context", &mut exported)
+                .expect("export failed");
+            assert_eq!(exported == expected, matches);
+        }
+    }
+}
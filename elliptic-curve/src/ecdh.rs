@@ -23,12 +23,18 @@
 //! Static ECDH key exchanges are supported via the low-level
 //! [`diffie_hellman`] function.
 //!
+//! For deployments where an attacker may share hardware with the victim,
+//! [`diffie_hellman_hardened`] performs the same computation using
+//! side-channel hardened scalar multiplication.
+//!
 //! [AKE]: https://en.wikipedia.org/wiki/Authenticated_Key_Exchange
 //! [SIGMA]: https://www.iacr.org/cryptodb/archive/2003/CRYPTO/1495/1495.pdf
 
 use crate::{
     AffinePoint, Curve, CurveArithmetic, CurveGroup, FieldBytes, NonZeroScalar, ProjectivePoint,
-    PublicKey, point::AffineCoordinates,
+    PublicKey,
+    point::{AffineCoordinates, RandomizeCoordinates},
+    scalar::BlindedScalar,
 };
 use core::{borrow::Borrow, fmt};
 use digest::{Digest, crypto_common::BlockSizeUser};
@@ -68,6 +74,38 @@ where
     SharedSecret::new(secret_point)
 }
 
+/// Low-level Elliptic Curve Diffie-Hellman (ECDH) function using side-channel
+/// hardened scalar multiplication.
+///
+/// This computes the same [`SharedSecret`] as [`diffie_hellman`], but blinds
+/// the secret scalar using the provided [`CryptoRng`] and performs the
+/// multiplication with [`BlindedScalar::mul_hardened`], which also randomizes
+/// the projective coordinates of the public point using the curve's
+/// [`RandomizeCoordinates`] impl.
+///
+/// ```ignore
+/// let shared_secret = elliptic_curve::ecdh::diffie_hellman_hardened(
+///     secret_key.to_nonzero_scalar(),
+///     public_key.as_affine(),
+///     &mut rng
+/// );
+/// ```
+pub fn diffie_hellman_hardened<C, R>(
+    secret_key: impl Borrow<NonZeroScalar<C>>,
+    public_key: impl Borrow<AffinePoint<C>>,
+    rng: &mut R,
+) -> SharedSecret<C>
+where
+    C: CurveArithmetic,
+    ProjectivePoint<C>: RandomizeCoordinates,
+    R: CryptoRng + ?Sized,
+{
+    let public_point = ProjectivePoint::<C>::from(*public_key.borrow());
+    let secret_scalar = BlindedScalar::<C>::new(*secret_key.borrow().as_ref(), rng);
+    let secret_point = secret_scalar.mul_hardened(&public_point, rng).to_affine();
+    SharedSecret::new(secret_point)
+}
+
 /// Ephemeral Diffie-Hellman Secret.
 ///
 /// These are ephemeral "secret key" values which are deliberately designed
@@ -88,6 +88,8 @@ pub mod scalar;
 
 #[cfg(feature = "dev")]
 pub mod dev;
+#[cfg(feature = "dhkem")]
+pub mod dhkem;
 #[cfg(feature = "ecdh")]
 pub mod ecdh;
 #[cfg(feature = "arithmetic")]
@@ -9,6 +9,12 @@ pub use {self::non_identity::NonIdentity, crate::CurveArithmetic};
 use crate::{Curve, FieldBytes};
 use subtle::{Choice, CtOption};
 
+#[cfg(feature = "arithmetic")]
+use {core::array, group::Group, rand_core::CryptoRng};
+
+#[cfg(all(feature = "alloc", feature = "arithmetic"))]
+use alloc::vec::Vec;
+
 /// Affine point type for a given curve with a [`CurveArithmetic`]
 /// implementation.
 #[cfg(feature = "arithmetic")]
@@ -50,6 +56,21 @@ pub trait BatchNormalize<Points: ?Sized> {
     fn batch_normalize(points: &Points) -> <Self as BatchNormalize<Points>>::Output;
 }
 
+/// Randomize the coordinates of a point in projective representation.
+///
+/// Projective coordinates are only defined up to a non-zero factor, so the same
+/// point has many representations. Multiplying all coordinates by a random
+/// non-zero field element (i.e. Coron's countermeasure) decorrelates the
+/// representation of intermediate values from the secret inputs of a scalar
+/// multiplication.
+///
+/// Used by [`BlindedScalar::mul_hardened`][`crate::scalar::BlindedScalar::mul_hardened`].
+#[cfg(feature = "arithmetic")]
+pub trait RandomizeCoordinates: Sized {
+    /// Return a representation of the same point with randomized coordinates.
+    fn randomize_coordinates<R: CryptoRng + ?Sized>(&self, rng: &mut R) -> Self;
+}
+
 /// Double a point (i.e. add it to itself)
 pub trait Double {
     /// Double this point.
@@ -65,6 +86,74 @@ pub trait DecompressPoint<C: Curve>: Sized {
     fn decompress(x: &FieldBytes<C>, y_is_odd: Choice) -> CtOption<Self>;
 }
 
+/// Decompress elliptic curve points into projective representation.
+///
+/// Unlike [`DecompressPoint`], the decompressed point doesn't need to be
+/// normalized, so curves can defer the field inversion needed to compute its
+/// affine coordinates. This allows decompressing a batch of points using a
+/// single [`BatchNormalize`] call, which amortizes the inversions using
+/// [`BatchInvert`][`crate::ops::BatchInvert`].
+#[cfg(feature = "arithmetic")]
+pub trait DecompressProjective<C: CurveArithmetic> {
+    /// Attempt to decompress an elliptic curve point into projective
+    /// representation.
+    fn decompress_projective(x: &FieldBytes<C>, y_is_odd: Choice) -> CtOption<ProjectivePoint<C>>;
+
+    /// Attempt to decompress a batch of elliptic curve points, normalizing
+    /// them all at once.
+    ///
+    /// The validity of each element is reported individually.
+    fn decompress_batch<const N: usize>(
+        coordinates: &[(FieldBytes<C>, Choice); N],
+    ) -> [CtOption<AffinePoint<C>>; N]
+    where
+        ProjectivePoint<C>: BatchNormalize<[ProjectivePoint<C>; N], Output = [AffinePoint<C>; N]>,
+    {
+        let mut is_valid = [Choice::from(0); N];
+        let points: [ProjectivePoint<C>; N] = array::from_fn(|i| {
+            let (x, y_is_odd) = &coordinates[i];
+            let point = Self::decompress_projective(x, *y_is_odd);
+            is_valid[i] = point.is_some();
+
+            // Invalid points are replaced by a valid one so that they don't
+            // affect the normalization of the rest of the batch
+            point.unwrap_or(ProjectivePoint::<C>::generator())
+        });
+
+        let points = ProjectivePoint::<C>::batch_normalize(&points);
+        array::from_fn(|i| CtOption::new(points[i], is_valid[i]))
+    }
+
+    /// Attempt to decompress a batch of elliptic curve points, normalizing
+    /// them all at once.
+    ///
+    /// The validity of each element is reported individually.
+    #[cfg(feature = "alloc")]
+    fn decompress_batch_vec(
+        coordinates: &[(FieldBytes<C>, Choice)],
+    ) -> Vec<CtOption<AffinePoint<C>>>
+    where
+        ProjectivePoint<C>: BatchNormalize<[ProjectivePoint<C>], Output = Vec<AffinePoint<C>>>,
+    {
+        let (points, is_valid): (Vec<ProjectivePoint<C>>, Vec<Choice>) = coordinates
+            .iter()
+            .map(|(x, y_is_odd)| {
+                let point = Self::decompress_projective(x, *y_is_odd);
+                (
+                    point.unwrap_or(ProjectivePoint::<C>::generator()),
+                    point.is_some(),
+                )
+            })
+            .unzip();
+
+        ProjectivePoint::<C>::batch_normalize(points.as_slice())
+            .into_iter()
+            .zip(is_valid)
+            .map(|(point, is_valid)| CtOption::new(point, is_valid))
+            .collect()
+    }
+}
+
 /// Decompact an elliptic curve point from an x-coordinate.
 ///
 /// Decompaction relies on properties of specially-generated keys but provides
@@ -1,13 +1,21 @@
 //! Random blinding support for [`Scalar`]
 
 use super::Scalar;
-use crate::{CurveArithmetic, ops::Invert};
+use crate::{
+    CurveArithmetic, ProjectivePoint, bigint::Encoding, ops::Invert, point::RandomizeCoordinates,
+};
 use core::fmt;
-use group::ff::Field;
+use group::{Group, ff::Field};
 use rand_core::CryptoRng;
-use subtle::CtOption;
+use subtle::{ConditionallySelectable, ConstantTimeEq, CtOption};
 use zeroize::Zeroize;
 
+/// Window size (in bits) used by [`BlindedScalar::mul_hardened`].
+const WINDOW_SIZE: usize = 4;
+
+/// Number of precomputed multiples of the base point for a window of [`WINDOW_SIZE`] bits.
+const WINDOW_TABLE_SIZE: usize = 1 << WINDOW_SIZE;
+
 /// Scalar blinded with a randomly generated masking value.
 ///
 /// This provides a randomly blinded impl of [`Invert`] which is useful for
@@ -15,6 +23,9 @@ use zeroize::Zeroize;
 ///
 /// It implements masked variable-time inversions using Stein's algorithm, which
 /// may be helpful for performance on embedded platforms.
+///
+/// It also provides a side-channel hardened scalar multiplication in the form
+/// of [`BlindedScalar::mul_hardened`].
 #[derive(Clone)]
 pub struct BlindedScalar<C>
 where
@@ -44,6 +55,84 @@ where
             mask: Scalar::<C>::random(rng),
         }
     }
+
+    /// Multiply the given point by this scalar using a side-channel hardened
+    /// fixed-window ladder.
+    ///
+    /// The following countermeasures are applied:
+    ///
+    /// - The scalar is split into the two random-looking shares `k - m` and `m`
+    ///   using the mask `m`, and both shares are processed by the same ladder.
+    /// - The accumulator is offset by a random point, so with overwhelming
+    ///   probability intermediate values don't coincide with the identity or
+    ///   with small multiples of the base point.
+    /// - The projective coordinates of the base point and of every table entry
+    ///   are randomized using the curve's [`RandomizeCoordinates`] impl.
+    /// - Table entries are selected with a constant-time linear scan, and the
+    ///   sequence of group operations only depends on the size of the scalar.
+    ///
+    /// Only the operations provided by [`CurveArithmetic`] and
+    /// [`RandomizeCoordinates`] are used, which makes this slower than a
+    /// curve-specific implementation. It's intended for deployments where the
+    /// attacker may share hardware with the victim (e.g. caches or power
+    /// measurements).
+    pub fn mul_hardened<R: CryptoRng + ?Sized>(
+        &self,
+        point: &ProjectivePoint<C>,
+        rng: &mut R,
+    ) -> ProjectivePoint<C>
+    where
+        ProjectivePoint<C>: RandomizeCoordinates,
+    {
+        let point = point.randomize_coordinates(rng);
+        let mut table = [ProjectivePoint::<C>::identity(); WINDOW_TABLE_SIZE];
+        for i in 1..WINDOW_TABLE_SIZE {
+            table[i] = table[i - 1] + point;
+        }
+        for entry in &mut table {
+            *entry = entry.randomize_coordinates(rng);
+        }
+
+        let mut share = self.scalar - self.mask;
+        let mut share_bytes = Into::<C::Uint>::into(share).to_le_bytes();
+        let mut mask_bytes = Into::<C::Uint>::into(self.mask).to_le_bytes();
+
+        let offset = ProjectivePoint::<C>::random(&mut *rng);
+        let mut acc = offset;
+        let mut offset_multiple = offset;
+
+        for (share_byte, mask_byte) in share_bytes.as_ref().iter().zip(mask_bytes.as_ref()).rev() {
+            for shift in [WINDOW_SIZE, 0] {
+                for _ in 0..WINDOW_SIZE {
+                    acc = acc.double();
+                    offset_multiple = offset_multiple.double();
+                }
+
+                acc += lookup(&table, (share_byte >> shift) & 0xF);
+                acc += lookup(&table, (mask_byte >> shift) & 0xF);
+            }
+        }
+
+        share.zeroize();
+        share_bytes.as_mut().zeroize();
+        mask_bytes.as_mut().zeroize();
+
+        acc - offset_multiple
+    }
+}
+
+/// Select `table[index]` in constant time by scanning the whole table.
+fn lookup<P>(table: &[P; WINDOW_TABLE_SIZE], index: u8) -> P
+where
+    P: ConditionallySelectable + Default,
+{
+    let mut ret = P::default();
+
+    for (i, point) in (0u8..).zip(table.iter()) {
+        ret.conditional_assign(point, i.ct_eq(&index));
+    }
+
+    ret
 }
 
 impl<C> AsRef<Scalar<C>> for BlindedScalar<C>
@@ -79,3 +168,55 @@ where
         self.mask.zeroize();
     }
 }
+
+#[cfg(all(test, feature = "dev"))]
+mod tests {
+    use super::BlindedScalar;
+    use crate::dev::{MockCurve, MockRng, ProjectivePoint, Scalar, SecretKey};
+    use group::{Group, ff::Field};
+
+    #[test]
+    fn mul_hardened_matches_mul() {
+        let mut rng = MockRng(1);
+
+        for _ in 0..16 {
+            let scalar = Scalar::random(&mut rng);
+            let point = ProjectivePoint::random(&mut rng);
+            let blinded = BlindedScalar::<MockCurve>::new(scalar, &mut rng);
+            assert_eq!(blinded.mul_hardened(&point, &mut rng), point * scalar);
+        }
+    }
+
+    #[test]
+    fn public_key_hardened_matches_public_key() {
+        let mut rng = MockRng(2);
+
+        for _ in 0..16 {
+            let secret_key = SecretKey::random(&mut rng);
+            assert_eq!(
+                secret_key.public_key_hardened(&mut rng),
+                secret_key.public_key()
+            );
+        }
+    }
+
+    #[cfg(feature = "ecdh")]
+    #[test]
+    fn diffie_hellman_hardened_matches_diffie_hellman() {
+        use crate::ecdh::{diffie_hellman, diffie_hellman_hardened};
+
+        let mut rng = MockRng(3);
+
+        for _ in 0..16 {
+            let secret_key = SecretKey::random(&mut rng);
+            let public_key = SecretKey::random(&mut rng).public_key();
+            let expected = diffie_hellman(secret_key.to_nonzero_scalar(), public_key.as_affine());
+            let hardened = diffie_hellman_hardened(
+                secret_key.to_nonzero_scalar(),
+                public_key.as_affine(),
+                &mut rng,
+            );
+            assert_eq!(hardened.raw_secret_bytes(), expected.raw_secret_bytes());
+        }
+    }
+}
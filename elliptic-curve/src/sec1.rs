@@ -9,7 +9,17 @@ use hybrid_array::Array;
 use subtle::CtOption;
 
 #[cfg(feature = "arithmetic")]
-use crate::{AffinePoint, CurveArithmetic, Error};
+use {
+    crate::{
+        AffinePoint, BatchNormalize, CurveArithmetic, Error, FieldBytes, ProjectivePoint,
+        point::DecompressProjective,
+    },
+    core::array,
+    subtle::{Choice, ConstantTimeEq},
+};
+
+#[cfg(all(feature = "alloc", feature = "arithmetic"))]
+use alloc::vec::Vec;
 
 /// Encoded elliptic curve point with point compression.
 pub type CompressedPoint<C> = Array<u8, CompressedPointSize<C>>;
@@ -112,3 +122,168 @@ where
         }
     }
 }
+
+/// Decode a batch of SEC1 compressed points.
+///
+/// Decompression is performed by [`DecompressProjective::decompress_batch`],
+/// which normalizes all points at once using [`BatchNormalize`].
+///
+/// The validity of each element is reported individually: an element is
+/// `None` if it has an invalid tag or doesn't decompress to a curve point.
+#[cfg(feature = "arithmetic")]
+pub fn decode_compressed_batch<C, const N: usize>(
+    points: &[CompressedPoint<C>; N],
+) -> [CtOption<AffinePoint<C>>; N]
+where
+    C: CurveArithmetic,
+    FieldBytesSize<C>: ModulusSize,
+    ProjectivePoint<C>: DecompressProjective<C>
+        + BatchNormalize<[ProjectivePoint<C>; N], Output = [AffinePoint<C>; N]>,
+{
+    let mut tag_is_valid = [Choice::from(0); N];
+    let coordinates = array::from_fn(|i| {
+        let (x, y_is_odd, is_valid) = split_compressed_point::<C>(&points[i]);
+        tag_is_valid[i] = is_valid;
+        (x, y_is_odd)
+    });
+
+    let mut decoded = ProjectivePoint::<C>::decompress_batch(&coordinates);
+    for (point, is_valid) in decoded.iter_mut().zip(tag_is_valid) {
+        *point = point.and_then(|point| CtOption::new(point, is_valid));
+    }
+
+    decoded
+}
+
+/// Decode a batch of SEC1 compressed points.
+///
+/// Decompression is performed by [`DecompressProjective::decompress_batch_vec`],
+/// which normalizes all points at once using [`BatchNormalize`].
+///
+/// The validity of each element is reported individually: an element is
+/// `None` if it has an invalid tag or doesn't decompress to a curve point.
+#[cfg(all(feature = "alloc", feature = "arithmetic"))]
+pub fn decode_compressed_batch_vec<C>(
+    points: &[CompressedPoint<C>],
+) -> Vec<CtOption<AffinePoint<C>>>
+where
+    C: CurveArithmetic,
+    FieldBytesSize<C>: ModulusSize,
+    ProjectivePoint<C>: DecompressProjective<C>
+        + BatchNormalize<[ProjectivePoint<C>], Output = Vec<AffinePoint<C>>>,
+{
+    let (coordinates, tag_is_valid): (Vec<_>, Vec<_>) = points
+        .iter()
+        .map(|point| {
+            let (x, y_is_odd, is_valid) = split_compressed_point::<C>(point);
+            ((x, y_is_odd), is_valid)
+        })
+        .unzip();
+
+    ProjectivePoint::<C>::decompress_batch_vec(&coordinates)
+        .into_iter()
+        .zip(tag_is_valid)
+        .map(|(point, is_valid)| point.and_then(|point| CtOption::new(point, is_valid)))
+        .collect()
+}
+
+/// Encode a batch of projective points as SEC1 compressed points.
+///
+/// The points are first converted to affine coordinates using
+/// [`BatchNormalize`], which amortizes the cost of the inversions.
+///
+/// The identity point is encoded as [`EncodedPoint::identity`].
+#[cfg(feature = "arithmetic")]
+pub fn encode_compressed_batch<C, const N: usize>(
+    points: &[ProjectivePoint<C>; N],
+) -> [EncodedPoint<C>; N]
+where
+    C: CurveArithmetic,
+    AffinePoint<C>: ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+    ProjectivePoint<C>: BatchNormalize<[ProjectivePoint<C>; N], Output = [AffinePoint<C>; N]>,
+{
+    ProjectivePoint::<C>::batch_normalize(points).map(|point| point.to_encoded_point(true))
+}
+
+/// Encode a batch of projective points as SEC1 compressed points.
+///
+/// The points are first converted to affine coordinates using
+/// [`BatchNormalize`], which amortizes the cost of the inversions.
+///
+/// The identity point is encoded as [`EncodedPoint::identity`].
+#[cfg(all(feature = "alloc", feature = "arithmetic"))]
+pub fn encode_compressed_batch_vec<C>(points: &[ProjectivePoint<C>]) -> Vec<EncodedPoint<C>>
+where
+    C: CurveArithmetic,
+    AffinePoint<C>: ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+    ProjectivePoint<C>: BatchNormalize<[ProjectivePoint<C>], Output = Vec<AffinePoint<C>>>,
+{
+    ProjectivePoint::<C>::batch_normalize(points)
+        .iter()
+        .map(|point| point.to_encoded_point(true))
+        .collect()
+}
+
+/// Split a SEC1 compressed point into its x-coordinate and y-coordinate
+/// parity, checking the tag in constant time.
+#[cfg(feature = "arithmetic")]
+fn split_compressed_point<C>(point: &CompressedPoint<C>) -> (FieldBytes<C>, Choice, Choice)
+where
+    C: Curve,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let tag = point[0];
+    let is_valid =
+        tag.ct_eq(&(Tag::CompressedEvenY as u8)) | tag.ct_eq(&(Tag::CompressedOddY as u8));
+
+    let mut x = FieldBytes::<C>::default();
+    x.copy_from_slice(&point[1..]);
+
+    (x, Choice::from(tag & 1), is_valid)
+}
+
+#[cfg(all(test, feature = "dev"))]
+mod tests {
+    use super::{CompressedPoint, decode_compressed_batch, encode_compressed_batch};
+    use crate::dev::{MockCurve, ProjectivePoint};
+    use hex_literal::hex;
+
+    const COMPRESSED_POINT: [u8; 33] =
+        hex!("02c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+
+    #[test]
+    fn decode_batch() {
+        let mut invalid_tag = COMPRESSED_POINT;
+        invalid_tag[0] = 0x04;
+
+        let points: [CompressedPoint<MockCurve>; 2] = [COMPRESSED_POINT.into(), invalid_tag.into()];
+        let [valid, invalid] = decode_compressed_batch::<MockCurve, 2>(&points);
+
+        assert!(bool::from(valid.is_some()));
+        assert!(bool::from(invalid.is_none()));
+    }
+
+    #[test]
+    fn round_trip_batch() {
+        let points: [CompressedPoint<MockCurve>; 2] =
+            [COMPRESSED_POINT.into(), COMPRESSED_POINT.into()];
+        let decoded = decode_compressed_batch::<MockCurve, 2>(&points)
+            .map(|point| ProjectivePoint::from(point.unwrap()));
+
+        for encoded in encode_compressed_batch::<MockCurve, 2>(&decoded) {
+            assert_eq!(encoded.as_bytes(), COMPRESSED_POINT);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn decode_batch_vec() {
+        let points = [CompressedPoint::<MockCurve>::from(COMPRESSED_POINT); 3];
+        let decoded = super::decode_compressed_batch_vec::<MockCurve>(&points);
+
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded.iter().all(|point| bool::from(point.is_some())));
+    }
+}
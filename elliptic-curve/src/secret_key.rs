@@ -16,8 +16,10 @@ use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[cfg(feature = "arithmetic")]
 use crate::{
-    CurveArithmetic, NonZeroScalar, PublicKey,
+    CurveArithmetic, CurveGroup, Group, NonZeroScalar, ProjectivePoint, PublicKey,
+    point::{NonIdentity, RandomizeCoordinates},
     rand_core::{CryptoRng, TryCryptoRng},
+    scalar::BlindedScalar,
 };
 
 #[cfg(feature = "pem")]
@@ -155,6 +157,29 @@ where
         PublicKey::from_secret_scalar(&self.to_nonzero_scalar())
     }
 
+    /// Get the [`PublicKey`] which corresponds to this secret key, using
+    /// side-channel hardened scalar multiplication.
+    ///
+    /// The secret scalar is blinded using the provided [`CryptoRng`], and the
+    /// multiplication is performed by [`BlindedScalar::mul_hardened`], which
+    /// also randomizes the projective coordinates of the generator using the
+    /// curve's [`RandomizeCoordinates`] impl.
+    ///
+    /// This is considerably slower than [`SecretKey::public_key`] and is
+    /// intended for deployments on shared hardware.
+    #[cfg(feature = "arithmetic")]
+    pub fn public_key_hardened<R: CryptoRng + ?Sized>(&self, rng: &mut R) -> PublicKey<C>
+    where
+        C: CurveArithmetic,
+        ProjectivePoint<C>: RandomizeCoordinates,
+    {
+        let scalar = BlindedScalar::<C>::new(*self.to_nonzero_scalar(), rng);
+        let point = scalar.mul_hardened(&ProjectivePoint::<C>::generator(), rng);
+
+        // `NonZeroScalar` ensures the resulting point is not the identity
+        NonIdentity::new_unchecked(point.to_affine()).into()
+    }
+
     /// Deserialize secret key from an encoded secret scalar.
     pub fn from_bytes(bytes: &FieldBytes<C>) -> Result<Self> {
         let inner = ScalarPrimitive::<C>::from_bytes(bytes)
@@ -0,0 +1,362 @@
+//! Development-related functionality
+use crate::{
+    Decapsulate, Decapsulator, EncappedKeySizeUser, Encapsulate, Kem, KeyPairGen, Seed,
+    SharedSecretSizeUser,
+};
+use alloc::vec::Vec;
+pub use blobby;
+use core::num::Wrapping;
+use rand_core::{CryptoRng, RngCore};
+
+/// KEM known-answer test vector
+#[derive(Debug, Clone, Copy)]
+pub struct TestVector {
+    /// Key generation seed
+    pub seed: &'static [u8],
+    /// Randomness consumed by encapsulation
+    pub randomness: &'static [u8],
+    /// Encapsulation key
+    pub ek: &'static [u8],
+    /// Encapsulated key
+    pub ct: &'static [u8],
+    /// Shared secret
+    pub ss: &'static [u8],
+}
+
+/// RNG which replays a fixed sequence of bytes, used to make encapsulation
+/// deterministic in known-answer tests.
+///
+/// Panics if more bytes are requested than available.
+#[derive(Debug)]
+pub struct ReplayRng<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ReplayRng<'a> {
+    /// Create a new RNG which outputs `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Number of bytes which have not been consumed yet.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl RngCore for ReplayRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        assert!(
+            dst.len() <= self.bytes.len(),
+            "test vector randomness exhausted"
+        );
+        let (head, tail) = self.bytes.split_at(dst.len());
+        dst.copy_from_slice(head);
+        self.bytes = tail;
+    }
+}
+
+impl CryptoRng for ReplayRng<'_> {}
+
+/// Xorshift RNG used for tests. Based on the `rand_xorshift` crate.
+///
+/// It is NOT cryptographically secure and implements [`CryptoRng`] only to be
+/// usable with the KEM traits in tests and benchmarks.
+#[derive(Debug)]
+pub struct TestRng {
+    x: Wrapping<u32>,
+    y: Wrapping<u32>,
+    z: Wrapping<u32>,
+    w: Wrapping<u32>,
+}
+
+impl Default for TestRng {
+    fn default() -> Self {
+        // chosen by fair dice roll. guaranteed to be random.
+        Self {
+            x: Wrapping(0x0787_3B4A),
+            y: Wrapping(0xFAAB_8FFE),
+            z: Wrapping(0x1745_980F),
+            w: Wrapping(0xB0AD_B4F3),
+        }
+    }
+}
+
+impl RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.x;
+        let t = x ^ (x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        let w = self.w;
+        self.w = w ^ (w >> 19) ^ (t ^ (t >> 8));
+        self.w.0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl CryptoRng for TestRng {}
+
+/// Run KEM known-answer test for the provided test vector.
+///
+/// The key pair is derived from the seed using [`KeyPairGen::from_seed`], and
+/// encapsulation is driven by a [`ReplayRng`] which must consume exactly the
+/// test vector randomness.
+pub fn kat_test<K: Kem>(
+    &TestVector {
+        seed,
+        randomness,
+        ek,
+        ct,
+        ss,
+    }: &TestVector,
+) -> Result<(), &'static str>
+where
+    K::DecapsulationKey: KeyPairGen<Encapsulator = K::EncapsulationKey>,
+    K::EncapsulationKey: AsRef<[u8]>,
+    K::EncappedKey: AsRef<[u8]> + for<'a> TryFrom<&'a [u8]>,
+    K::SharedSecret: AsRef<[u8]>,
+{
+    let seed = Seed::<K::DecapsulationKey>::try_from(seed).map_err(|_| "wrong seed size")?;
+    let (dk, encapsulator) =
+        K::DecapsulationKey::from_seed(&seed).map_err(|_| "seed rejected by key generation")?;
+    if encapsulator.as_ref() != ek {
+        return Err("encapsulation key mismatch");
+    }
+    if dk.encapsulator().as_ref() != ek {
+        return Err("decapsulator returned a different encapsulation key");
+    }
+
+    let mut rng = ReplayRng::new(randomness);
+    let (calc_ct, calc_ss) = encapsulator
+        .encapsulate(&mut rng)
+        .map_err(|_| "encapsulation failure")?;
+    if rng.remaining() != 0 {
+        return Err("encapsulation did not consume all randomness");
+    }
+    if calc_ct.as_ref() != ct {
+        return Err("encapsulated key mismatch");
+    }
+    if calc_ss.as_ref() != ss {
+        return Err("encapsulation shared secret mismatch");
+    }
+
+    let ct = K::EncappedKey::try_from(ct).map_err(|_| "wrong encapsulated key size")?;
+    let calc_ss = dk.decapsulate(&ct).map_err(|_| "decapsulation failure")?;
+    if calc_ss.as_ref() != ss {
+        return Err("decapsulation shared secret mismatch");
+    }
+
+    Ok(())
+}
+
+/// Check that decapsulating a freshly encapsulated key returns the same shared
+/// secret, and that the sizes match the [`EncappedKeySizeUser`] and
+/// [`SharedSecretSizeUser`] impls of the KEM.
+pub fn round_trip_test<K, R>(dk: &K::DecapsulationKey, rng: &mut R) -> Result<(), &'static str>
+where
+    K: Kem + EncappedKeySizeUser + SharedSecretSizeUser,
+    K::EncappedKey: AsRef<[u8]>,
+    K::SharedSecret: AsRef<[u8]>,
+    R: CryptoRng + ?Sized,
+{
+    let (ct, ss) = dk
+        .encapsulator()
+        .encapsulate(rng)
+        .map_err(|_| "encapsulation failure")?;
+    if ct.as_ref().len() != K::encapped_key_size() {
+        return Err("encapsulated key size mismatch");
+    }
+    if ss.as_ref().len() != K::shared_secret_size() {
+        return Err("shared secret size mismatch");
+    }
+
+    let calc_ss = dk.decapsulate(&ct).map_err(|_| "decapsulation failure")?;
+    if calc_ss.as_ref() != ss.as_ref() {
+        return Err("shared secret mismatch");
+    }
+
+    Ok(())
+}
+
+/// Check that tampered encapsulated keys are implicitly rejected: decapsulation
+/// must succeed, deterministically returning a shared secret which differs from
+/// the encapsulated one.
+pub fn implicit_rejection_test<K: Kem, R: CryptoRng + ?Sized>(
+    dk: &K::DecapsulationKey,
+    rng: &mut R,
+) -> Result<(), &'static str>
+where
+    K::EncappedKey: AsRef<[u8]> + for<'a> TryFrom<&'a [u8]>,
+    K::SharedSecret: AsRef<[u8]>,
+{
+    let (ct, ss) = dk
+        .encapsulator()
+        .encapsulate(rng)
+        .map_err(|_| "encapsulation failure")?;
+    let ct = ct.as_ref();
+    if ct.is_empty() {
+        return Err("empty encapsulated key");
+    }
+
+    for i in [0, ct.len() / 2, ct.len() - 1] {
+        let mut tampered = Vec::from(ct);
+        tampered[i] ^= 1;
+        let tampered = K::EncappedKey::try_from(tampered.as_slice())
+            .map_err(|_| "wrong encapsulated key size")?;
+
+        let ss1 = dk
+            .decapsulate(&tampered)
+            .map_err(|_| "tampered encapsulated key was explicitly rejected")?;
+        if ss1.as_ref() == ss.as_ref() {
+            return Err("tampered encapsulated key produced the same shared secret");
+        }
+
+        let ss2 = dk
+            .decapsulate(&tampered)
+            .map_err(|_| "tampered encapsulated key was explicitly rejected")?;
+        if ss1.as_ref() != ss2.as_ref() {
+            return Err("implicit rejection is not deterministic");
+        }
+    }
+
+    Ok(())
+}
+
+/// Define KEM known-answer test
+#[macro_export]
+macro_rules! new_test {
+    ($name:ident, $test_name:expr, $kem:ty $(,)?) => {
+        #[test]
+        fn $name() {
+            use $crate::dev::TestVector;
+
+            $crate::dev::blobby::parse_into_structs!(
+                include_bytes!(concat!("data/", $test_name, ".blb"));
+                static TEST_VECTORS: &[
+                    TestVector { seed, randomness, ek, ct, ss }
+                ];
+            );
+
+            for (i, tv) in TEST_VECTORS.iter().enumerate() {
+                if let Err(reason) = $crate::dev::kat_test::<$kem>(tv) {
+                    panic!(
+                        "\n\
+                        Failed test #{i}\n\
+                        reason:\t{reason:?}\n\
+                        test vector:\t{tv:?}\n"
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Define KEM round-trip test over randomly generated key pairs
+#[macro_export]
+macro_rules! new_round_trip_test {
+    ($name:ident, $kem:ty $(,)?) => {
+        #[test]
+        fn $name() {
+            use $crate::{Kem, KeyPairGen};
+
+            let mut rng = $crate::dev::TestRng::default();
+            for i in 0..16 {
+                let (dk, _) = <<$kem as Kem>::DecapsulationKey as KeyPairGen>::generate(&mut rng);
+                if let Err(reason) = $crate::dev::round_trip_test::<$kem, _>(&dk, &mut rng) {
+                    panic!("\nFailed round trip #{i}\nreason:\t{reason:?}\n");
+                }
+            }
+        }
+    };
+}
+
+/// Define KEM implicit rejection test over randomly generated key pairs
+#[macro_export]
+macro_rules! new_implicit_rejection_test {
+    ($name:ident, $kem:ty $(,)?) => {
+        #[test]
+        fn $name() {
+            use $crate::{Kem, KeyPairGen};
+
+            let mut rng = $crate::dev::TestRng::default();
+            for i in 0..16 {
+                let (dk, _) = <<$kem as Kem>::DecapsulationKey as KeyPairGen>::generate(&mut rng);
+                if let Err(reason) = $crate::dev::implicit_rejection_test::<$kem, _>(&dk, &mut rng)
+                {
+                    panic!("\nFailed implicit rejection #{i}\nreason:\t{reason:?}\n");
+                }
+            }
+        }
+    };
+}
+
+/// Define KEM key generation benchmark
+#[macro_export]
+macro_rules! bench_keygen {
+    ($name:ident, $kem:ty $(,)?) => {
+        #[bench]
+        pub fn $name(bh: &mut test::Bencher) {
+            use $crate::{Kem, KeyPairGen};
+
+            let mut rng = $crate::dev::TestRng::default();
+            bh.iter(|| {
+                let key_pair = <<$kem as Kem>::DecapsulationKey as KeyPairGen>::generate(&mut rng);
+                test::black_box(key_pair);
+            });
+        }
+    };
+}
+
+/// Define KEM encapsulation benchmark
+#[macro_export]
+macro_rules! bench_encapsulate {
+    ($name:ident, $kem:ty $(,)?) => {
+        #[bench]
+        pub fn $name(bh: &mut test::Bencher) {
+            use $crate::{Encapsulate, Kem, KeyPairGen};
+
+            let mut rng = $crate::dev::TestRng::default();
+            let (_, ek) = <<$kem as Kem>::DecapsulationKey as KeyPairGen>::generate(&mut rng);
+            bh.iter(|| {
+                let res = test::black_box(&ek).encapsulate(&mut rng);
+                test::black_box(res.expect("encapsulation failure"));
+            });
+        }
+    };
+}
+
+/// Define KEM decapsulation benchmark
+#[macro_export]
+macro_rules! bench_decapsulate {
+    ($name:ident, $kem:ty $(,)?) => {
+        #[bench]
+        pub fn $name(bh: &mut test::Bencher) {
+            use $crate::{Decapsulate, Encapsulate, Kem, KeyPairGen};
+
+            let mut rng = $crate::dev::TestRng::default();
+            let (dk, ek) = <<$kem as Kem>::DecapsulationKey as KeyPairGen>::generate(&mut rng);
+            let (ct, _) = ek.encapsulate(&mut rng).expect("encapsulation failure");
+            bh.iter(|| {
+                let res = test::black_box(&dk).decapsulate(test::black_box(&ct));
+                test::black_box(res.expect("decapsulation failure"));
+            });
+        }
+    };
+}
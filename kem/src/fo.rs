@@ -0,0 +1,406 @@
+//! Fujisaki–Okamoto transform.
+//!
+//! [`Fo`] turns an IND-CPA secure public-key encryption scheme ([`Pke`]) into
+//! an IND-CCA secure [`Kem`], using the FO transform with implicit rejection
+//! (`FO^⊥̸` in [HHK17]):
+//!
+//! - `Encaps(pk)`: `m ← random`, `c = Enc(pk, m; G(H(pk) || m))` and
+//!   `K = H(m || c)`.
+//! - `Decaps(sk, c)`: `m' = Dec(sk, c)` and `c' = Enc(pk, m'; G(H(pk) || m'))`.
+//!   If `c == c'` then `K = H(m' || c)`, otherwise `K = H(z || c)` where `z` is
+//!   a secret random value stored in the decapsulation key.
+//!
+//! `G` is an extendable output function used to derive the encryption coins
+//! and `H` is a hash function used to hash the public key and derive the shared
+//! secret. The re-encryption check and the selection between `m'` and `z` are
+//! performed in constant time.
+//!
+//! [HHK17]: https://eprint.iacr.org/2017/604
+
+use crate::{Decapsulate, Decapsulator, Encapsulate, Kem};
+use core::{convert::Infallible, fmt, marker::PhantomData};
+use crypto_common::array::{Array, ArraySize};
+use digest::{Digest, ExtendableOutput, Output, Update, XofReader};
+use rand_core::CryptoRng;
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Message encrypted by a [`Pke`].
+pub type Message<P> = Array<u8, <P as Pke>::MessageSize>;
+
+/// Random coins used by [`Pke::encrypt`].
+pub type Coins<P> = Array<u8, <P as Pke>::CoinsSize>;
+
+/// Public-key encryption scheme with deterministic encryption given explicit
+/// random coins.
+///
+/// This is usually implemented on a marker type.
+pub trait Pke {
+    /// Public key.
+    type PublicKey;
+
+    /// Secret key.
+    ///
+    /// It is zeroized when the [`FoDecapsulationKey`] holding it is dropped.
+    type SecretKey: Zeroize;
+
+    /// Ciphertext.
+    type Ciphertext: AsRef<[u8]>;
+
+    /// Message size in bytes.
+    type MessageSize: ArraySize;
+
+    /// Size of the random coins used by encryption in bytes.
+    type CoinsSize: ArraySize;
+
+    /// Generate a random key pair using the provided [`CryptoRng`].
+    fn generate_keypair<R: CryptoRng + ?Sized>(rng: &mut R) -> (Self::SecretKey, Self::PublicKey);
+
+    /// Encrypt the message `m` to `pk` using the random coins `coins`.
+    ///
+    /// Encryption must be deterministic given `coins`.
+    fn encrypt(pk: &Self::PublicKey, m: &Message<Self>, coins: &Coins<Self>) -> Self::Ciphertext;
+
+    /// Decrypt the ciphertext `c` using `sk`.
+    ///
+    /// Schemes whose decryption can fail may return an arbitrary message in
+    /// this case: it will be rejected by the re-encryption check.
+    fn decrypt(sk: &Self::SecretKey, c: &Self::Ciphertext) -> Message<Self>;
+}
+
+/// KEM built from the [`Pke`] `P` with the Fujisaki–Okamoto transform, using
+/// the extendable output function `G` and the hash function `H`.
+pub struct Fo<P, G, H>(PhantomData<(P, G, H)>);
+
+impl<P, G, H> Kem for Fo<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: AsRef<[u8]> + Clone,
+    G: Default + ExtendableOutput + Update,
+    H: Digest,
+{
+    type EncappedKey = P::Ciphertext;
+    type SharedSecret = FoSharedSecret<H>;
+    type EncapsulationKey = FoEncapsulationKey<P, G, H>;
+    type DecapsulationKey = FoDecapsulationKey<P, G, H>;
+}
+
+impl<P, G, H> fmt::Debug for Fo<P, G, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Fo")
+    }
+}
+
+/// Shared secret of an [`Fo`] KEM.
+pub struct FoSharedSecret<H: Digest>(Output<H>);
+
+impl<H: Digest> FoSharedSecret<H> {
+    /// Shared secret value, serialized as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<H: Digest> AsRef<[u8]> for FoSharedSecret<H> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<H: Digest> fmt::Debug for FoSharedSecret<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FoSharedSecret").finish_non_exhaustive()
+    }
+}
+
+impl<H: Digest> Drop for FoSharedSecret<H> {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl<H: Digest> ZeroizeOnDrop for FoSharedSecret<H> {}
+
+/// Encapsulation key of an [`Fo`] KEM.
+pub struct FoEncapsulationKey<P: Pke, G, H: Digest> {
+    pk: P::PublicKey,
+    pk_hash: Output<H>,
+    _pd: PhantomData<G>,
+}
+
+impl<P, G, H> FoEncapsulationKey<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: AsRef<[u8]>,
+    H: Digest,
+{
+    /// Create a new encapsulation key from a [`Pke`] public key.
+    pub fn new(pk: P::PublicKey) -> Self {
+        let pk_hash = H::digest(pk.as_ref());
+        Self {
+            pk,
+            pk_hash,
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<P: Pke, G, H: Digest> FoEncapsulationKey<P, G, H> {
+    /// Get the [`Pke`] public key.
+    pub fn public_key(&self) -> &P::PublicKey {
+        &self.pk
+    }
+}
+
+impl<P, G, H> FoEncapsulationKey<P, G, H>
+where
+    P: Pke,
+    G: Default + ExtendableOutput + Update,
+    H: Digest,
+{
+    /// Encrypt `m` with coins derived from it.
+    fn encrypt(&self, m: &Message<P>) -> P::Ciphertext {
+        let mut coins = Coins::<P>::default();
+        G::default()
+            .chain(&self.pk_hash)
+            .chain(m)
+            .finalize_xof()
+            .read(&mut coins);
+
+        let c = P::encrypt(&self.pk, m, &coins);
+        coins.as_mut_slice().zeroize();
+        c
+    }
+}
+
+impl<P, G, H> Encapsulate<P::Ciphertext, FoSharedSecret<H>> for FoEncapsulationKey<P, G, H>
+where
+    P: Pke,
+    G: Default + ExtendableOutput + Update,
+    H: Digest,
+{
+    type Error = Infallible;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(P::Ciphertext, FoSharedSecret<H>), Infallible> {
+        let mut m = Message::<P>::default();
+        rng.fill_bytes(&mut m);
+
+        let c = self.encrypt(&m);
+        let k = derive_key::<H>(&m, &c);
+        m.as_mut_slice().zeroize();
+        Ok((c, k))
+    }
+}
+
+impl<P, G, H> Clone for FoEncapsulationKey<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: Clone,
+    H: Digest,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pk: self.pk.clone(),
+            pk_hash: self.pk_hash.clone(),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<P, G, H> fmt::Debug for FoEncapsulationKey<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: fmt::Debug,
+    H: Digest,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FoEncapsulationKey")
+            .field("pk", &self.pk)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decapsulation key of an [`Fo`] KEM.
+pub struct FoDecapsulationKey<P: Pke, G, H: Digest> {
+    sk: P::SecretKey,
+    ek: FoEncapsulationKey<P, G, H>,
+    z: Message<P>,
+}
+
+impl<P, G, H> FoDecapsulationKey<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: AsRef<[u8]>,
+    H: Digest,
+{
+    /// Create a new decapsulation key from a [`Pke`] key pair and the
+    /// implicit rejection secret `z`, which must be uniformly random.
+    pub fn new(sk: P::SecretKey, pk: P::PublicKey, z: Message<P>) -> Self {
+        Self {
+            sk,
+            ek: FoEncapsulationKey::new(pk),
+            z,
+        }
+    }
+
+    /// Generate a random key pair using the provided [`CryptoRng`].
+    pub fn generate<R: CryptoRng + ?Sized>(rng: &mut R) -> (Self, FoEncapsulationKey<P, G, H>)
+    where
+        P::PublicKey: Clone,
+    {
+        let (sk, pk) = P::generate_keypair(rng);
+        let mut z = Message::<P>::default();
+        rng.fill_bytes(&mut z);
+
+        let dk = Self::new(sk, pk, z);
+        let ek = dk.ek.clone();
+        (dk, ek)
+    }
+}
+
+impl<P: Pke, G, H: Digest> FoDecapsulationKey<P, G, H> {
+    /// Get the matching encapsulation key.
+    pub fn encapsulation_key(&self) -> &FoEncapsulationKey<P, G, H> {
+        &self.ek
+    }
+}
+
+impl<P, G, H> Decapsulate<P::Ciphertext, FoSharedSecret<H>> for FoDecapsulationKey<P, G, H>
+where
+    P: Pke,
+    G: Default + ExtendableOutput + Update,
+    H: Digest,
+{
+    type Error = Infallible;
+
+    fn decapsulate(
+        &self,
+        encapsulated_key: &P::Ciphertext,
+    ) -> Result<FoSharedSecret<H>, Infallible> {
+        let mut m = P::decrypt(&self.sk, encapsulated_key);
+        let c = self.ek.encrypt(&m);
+        let valid = encapsulated_key.as_ref().ct_eq(c.as_ref());
+
+        for (m, z) in m.iter_mut().zip(self.z.iter()) {
+            m.conditional_assign(z, !valid);
+        }
+
+        let k = derive_key::<H>(&m, encapsulated_key);
+        m.as_mut_slice().zeroize();
+        Ok(k)
+    }
+}
+
+impl<P, G, H> Decapsulator<P::Ciphertext, FoSharedSecret<H>> for FoDecapsulationKey<P, G, H>
+where
+    P: Pke,
+    P::PublicKey: Clone,
+    G: Default + ExtendableOutput + Update,
+    H: Digest,
+{
+    type Encapsulator = FoEncapsulationKey<P, G, H>;
+
+    fn encapsulator(&self) -> FoEncapsulationKey<P, G, H> {
+        self.ek.clone()
+    }
+}
+
+impl<P: Pke, G, H: Digest> fmt::Debug for FoDecapsulationKey<P, G, H>
+where
+    P::PublicKey: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FoDecapsulationKey")
+            .field("ek", &self.ek)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Pke, G, H: Digest> Drop for FoDecapsulationKey<P, G, H> {
+    fn drop(&mut self) {
+        self.sk.zeroize();
+        self.z.as_mut_slice().zeroize();
+    }
+}
+
+impl<P: Pke, G, H: Digest> ZeroizeOnDrop for FoDecapsulationKey<P, G, H> {}
+
+/// Derive the shared secret as `H(m || c)`.
+fn derive_key<H: Digest>(m: &[u8], c: &impl AsRef<[u8]>) -> FoSharedSecret<H> {
+    FoSharedSecret(H::new().chain_update(m).chain_update(c).finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{array::typenum::U16, test_utils::CounterRng};
+    use sha3::{Sha3_256, Shake128};
+
+    /// Insecure toy PKE: `c = (coins, m ^ key ^ coins)`.
+    struct ToyPke;
+
+    impl Pke for ToyPke {
+        type PublicKey = [u8; 16];
+        type SecretKey = [u8; 16];
+        type Ciphertext = [u8; 32];
+        type MessageSize = U16;
+        type CoinsSize = U16;
+
+        fn generate_keypair<R: CryptoRng + ?Sized>(rng: &mut R) -> ([u8; 16], [u8; 16]) {
+            let mut key = [0u8; 16];
+            rng.fill_bytes(&mut key);
+            (key, key)
+        }
+
+        fn encrypt(pk: &[u8; 16], m: &Message<Self>, coins: &Coins<Self>) -> [u8; 32] {
+            let mut c = [0u8; 32];
+            c[..16].copy_from_slice(coins);
+            for i in 0..16 {
+                c[16 + i] = m[i] ^ pk[i] ^ coins[i];
+            }
+            c
+        }
+
+        fn decrypt(sk: &[u8; 16], c: &[u8; 32]) -> Message<Self> {
+            Array::from_fn(|i| c[16 + i] ^ sk[i] ^ c[i])
+        }
+    }
+
+    type TestKem = Fo<ToyPke, Shake128, Sha3_256>;
+
+    #[test]
+    fn round_trip() {
+        let mut rng = CounterRng(0);
+        let (dk, ek) = <TestKem as Kem>::DecapsulationKey::generate(&mut rng);
+        let (c, k1) = ek.encapsulate(&mut rng).unwrap();
+        let k2 = dk.decapsulate(&c).unwrap();
+        assert_eq!(k1.as_bytes(), k2.as_bytes());
+    }
+
+    #[test]
+    fn secrets_zeroize_on_drop() {
+        fn assert_zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        assert_zeroize_on_drop::<<TestKem as Kem>::DecapsulationKey>();
+        assert_zeroize_on_drop::<<TestKem as Kem>::SharedSecret>();
+    }
+
+    #[test]
+    fn implicit_rejection() {
+        let mut rng = CounterRng(0);
+        let (dk, ek) = <TestKem as Kem>::DecapsulationKey::generate(&mut rng);
+        let (mut c, k) = ek.encapsulate(&mut rng).unwrap();
+
+        // Tampering with the ciphertext decrypts to a different message whose
+        // re-encryption doesn't match.
+        c[31] ^= 1;
+        let rejected = dk.decapsulate(&c).unwrap();
+        assert_ne!(rejected.as_bytes(), k.as_bytes());
+        assert_eq!(
+            rejected.as_bytes(),
+            derive_key::<Sha3_256>(&dk.z, &c).as_bytes()
+        );
+    }
+}
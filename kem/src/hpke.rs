@@ -0,0 +1,864 @@
+//! Hybrid Public Key Encryption (HPKE) as described in [RFC 9180].
+//!
+//! HPKE combines a KEM, a KDF and an AEAD into a public key encryption
+//! scheme. This module is generic over:
+//!
+//! - any [`Kem`] with keys implementing [`Encapsulate`] and [`Decapsulate`],
+//! - any AEAD implementing [`AeadInOut`] and [`KeyInit`],
+//! - any [`Digest`] to be used with HKDF.
+//!
+//! The combination of algorithms is described by a [`CipherSuite`], which also
+//! carries the algorithm identifiers from the [HPKE IANA registry].
+//!
+//! All four modes are supported:
+//!
+//! | Mode        | Sender setup                      | Recipient setup                      |
+//! |-------------|-----------------------------------|--------------------------------------|
+//! | `Base`      | [`SenderContext::setup_base`]     | [`RecipientContext::setup_base`]     |
+//! | `Psk`       | [`SenderContext::setup_psk`]      | [`RecipientContext::setup_psk`]      |
+//! | `Auth`      | [`SenderContext::setup_auth`]     | [`RecipientContext::setup_auth`]     |
+//! | `AuthPsk`   | [`SenderContext::setup_auth_psk`] | [`RecipientContext::setup_auth_psk`] |
+//!
+//! The `Auth` and `AuthPsk` modes require authenticated encapsulation, i.e.
+//! an [`AuthEncapsulate`] impl which includes the sender's secret key and an
+//! [`AuthDecapsulate`] impl which includes the sender's public key
+//! (`AuthEncap` and `AuthDecap` in RFC 9180 terms).
+//!
+//! [RFC 9180]: https://www.rfc-editor.org/rfc/rfc9180.html
+//! [HPKE IANA registry]: https://www.iana.org/assignments/hpke/hpke.xhtml
+
+use crate::{
+    AuthDecapsulate, AuthEncapsulate, Decapsulate, EncappedKeySizeUser, Encapsulate, Kem,
+    SharedSecretSizeUser,
+    labeled_kdf::{labeled_expand, labeled_extract},
+};
+use aead::{
+    AeadCore, AeadInOut, Buffer, Key, KeyInit, Nonce, Tag, array::typenum::Unsigned,
+    inout::InOutBuf,
+};
+use core::fmt;
+use digest::{Digest, Output, crypto_common::BlockSizeUser};
+use hkdf::{Hkdf, hmac::SimpleHmac};
+use rand_core::CryptoRng;
+use zeroize::Zeroize;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// HPKE errors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// KEM encapsulation failed.
+    Encap,
+
+    /// KEM decapsulation failed.
+    Decap,
+
+    /// AEAD encryption failed.
+    Seal,
+
+    /// AEAD decryption failed, e.g. the ciphertext is not authentic.
+    Open,
+
+    /// The PSK inputs are inconsistent with the mode, or with each other.
+    InconsistentPsk,
+
+    /// The sequence number of the context has been exhausted.
+    MessageLimitReached,
+
+    /// The requested output length is too large for the KDF.
+    InvalidLength,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::Encap => "HPKE encapsulation error",
+            Error::Decap => "HPKE decapsulation error",
+            Error::Seal => "HPKE seal error",
+            Error::Open => "HPKE open error",
+            Error::InconsistentPsk => "HPKE inconsistent PSK inputs",
+            Error::MessageLimitReached => "HPKE message limit reached",
+            Error::InvalidLength => "HPKE invalid output length",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Result type alias with [`Error`].
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// HPKE modes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum Mode {
+    /// Encryption to a public key.
+    Base = 0x00,
+
+    /// Encryption to a public key, authenticated with a pre-shared key.
+    Psk = 0x01,
+
+    /// Encryption to a public key, authenticated with the sender's key.
+    Auth = 0x02,
+
+    /// Encryption to a public key, authenticated with both the sender's key
+    /// and a pre-shared key.
+    AuthPsk = 0x03,
+}
+
+impl Mode {
+    /// Does this mode use a pre-shared key?
+    fn uses_psk(self) -> bool {
+        matches!(self, Mode::Psk | Mode::AuthPsk)
+    }
+}
+
+/// HPKE cipher suite: a combination of KEM, KDF and AEAD.
+///
+/// This trait is intended to be impl'd by a ZST.
+pub trait CipherSuite {
+    /// KEM identifier, e.g. `0x0010` for DHKEM(P-256, HKDF-SHA256).
+    const KEM_ID: u16;
+
+    /// KDF identifier, e.g. `0x0001` for HKDF-SHA256.
+    const KDF_ID: u16;
+
+    /// AEAD identifier, e.g. `0x0001` for AES-128-GCM.
+    const AEAD_ID: u16;
+
+    /// KEM algorithm.
+    ///
+    /// Its [`EncappedKeySizeUser`] and [`SharedSecretSizeUser`] impls provide
+    /// `Nenc` and `Nsecret`.
+    type Kem: Kem<SharedSecret: AsRef<[u8]>> + EncappedKeySizeUser + SharedSecretSizeUser;
+
+    /// Hash function used to instantiate HKDF.
+    type Kdf: BlockSizeUser + Clone + Digest;
+
+    /// AEAD algorithm.
+    type Aead: AeadInOut + KeyInit;
+
+    /// Suite identifier: `concat("HPKE", I2OSP(kem_id, 2), I2OSP(kdf_id, 2), I2OSP(aead_id, 2))`.
+    fn suite_id() -> [u8; 10] {
+        let [kem_hi, kem_lo] = Self::KEM_ID.to_be_bytes();
+        let [kdf_hi, kdf_lo] = Self::KDF_ID.to_be_bytes();
+        let [aead_hi, aead_lo] = Self::AEAD_ID.to_be_bytes();
+        [
+            b'H', b'P', b'K', b'E', kem_hi, kem_lo, kdf_hi, kdf_lo, aead_hi, aead_lo,
+        ]
+    }
+}
+
+/// Encapsulated key produced by the KEM of the [`CipherSuite`] `S`.
+pub type EncappedKey<S> = <<S as CipherSuite>::Kem as Kem>::EncappedKey;
+
+/// Shared secret produced by the KEM of the [`CipherSuite`] `S`.
+pub type SharedSecret<S> = <<S as CipherSuite>::Kem as Kem>::SharedSecret;
+
+/// Encryption context shared by the sender and the recipient.
+struct Context<S: CipherSuite> {
+    aead: S::Aead,
+    base_nonce: Nonce<S::Aead>,
+    seq: u64,
+    exporter_secret: Output<S::Kdf>,
+}
+
+impl<S: CipherSuite> Context<S> {
+    /// `KeySchedule<ROLE>(mode, shared_secret, info, psk, psk_id)`.
+    fn new(
+        mode: Mode,
+        shared_secret: &[u8],
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Self> {
+        if psk.is_empty() != psk_id.is_empty() || psk.is_empty() == mode.uses_psk() {
+            return Err(Error::InconsistentPsk);
+        }
+
+        let suite_id = S::suite_id();
+        let psk_id_hash = labeled_extract::<S::Kdf>(&suite_id, &[], b"psk_id_hash", &[psk_id]).0;
+        let info_hash = labeled_extract::<S::Kdf>(&suite_id, &[], b"info_hash", &[info]).0;
+        let key_schedule_context: &[&[u8]] = &[&[mode as u8], &psk_id_hash, &info_hash];
+
+        let (_, secret) = labeled_extract::<S::Kdf>(&suite_id, shared_secret, b"secret", &[psk]);
+
+        let mut key = Key::<S::Aead>::default();
+        labeled_expand(&secret, &suite_id, b"key", key_schedule_context, &mut key)
+            .map_err(|_| Error::InvalidLength)?;
+        let aead = S::Aead::new(&key);
+        key.as_mut_slice().zeroize();
+
+        let mut base_nonce = Nonce::<S::Aead>::default();
+        labeled_expand(
+            &secret,
+            &suite_id,
+            b"base_nonce",
+            key_schedule_context,
+            &mut base_nonce,
+        )
+        .map_err(|_| Error::InvalidLength)?;
+
+        let mut exporter_secret = Output::<S::Kdf>::default();
+        labeled_expand(
+            &secret,
+            &suite_id,
+            b"exp",
+            key_schedule_context,
+            &mut exporter_secret,
+        )
+        .map_err(|_| Error::InvalidLength)?;
+
+        Ok(Self {
+            aead,
+            base_nonce,
+            seq: 0,
+            exporter_secret,
+        })
+    }
+
+    /// `ComputeNonce(seq)`.
+    fn compute_nonce(&self) -> Nonce<S::Aead> {
+        let mut nonce = self.base_nonce.clone();
+        let seq = self.seq.to_be_bytes();
+
+        for (nonce_byte, seq_byte) in nonce.iter_mut().rev().zip(seq.iter().rev()) {
+            *nonce_byte ^= seq_byte;
+        }
+
+        nonce
+    }
+
+    /// `IncrementSeq()`.
+    fn increment_seq(&mut self) -> Result<()> {
+        let nonce_bits = <S::Aead as AeadCore>::NonceSize::U32.saturating_mul(8);
+        let limit = 1u64
+            .checked_shl(nonce_bits)
+            .map_or(u64::MAX, |limit| limit - 1);
+
+        if self.seq >= limit {
+            return Err(Error::MessageLimitReached);
+        }
+
+        self.seq += 1;
+        Ok(())
+    }
+
+    fn seal_inout_detached(
+        &mut self,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<S::Aead>> {
+        let tag = self
+            .aead
+            .encrypt_inout_detached(&self.compute_nonce(), aad, buffer)
+            .map_err(|_| Error::Seal)?;
+        self.increment_seq()?;
+        Ok(tag)
+    }
+
+    fn open_inout_detached(
+        &mut self,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<S::Aead>,
+    ) -> Result<()> {
+        self.aead
+            .decrypt_inout_detached(&self.compute_nonce(), aad, buffer, tag)
+            .map_err(|_| Error::Open)?;
+        self.increment_seq()
+    }
+
+    fn seal_in_place(&mut self, aad: &[u8], buffer: &mut dyn Buffer) -> Result<()> {
+        self.aead
+            .encrypt_in_place(&self.compute_nonce(), aad, buffer)
+            .map_err(|_| Error::Seal)?;
+        self.increment_seq()
+    }
+
+    fn open_in_place(&mut self, aad: &[u8], buffer: &mut dyn Buffer) -> Result<()> {
+        self.aead
+            .decrypt_in_place(&self.compute_nonce(), aad, buffer)
+            .map_err(|_| Error::Open)?;
+        self.increment_seq()
+    }
+
+    /// `Context.Export(exporter_context, L)`.
+    fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<()> {
+        let suite_id = S::suite_id();
+        let exporter = Hkdf::<S::Kdf, SimpleHmac<S::Kdf>>::from_prk(&self.exporter_secret)
+            .map_err(|_| Error::InvalidLength)?;
+        labeled_expand(&exporter, &suite_id, b"sec", &[exporter_context], out)
+            .map_err(|_| Error::InvalidLength)
+    }
+}
+
+impl<S: CipherSuite> Drop for Context<S> {
+    fn drop(&mut self) {
+        self.base_nonce.as_mut_slice().zeroize();
+        self.exporter_secret.as_mut_slice().zeroize();
+    }
+}
+
+/// HPKE sender context, used to encrypt messages to a recipient.
+pub struct SenderContext<S: CipherSuite> {
+    context: Context<S>,
+}
+
+impl<S: CipherSuite> SenderContext<S> {
+    /// Set up a sender context in the `Base` mode (`SetupBaseS`).
+    ///
+    /// Returns the encapsulated key to be sent to the recipient along with
+    /// the context.
+    pub fn setup_base<K, R>(
+        recipient: &K,
+        info: &[u8],
+        rng: &mut R,
+    ) -> Result<(EncappedKey<S>, Self)>
+    where
+        K: Encapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+        R: CryptoRng + ?Sized,
+    {
+        Self::setup(Mode::Base, recipient, info, &[], &[], rng)
+    }
+
+    /// Set up a sender context in the `Psk` mode (`SetupPSKS`).
+    pub fn setup_psk<K, R>(
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        rng: &mut R,
+    ) -> Result<(EncappedKey<S>, Self)>
+    where
+        K: Encapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+        R: CryptoRng + ?Sized,
+    {
+        Self::setup(Mode::Psk, recipient, info, psk, psk_id, rng)
+    }
+
+    /// Set up a sender context in the `Auth` mode (`SetupAuthS`).
+    ///
+    /// `recipient` performs authenticated encapsulation using the sender's
+    /// secret key.
+    pub fn setup_auth<K, R>(
+        recipient: &K,
+        info: &[u8],
+        rng: &mut R,
+    ) -> Result<(EncappedKey<S>, Self)>
+    where
+        K: AuthEncapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+        R: CryptoRng + ?Sized,
+    {
+        Self::setup(Mode::Auth, recipient, info, &[], &[], rng)
+    }
+
+    /// Set up a sender context in the `AuthPsk` mode (`SetupAuthPSKS`).
+    ///
+    /// `recipient` performs authenticated encapsulation using the sender's
+    /// secret key.
+    pub fn setup_auth_psk<K, R>(
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        rng: &mut R,
+    ) -> Result<(EncappedKey<S>, Self)>
+    where
+        K: AuthEncapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+        R: CryptoRng + ?Sized,
+    {
+        Self::setup(Mode::AuthPsk, recipient, info, psk, psk_id, rng)
+    }
+
+    fn setup<K, R>(
+        mode: Mode,
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+        rng: &mut R,
+    ) -> Result<(EncappedKey<S>, Self)>
+    where
+        K: Encapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+        R: CryptoRng + ?Sized,
+    {
+        let (encapped_key, shared_secret) = recipient.encapsulate(rng).map_err(|_| Error::Encap)?;
+        if shared_secret.as_ref().len() != S::Kem::shared_secret_size() {
+            return Err(Error::Encap);
+        }
+
+        let context = Context::new(mode, shared_secret.as_ref(), info, psk, psk_id)?;
+        Ok((encapped_key, Self { context }))
+    }
+
+    /// Encrypt the data in the provided [`InOutBuf`] using the next nonce of
+    /// the sequence, returning the authentication tag.
+    pub fn seal_inout_detached(
+        &mut self,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+    ) -> Result<Tag<S::Aead>> {
+        self.context.seal_inout_detached(aad, buffer)
+    }
+
+    /// Encrypt the given buffer containing a plaintext message in-place using
+    /// the next nonce of the sequence.
+    pub fn seal_in_place(&mut self, aad: &[u8], buffer: &mut dyn Buffer) -> Result<()> {
+        self.context.seal_in_place(aad, buffer)
+    }
+
+    /// Encrypt the given plaintext using the next nonce of the sequence
+    /// (`Context.Seal(aad, pt)`).
+    #[cfg(feature = "alloc")]
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut buffer = Vec::from(plaintext);
+        self.seal_in_place(aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Derive a secret of `out.len()` bytes from the context
+    /// (`Context.Export(exporter_context, L)`).
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<()> {
+        self.context.export(exporter_context, out)
+    }
+}
+
+impl<S: CipherSuite> fmt::Debug for SenderContext<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SenderContext").finish_non_exhaustive()
+    }
+}
+
+/// HPKE recipient context, used to decrypt messages from a sender.
+pub struct RecipientContext<S: CipherSuite> {
+    context: Context<S>,
+}
+
+impl<S: CipherSuite> RecipientContext<S> {
+    /// Set up a recipient context in the `Base` mode (`SetupBaseR`).
+    pub fn setup_base<K>(encapped_key: &EncappedKey<S>, recipient: &K, info: &[u8]) -> Result<Self>
+    where
+        K: Decapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+    {
+        Self::setup(Mode::Base, encapped_key, recipient, info, &[], &[])
+    }
+
+    /// Set up a recipient context in the `Psk` mode (`SetupPSKR`).
+    pub fn setup_psk<K>(
+        encapped_key: &EncappedKey<S>,
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Self>
+    where
+        K: Decapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+    {
+        Self::setup(Mode::Psk, encapped_key, recipient, info, psk, psk_id)
+    }
+
+    /// Set up a recipient context in the `Auth` mode (`SetupAuthR`).
+    ///
+    /// `recipient` performs authenticated decapsulation using the sender's
+    /// public key.
+    pub fn setup_auth<K>(encapped_key: &EncappedKey<S>, recipient: &K, info: &[u8]) -> Result<Self>
+    where
+        K: AuthDecapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+    {
+        Self::setup(Mode::Auth, encapped_key, recipient, info, &[], &[])
+    }
+
+    /// Set up a recipient context in the `AuthPsk` mode (`SetupAuthPSKR`).
+    ///
+    /// `recipient` performs authenticated decapsulation using the sender's
+    /// public key.
+    pub fn setup_auth_psk<K>(
+        encapped_key: &EncappedKey<S>,
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Self>
+    where
+        K: AuthDecapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+    {
+        Self::setup(Mode::AuthPsk, encapped_key, recipient, info, psk, psk_id)
+    }
+
+    fn setup<K>(
+        mode: Mode,
+        encapped_key: &EncappedKey<S>,
+        recipient: &K,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Self>
+    where
+        K: Decapsulate<EncappedKey<S>, SharedSecret<S>> + ?Sized,
+    {
+        let shared_secret = recipient
+            .decapsulate(encapped_key)
+            .map_err(|_| Error::Decap)?;
+        if shared_secret.as_ref().len() != S::Kem::shared_secret_size() {
+            return Err(Error::Decap);
+        }
+
+        let context = Context::new(mode, shared_secret.as_ref(), info, psk, psk_id)?;
+        Ok(Self { context })
+    }
+
+    /// Decrypt the data in the provided [`InOutBuf`] using the next nonce of
+    /// the sequence, returning an error if the tag is invalid.
+    pub fn open_inout_detached(
+        &mut self,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<S::Aead>,
+    ) -> Result<()> {
+        self.context.open_inout_detached(aad, buffer, tag)
+    }
+
+    /// Decrypt the given buffer containing a ciphertext message in-place using
+    /// the next nonce of the sequence.
+    pub fn open_in_place(&mut self, aad: &[u8], buffer: &mut dyn Buffer) -> Result<()> {
+        self.context.open_in_place(aad, buffer)
+    }
+
+    /// Decrypt the given ciphertext using the next nonce of the sequence
+    /// (`Context.Open(aad, ct)`).
+    #[cfg(feature = "alloc")]
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let mut buffer = Vec::from(ciphertext);
+        self.open_in_place(aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Derive a secret of `out.len()` bytes from the context
+    /// (`Context.Export(exporter_context, L)`).
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<()> {
+        self.context.export(exporter_context, out)
+    }
+}
+
+impl<S: CipherSuite> fmt::Debug for RecipientContext<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecipientContext").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CipherSuite, Context, Mode, RecipientContext, SenderContext};
+    use crate::{
+        AuthDecapsulate, AuthEncapsulate, Decapsulate, Decapsulator, EncappedKeySizeUser,
+        Encapsulate, Kem, SharedSecretSizeUser,
+        test_utils::{CounterRng, DummyAead},
+        typenum::U32,
+    };
+    use core::convert::Infallible;
+    use hex_literal::hex;
+    use rand_core::CryptoRng;
+    use sha2::Sha256;
+
+    const INFO: [u8; 20] = hex!("4f6465206f6e2061204772656369616e2055726e");
+    const PSK: [u8; 32] = hex!("0247fd33b913760fa1fa51e1892d9f307fbe65eb171e8132c2af18555a738b82");
+    const PSK_ID: [u8; 22] = hex!("456e6e796e20447572696e206172616e204d6f726961");
+    const EXPORTER_CONTEXTS: [&[u8]; 3] = [b"", b"\x00", b"TestContext"];
+
+    /// Key schedule outputs of an RFC 9180 test vector.
+    struct Vector {
+        mode: Mode,
+        shared_secret: [u8; 32],
+        key: [u8; 16],
+        base_nonce: [u8; 12],
+        second_nonce: [u8; 12],
+        exporter_secret: [u8; 32],
+        exports: [[u8; 32]; 3],
+    }
+
+    impl Vector {
+        fn psk(&self) -> (&'static [u8], &'static [u8]) {
+            if self.mode.uses_psk() {
+                (&PSK, &PSK_ID)
+            } else {
+                (&[], &[])
+            }
+        }
+
+        fn context(&self) -> Context<TestSuite> {
+            let (psk, psk_id) = self.psk();
+            Context::new(self.mode, &self.shared_secret, &INFO, psk, psk_id)
+                .expect("key schedule failed")
+        }
+    }
+
+    /// RFC 9180 Appendix A.1: DHKEM(X25519, HKDF-SHA256), HKDF-SHA256, AES-128-GCM
+    const VECTORS: [Vector; 4] = [
+        // A.1.1: base mode
+        Vector {
+            mode: Mode::Base,
+            shared_secret: hex!("fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc"),
+            key: hex!("4531685d41d65f03dc48f6b8302c05b0"),
+            base_nonce: hex!("56d890e5accaaf011cff4b7d"),
+            second_nonce: hex!("56d890e5accaaf011cff4b7c"),
+            exporter_secret: hex!(
+                "45ff1c2e220db587171952c0592d5f5ebe103f1561a2614e38f2ffd47e99e3f8"
+            ),
+            exports: [
+                hex!("3853fe2b4035195a573ffc53856e77058e15d9ea064de3e59f4961d0095250ee"),
+                hex!("2e8f0b54673c7029649d4eb9d5e33bf1872cf76d623ff164ac185da9e88c21a5"),
+                hex!("e9e43065102c3836401bed8c3c3c75ae46be1639869391d62c61f1ec7af54931"),
+            ],
+        },
+        // A.1.2: PSK mode
+        Vector {
+            mode: Mode::Psk,
+            shared_secret: hex!("727699f009ffe3c076315019c69648366b69171439bd7dd0807743bde76986cd"),
+            key: hex!("15026dba546e3ae05836fc7de5a7bb26"),
+            base_nonce: hex!("9518635eba129d5ce0914555"),
+            second_nonce: hex!("9518635eba129d5ce0914554"),
+            exporter_secret: hex!(
+                "3d76025dbbedc49448ec3f9080a1abab6b06e91c0b11ad23c912f043a0ee7655"
+            ),
+            exports: [
+                hex!("dff17af354c8b41673567db6259fd6029967b4e1aad13023c2ae5df8f4f43bf6"),
+                hex!("6a847261d8207fe596befb52928463881ab493da345b10e1dcc645e3b94e2d95"),
+                hex!("8aff52b45a1be3a734bc7a41e20b4e055ad4c4d22104b0c20285a7c4302401cd"),
+            ],
+        },
+        // A.1.3: auth mode
+        Vector {
+            mode: Mode::Auth,
+            shared_secret: hex!("2d6db4cf719dc7293fcbf3fa64690708e44e2bebc81f84608677958c0d4448a7"),
+            key: hex!("b062cb2c4dd4bca0ad7c7a12bbc341e6"),
+            base_nonce: hex!("a1bc314c1942ade7051ffed0"),
+            second_nonce: hex!("a1bc314c1942ade7051ffed1"),
+            exporter_secret: hex!(
+                "ee1a093e6e1c393c162ea98fdf20560c75909653550540a2700511b65c88c6f1"
+            ),
+            exports: [
+                hex!("28c70088017d70c896a8420f04702c5a321d9cbf0279fba899b59e51bac72c85"),
+                hex!("25dfc004b0892be1888c3914977aa9c9bbaf2c7471708a49e1195af48a6f29ce"),
+                hex!("5a0131813abc9a522cad678eb6bafaabc43389934adb8097d23c5ff68059eb64"),
+            ],
+        },
+        // A.1.4: auth PSK mode
+        Vector {
+            mode: Mode::AuthPsk,
+            shared_secret: hex!("f9d0e870aba28d04709b2680cb8185466c6a6ff1d6e9d1091d5bf5e10ce3a577"),
+            key: hex!("1364ead92c47aa7becfa95203037b19a"),
+            base_nonce: hex!("99d8b5c54669807e9fc70df1"),
+            second_nonce: hex!("99d8b5c54669807e9fc70df0"),
+            exporter_secret: hex!(
+                "f048d55eacbf60f9c6154bd4021774d1075ebf963c6adc71fa846f183ab2dde6"
+            ),
+            exports: [
+                hex!("08f7e20644bb9b8af54ad66d2067457c5f9fcb2a23d9f6cb4445c0797b330067"),
+                hex!("52e51ff7d436557ced5265ff8b94ce69cf7583f49cdb374e6aad801fc063b010"),
+                hex!("a30c20370c026bbea4dca51cb63761695132d342bae33a6a11527d3e7679436d"),
+            ],
+        },
+    ];
+
+    /// KEM which always produces the given shared secret.
+    ///
+    /// It also stands in for an authenticated KEM to test the `Auth` modes.
+    #[derive(Clone)]
+    struct FixedKem([u8; 32]);
+
+    impl Encapsulate<[u8; 32], [u8; 32]> for FixedKem {
+        type Error = Infallible;
+
+        fn encapsulate<R: CryptoRng + ?Sized>(
+            &self,
+            _rng: &mut R,
+        ) -> Result<([u8; 32], [u8; 32]), Infallible> {
+            Ok(([0; 32], self.0))
+        }
+    }
+
+    impl Decapsulate<[u8; 32], [u8; 32]> for FixedKem {
+        type Error = Infallible;
+
+        fn decapsulate(&self, _encapped_key: &[u8; 32]) -> Result<[u8; 32], Infallible> {
+            Ok(self.0)
+        }
+    }
+
+    impl Decapsulator<[u8; 32], [u8; 32]> for FixedKem {
+        type Encapsulator = FixedKem;
+
+        fn encapsulator(&self) -> FixedKem {
+            self.clone()
+        }
+    }
+
+    impl AuthEncapsulate<[u8; 32], [u8; 32]> for FixedKem {}
+
+    impl AuthDecapsulate<[u8; 32], [u8; 32]> for FixedKem {}
+
+    impl Kem for FixedKem {
+        type EncappedKey = [u8; 32];
+        type SharedSecret = [u8; 32];
+        type EncapsulationKey = FixedKem;
+        type DecapsulationKey = FixedKem;
+    }
+
+    impl EncappedKeySizeUser for FixedKem {
+        type EncappedKeySize = U32;
+    }
+
+    impl SharedSecretSizeUser for FixedKem {
+        type SharedSecretSize = U32;
+    }
+
+    struct TestSuite;
+
+    impl CipherSuite for TestSuite {
+        const KEM_ID: u16 = 0x0020;
+        const KDF_ID: u16 = 0x0001;
+        const AEAD_ID: u16 = 0x0001;
+        type Kem = FixedKem;
+        type Kdf = Sha256;
+        type Aead = DummyAead;
+    }
+
+    #[test]
+    fn key_schedule() {
+        for vector in &VECTORS {
+            let mut context = vector.context();
+            assert_eq!(context.aead.key(), &vector.key);
+            assert_eq!(context.base_nonce.as_slice(), &vector.base_nonce);
+            assert_eq!(context.exporter_secret.as_slice(), &vector.exporter_secret);
+
+            context.increment_seq().expect("sequence exhausted");
+            assert_eq!(context.compute_nonce().as_slice(), &vector.second_nonce);
+        }
+    }
+
+    #[test]
+    fn export() {
+        for vector in &VECTORS {
+            let context = vector.context();
+
+            for (exporter_context, expected) in EXPORTER_CONTEXTS.iter().zip(&vector.exports) {
+                let mut out = [0u8; 32];
+                context
+                    .export(exporter_context, &mut out)
+                    .expect("export failed");
+                assert_eq!(&out, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn setup() {
+        for vector in &VECTORS {
+            let kem = FixedKem(vector.shared_secret);
+            let (psk, psk_id) = vector.psk();
+            let mut rng = CounterRng(0);
+
+            let (encapped_key, sender) = match vector.mode {
+                Mode::Base => SenderContext::<TestSuite>::setup_base(&kem, &INFO, &mut rng),
+                Mode::Psk => {
+                    SenderContext::<TestSuite>::setup_psk(&kem, &INFO, psk, psk_id, &mut rng)
+                }
+                Mode::Auth => SenderContext::<TestSuite>::setup_auth(&kem, &INFO, &mut rng),
+                Mode::AuthPsk => {
+                    SenderContext::<TestSuite>::setup_auth_psk(&kem, &INFO, psk, psk_id, &mut rng)
+                }
+            }
+            .expect("sender setup failed");
+
+            let recipient = match vector.mode {
+                Mode::Base => RecipientContext::<TestSuite>::setup_base(&encapped_key, &kem, &INFO),
+                Mode::Psk => RecipientContext::<TestSuite>::setup_psk(
+                    &encapped_key,
+                    &kem,
+                    &INFO,
+                    psk,
+                    psk_id,
+                ),
+                Mode::Auth => RecipientContext::<TestSuite>::setup_auth(&encapped_key, &kem, &INFO),
+                Mode::AuthPsk => RecipientContext::<TestSuite>::setup_auth_psk(
+                    &encapped_key,
+                    &kem,
+                    &INFO,
+                    psk,
+                    psk_id,
+                ),
+            }
+            .expect("recipient setup failed");
+
+            let (mut sender_export, mut recipient_export) = ([0u8; 32], [0u8; 32]);
+            sender
+                .export(EXPORTER_CONTEXTS[0], &mut sender_export)
+                .expect("export failed");
+            recipient
+                .export(EXPORTER_CONTEXTS[0], &mut recipient_export)
+                .expect("export failed");
+            assert_eq!(sender_export, vector.exports[0]);
+            assert_eq!(recipient_export, vector.exports[0]);
+        }
+    }
+
+    #[test]
+    fn nonce_sequence() {
+        let mut context = VECTORS[0].context();
+        assert_eq!(context.compute_nonce().as_slice(), &VECTORS[0].base_nonce);
+
+        context.increment_seq().unwrap();
+        assert_eq!(context.compute_nonce().as_slice(), &VECTORS[0].second_nonce);
+
+        context.seq = 256;
+        assert_eq!(
+            context.compute_nonce().as_slice(),
+            &hex!("56d890e5accaaf011cff4a7d")
+        );
+    }
+
+    #[test]
+    fn inconsistent_psk() {
+        let psk = [0x42; 32];
+        let shared_secret = VECTORS[0].shared_secret;
+
+        assert!(Context::<TestSuite>::new(Mode::Base, &shared_secret, &INFO, &psk, b"id").is_err());
+        assert!(Context::<TestSuite>::new(Mode::Psk, &shared_secret, &INFO, &[], &[]).is_err());
+        assert!(Context::<TestSuite>::new(Mode::Psk, &shared_secret, &INFO, &psk, &[]).is_err());
+        assert!(Context::<TestSuite>::new(Mode::Psk, &shared_secret, &INFO, &psk, b"id").is_ok());
+    }
+
+    #[test]
+    fn seal_open_round_trip() {
+        let kem = FixedKem(VECTORS[0].shared_secret);
+        let (encapped_key, mut sender) =
+            SenderContext::<TestSuite>::setup_base(&kem, &INFO, &mut CounterRng(0)).unwrap();
+        let mut recipient =
+            RecipientContext::<TestSuite>::setup_base(&encapped_key, &kem, &INFO).unwrap();
+
+        for i in 0..3u8 {
+            let mut buffer = [i; 8];
+            let tag = sender
+                .seal_inout_detached(b"aad", (&mut buffer[..]).into())
+                .unwrap();
+            recipient
+                .open_inout_detached(b"aad", (&mut buffer[..]).into(), &tag)
+                .unwrap();
+            assert_eq!(buffer, [i; 8]);
+        }
+
+        let mut buffer = [0u8; 8];
+        let tag = sender
+            .seal_inout_detached(b"aad", (&mut buffer[..]).into())
+            .unwrap();
+        buffer[0] ^= 1;
+        assert!(
+            recipient
+                .open_inout_detached(b"aad", (&mut buffer[..]).into(), &tag)
+                .is_err()
+        );
+    }
+}
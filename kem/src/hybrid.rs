@@ -0,0 +1,805 @@
+//! Hybrid KEM combiner.
+//!
+//! [`HybridKem`] combines two arbitrary [`Kem`]s, typically a classical one
+//! (e.g. X25519 or DHKEM) and a post-quantum one (e.g. ML-KEM), into a single
+//! KEM which stays secure as long as either of its components does.
+//!
+//! Both component KEMs are run independently, their encapsulated keys are
+//! concatenated, and their shared secrets are combined into a single shared
+//! secret by a [`Combiner`]. The following combiners are provided:
+//!
+//! - [`Qsf`]: the shared secret combiner of [X-Wing], which only binds the
+//!   encapsulated key and encapsulation key of the second (classical)
+//!   component.
+//! - [`CatKdf`]: HKDF over the concatenated shared secrets, with the
+//!   length-prefixed encapsulated keys and encapsulation keys of both
+//!   components as info.
+//!
+//! Custom combiners can be supplied by implementing the [`Combiner`] trait.
+//!
+//! # Encoding
+//!
+//! Combined encapsulation keys, decapsulation keys and encapsulated keys are
+//! encoded as the length of the first component as a big endian `u16`,
+//! followed by the first component and then the second one. See
+//! [`encode_pair`] and [`decode_pair`].
+//!
+//! Note that this means `HybridKem<MlKem768, X25519, Qsf<Sha3_256>>` is *not*
+//! X-Wing, even though it computes the same shared secret from the same
+//! component values: X-Wing encodes keys without a length prefix and derives
+//! both component key pairs from a single 32-byte seed.
+//!
+//! [X-Wing]: https://datatracker.ietf.org/doc/draft-connolly-cfrg-xwing-kem/
+
+use crate::{Decapsulate, Decapsulator, Encapsulate, Kem, KeyPairGen};
+use core::{fmt, marker::PhantomData};
+use digest::{Digest, Output, OutputSizeUser, crypto_common::BlockSizeUser};
+use hkdf::{HkdfExtract, hmac::SimpleHmac};
+use rand_core::CryptoRng;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Label appended to the input of the [`Qsf`] combiner, as used by [X-Wing].
+///
+/// [X-Wing]: https://datatracker.ietf.org/doc/draft-connolly-cfrg-xwing-kem/
+pub const XWING_LABEL: &[u8] = br"\.//^\";
+
+/// Size of the length prefix used by [`encode_pair`].
+const LEN_PREFIX_SIZE: usize = 2;
+
+/// Hybrid KEM errors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The first component KEM failed.
+    First,
+    /// The second component KEM failed.
+    Second,
+    /// Malformed or wrongly sized encoding.
+    Encoding,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::First => "hybrid KEM: first component failed",
+            Error::Second => "hybrid KEM: second component failed",
+            Error::Encoding => "hybrid KEM: malformed encoding",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Result type alias with [`Error`].
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Input of a [`Combiner`].
+///
+/// All values are the byte encodings of the respective component values.
+#[derive(Clone, Copy)]
+pub struct CombinerInput<'a> {
+    /// Shared secret of the first component.
+    pub ss1: &'a [u8],
+    /// Shared secret of the second component.
+    pub ss2: &'a [u8],
+    /// Encapsulated key of the first component.
+    pub ct1: &'a [u8],
+    /// Encapsulated key of the second component.
+    pub ct2: &'a [u8],
+    /// Encapsulation key of the first component.
+    pub ek1: &'a [u8],
+    /// Encapsulation key of the second component.
+    pub ek2: &'a [u8],
+}
+
+impl fmt::Debug for CombinerInput<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CombinerInput")
+            .field("ct1", &self.ct1)
+            .field("ct2", &self.ct2)
+            .field("ek1", &self.ek1)
+            .field("ek2", &self.ek2)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Shared secret combiner used by [`HybridKem`].
+pub trait Combiner: OutputSizeUser {
+    /// Combine the component shared secrets into a single shared secret.
+    fn combine(input: &CombinerInput<'_>) -> Output<Self>;
+}
+
+/// The "QSF" combiner used by [X-Wing]:
+/// `H(ss1 || ss2 || ct2 || ek2 || XWING_LABEL)`.
+///
+/// The first component is expected to be the post-quantum KEM, which is
+/// assumed to be ciphertext collision resistant, so its encapsulated key and
+/// encapsulation key are not bound. The second one is expected to be a
+/// Diffie-Hellman based KEM whose encapsulated key is the ephemeral public key.
+///
+/// All inputs are fixed size for a given pair of component KEMs, so they're
+/// concatenated without length prefixes.
+///
+/// [X-Wing]: https://datatracker.ietf.org/doc/draft-connolly-cfrg-xwing-kem/
+pub struct Qsf<H>(PhantomData<H>);
+
+impl<H: OutputSizeUser> OutputSizeUser for Qsf<H> {
+    type OutputSize = H::OutputSize;
+}
+
+impl<H: Digest> Combiner for Qsf<H> {
+    fn combine(input: &CombinerInput<'_>) -> Output<Self> {
+        H::new()
+            .chain_update(input.ss1)
+            .chain_update(input.ss2)
+            .chain_update(input.ct2)
+            .chain_update(input.ek2)
+            .chain_update(XWING_LABEL)
+            .finalize()
+    }
+}
+
+impl<H> fmt::Debug for Qsf<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Qsf")
+    }
+}
+
+/// KDF-based concatenation combiner:
+/// `HKDF-Expand(HKDF-Extract("", ss1 || ss2), len(ct1) || ct1 || ... || len(ek2) || ek2)`.
+///
+/// Each component of the info is prefixed with its length as a big endian
+/// `u64`, so distinct inputs can't produce the same info.
+pub struct CatKdf<D>(PhantomData<D>);
+
+impl<D: OutputSizeUser> OutputSizeUser for CatKdf<D> {
+    type OutputSize = D::OutputSize;
+}
+
+impl<D> Combiner for CatKdf<D>
+where
+    D: BlockSizeUser + Clone + Digest,
+{
+    fn combine(input: &CombinerInput<'_>) -> Output<Self> {
+        let mut extract = HkdfExtract::<D, SimpleHmac<D>>::new(None);
+        extract.input_ikm(input.ss1);
+        extract.input_ikm(input.ss2);
+        let (_, hkdf) = extract.finalize();
+
+        let [ct1_len, ct2_len, ek1_len, ek2_len] =
+            [input.ct1, input.ct2, input.ek1, input.ek2].map(|v| (v.len() as u64).to_be_bytes());
+        let info: [&[u8]; 8] = [
+            &ct1_len, input.ct1, &ct2_len, input.ct2, &ek1_len, input.ek1, &ek2_len, input.ek2,
+        ];
+
+        let mut okm = Output::<Self>::default();
+        hkdf.expand_multi_info(&info, &mut okm)
+            .expect("output size is equal to the hash output size");
+        okm
+    }
+}
+
+impl<D> fmt::Debug for CatKdf<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CatKdf")
+    }
+}
+
+/// Hybrid KEM combining the KEMs `K1` and `K2` using the [`Combiner`] `C`.
+pub struct HybridKem<K1, K2, C>(PhantomData<(K1, K2, C)>);
+
+impl<K1, K2, C> Kem for HybridKem<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    C: Combiner,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+    K1::SharedSecret: AsRef<[u8]>,
+    K2::SharedSecret: AsRef<[u8]>,
+    K1::EncapsulationKey: AsRef<[u8]> + Clone,
+    K2::EncapsulationKey: AsRef<[u8]> + Clone,
+{
+    type EncappedKey = HybridEncappedKey<K1, K2>;
+    type SharedSecret = HybridSharedSecret<C>;
+    type EncapsulationKey = HybridEncapsulationKey<K1, K2, C>;
+    type DecapsulationKey = HybridDecapsulationKey<K1, K2, C>;
+}
+
+impl<K1, K2, C> fmt::Debug for HybridKem<K1, K2, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HybridKem")
+    }
+}
+
+/// Encapsulated key of a [`HybridKem`].
+pub struct HybridEncappedKey<K1: Kem, K2: Kem> {
+    ct1: K1::EncappedKey,
+    ct2: K2::EncappedKey,
+}
+
+impl<K1: Kem, K2: Kem> HybridEncappedKey<K1, K2> {
+    /// Create a new encapsulated key from its components.
+    pub fn new(ct1: K1::EncappedKey, ct2: K2::EncappedKey) -> Self {
+        Self { ct1, ct2 }
+    }
+
+    /// Get the component encapsulated keys.
+    pub fn components(&self) -> (&K1::EncappedKey, &K2::EncappedKey) {
+        (&self.ct1, &self.ct2)
+    }
+}
+
+impl<K1, K2> HybridEncappedKey<K1, K2>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+{
+    /// Length of the encoded encapsulated key in bytes.
+    pub fn encoded_len(&self) -> usize {
+        encoded_pair_len(self.ct1.as_ref(), self.ct2.as_ref())
+    }
+
+    /// Encode into the given buffer, returning the number of bytes written.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize> {
+        encode_pair(self.ct1.as_ref(), self.ct2.as_ref(), out)
+    }
+
+    /// Encode into a newly allocated [`Vec`].
+    ///
+    /// Returns [`Error::Encoding`] if the first component is too long to be
+    /// encoded.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        encode_pair_to_vec(self.ct1.as_ref(), self.ct2.as_ref())
+    }
+}
+
+impl<K1, K2> HybridEncappedKey<K1, K2>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncappedKey: for<'a> TryFrom<&'a [u8]>,
+    K2::EncappedKey: for<'a> TryFrom<&'a [u8]>,
+{
+    /// Decode an encapsulated key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (ct1, ct2) = decode_pair(bytes)?;
+        Ok(Self {
+            ct1: ct1.try_into().map_err(|_| Error::Encoding)?,
+            ct2: ct2.try_into().map_err(|_| Error::Encoding)?,
+        })
+    }
+}
+
+impl<K1, K2> Clone for HybridEncappedKey<K1, K2>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncappedKey: Clone,
+    K2::EncappedKey: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            ct1: self.ct1.clone(),
+            ct2: self.ct2.clone(),
+        }
+    }
+}
+
+impl<K1, K2> fmt::Debug for HybridEncappedKey<K1, K2>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncappedKey: fmt::Debug,
+    K2::EncappedKey: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridEncappedKey")
+            .field("ct1", &self.ct1)
+            .field("ct2", &self.ct2)
+            .finish()
+    }
+}
+
+/// Shared secret of a [`HybridKem`], as output by its [`Combiner`].
+pub struct HybridSharedSecret<C: Combiner>(Output<C>);
+
+impl<C: Combiner> HybridSharedSecret<C> {
+    /// Shared secret value, serialized as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<C: Combiner> AsRef<[u8]> for HybridSharedSecret<C> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<C: Combiner> fmt::Debug for HybridSharedSecret<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridSharedSecret").finish_non_exhaustive()
+    }
+}
+
+impl<C: Combiner> Drop for HybridSharedSecret<C> {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl<C: Combiner> ZeroizeOnDrop for HybridSharedSecret<C> {}
+
+/// Encapsulation key of a [`HybridKem`].
+pub struct HybridEncapsulationKey<K1: Kem, K2: Kem, C> {
+    ek1: K1::EncapsulationKey,
+    ek2: K2::EncapsulationKey,
+    combiner: PhantomData<C>,
+}
+
+impl<K1: Kem, K2: Kem, C> HybridEncapsulationKey<K1, K2, C> {
+    /// Create a new encapsulation key from its components.
+    pub fn new(ek1: K1::EncapsulationKey, ek2: K2::EncapsulationKey) -> Self {
+        Self {
+            ek1,
+            ek2,
+            combiner: PhantomData,
+        }
+    }
+
+    /// Get the component encapsulation keys.
+    pub fn components(&self) -> (&K1::EncapsulationKey, &K2::EncapsulationKey) {
+        (&self.ek1, &self.ek2)
+    }
+}
+
+impl<K1, K2, C> HybridEncapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncapsulationKey: AsRef<[u8]>,
+    K2::EncapsulationKey: AsRef<[u8]>,
+{
+    /// Length of the encoded encapsulation key in bytes.
+    pub fn encoded_len(&self) -> usize {
+        encoded_pair_len(self.ek1.as_ref(), self.ek2.as_ref())
+    }
+
+    /// Encode into the given buffer, returning the number of bytes written.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize> {
+        encode_pair(self.ek1.as_ref(), self.ek2.as_ref(), out)
+    }
+
+    /// Encode into a newly allocated [`Vec`].
+    ///
+    /// Returns [`Error::Encoding`] if the first component is too long to be
+    /// encoded.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        encode_pair_to_vec(self.ek1.as_ref(), self.ek2.as_ref())
+    }
+}
+
+impl<K1, K2, C> HybridEncapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncapsulationKey: for<'a> TryFrom<&'a [u8]>,
+    K2::EncapsulationKey: for<'a> TryFrom<&'a [u8]>,
+{
+    /// Decode an encapsulation key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (ek1, ek2) = decode_pair(bytes)?;
+        Ok(Self::new(
+            ek1.try_into().map_err(|_| Error::Encoding)?,
+            ek2.try_into().map_err(|_| Error::Encoding)?,
+        ))
+    }
+}
+
+impl<K1, K2, C> Encapsulate<HybridEncappedKey<K1, K2>, HybridSharedSecret<C>>
+    for HybridEncapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    C: Combiner,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+    K1::SharedSecret: AsRef<[u8]>,
+    K2::SharedSecret: AsRef<[u8]>,
+    K1::EncapsulationKey: AsRef<[u8]>,
+    K2::EncapsulationKey: AsRef<[u8]>,
+{
+    type Error = Error;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(HybridEncappedKey<K1, K2>, HybridSharedSecret<C>)> {
+        let (ct1, ss1) = self.ek1.encapsulate(rng).map_err(|_| Error::First)?;
+        let (ct2, ss2) = self.ek2.encapsulate(rng).map_err(|_| Error::Second)?;
+        let encapped_key = HybridEncappedKey { ct1, ct2 };
+        let shared_secret = combine(&ss1, &ss2, &encapped_key, self);
+        Ok((encapped_key, shared_secret))
+    }
+}
+
+impl<K1, K2, C> Clone for HybridEncapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncapsulationKey: Clone,
+    K2::EncapsulationKey: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.ek1.clone(), self.ek2.clone())
+    }
+}
+
+impl<K1, K2, C> fmt::Debug for HybridEncapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncapsulationKey: fmt::Debug,
+    K2::EncapsulationKey: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridEncapsulationKey")
+            .field("ek1", &self.ek1)
+            .field("ek2", &self.ek2)
+            .finish()
+    }
+}
+
+/// Decapsulation key of a [`HybridKem`].
+///
+/// The encapsulation key is computed on construction, as it is an input to
+/// the [`Combiner`] on decapsulation.
+pub struct HybridDecapsulationKey<K1: Kem, K2: Kem, C> {
+    dk1: K1::DecapsulationKey,
+    dk2: K2::DecapsulationKey,
+    ek: HybridEncapsulationKey<K1, K2, C>,
+}
+
+impl<K1: Kem, K2: Kem, C> HybridDecapsulationKey<K1, K2, C> {
+    /// Create a new decapsulation key from its components.
+    pub fn new(dk1: K1::DecapsulationKey, dk2: K2::DecapsulationKey) -> Self {
+        let ek = HybridEncapsulationKey::new(dk1.encapsulator(), dk2.encapsulator());
+        Self { dk1, dk2, ek }
+    }
+
+    /// Generate a random key pair using the provided [`CryptoRng`].
+    pub fn generate<R: CryptoRng + ?Sized>(rng: &mut R) -> (Self, HybridEncapsulationKey<K1, K2, C>)
+    where
+        K1::DecapsulationKey: KeyPairGen<Encapsulator = K1::EncapsulationKey>,
+        K2::DecapsulationKey: KeyPairGen<Encapsulator = K2::EncapsulationKey>,
+        K1::EncapsulationKey: Clone,
+        K2::EncapsulationKey: Clone,
+    {
+        let (dk1, ek1) = K1::DecapsulationKey::generate(rng);
+        let (dk2, ek2) = K2::DecapsulationKey::generate(rng);
+        let ek = HybridEncapsulationKey::new(ek1, ek2);
+        (
+            Self {
+                dk1,
+                dk2,
+                ek: ek.clone(),
+            },
+            ek,
+        )
+    }
+
+    /// Get the component decapsulation keys.
+    pub fn components(&self) -> (&K1::DecapsulationKey, &K2::DecapsulationKey) {
+        (&self.dk1, &self.dk2)
+    }
+
+    /// Get the matching encapsulation key.
+    pub fn encapsulation_key(&self) -> &HybridEncapsulationKey<K1, K2, C> {
+        &self.ek
+    }
+}
+
+impl<K1, K2, C> HybridDecapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::DecapsulationKey: AsRef<[u8]>,
+    K2::DecapsulationKey: AsRef<[u8]>,
+{
+    /// Length of the encoded decapsulation key in bytes.
+    pub fn encoded_len(&self) -> usize {
+        encoded_pair_len(self.dk1.as_ref(), self.dk2.as_ref())
+    }
+
+    /// Encode into the given buffer, returning the number of bytes written.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize> {
+        encode_pair(self.dk1.as_ref(), self.dk2.as_ref(), out)
+    }
+}
+
+impl<K1, K2, C> HybridDecapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::DecapsulationKey: for<'a> TryFrom<&'a [u8]>,
+    K2::DecapsulationKey: for<'a> TryFrom<&'a [u8]>,
+{
+    /// Decode a decapsulation key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (dk1, dk2) = decode_pair(bytes)?;
+        Ok(Self::new(
+            dk1.try_into().map_err(|_| Error::Encoding)?,
+            dk2.try_into().map_err(|_| Error::Encoding)?,
+        ))
+    }
+}
+
+impl<K1, K2, C> Decapsulate<HybridEncappedKey<K1, K2>, HybridSharedSecret<C>>
+    for HybridDecapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    C: Combiner,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+    K1::SharedSecret: AsRef<[u8]>,
+    K2::SharedSecret: AsRef<[u8]>,
+    K1::EncapsulationKey: AsRef<[u8]>,
+    K2::EncapsulationKey: AsRef<[u8]>,
+{
+    type Error = Error;
+
+    fn decapsulate(
+        &self,
+        encapsulated_key: &HybridEncappedKey<K1, K2>,
+    ) -> Result<HybridSharedSecret<C>> {
+        let ss1 = self
+            .dk1
+            .decapsulate(&encapsulated_key.ct1)
+            .map_err(|_| Error::First)?;
+        let ss2 = self
+            .dk2
+            .decapsulate(&encapsulated_key.ct2)
+            .map_err(|_| Error::Second)?;
+        Ok(combine(&ss1, &ss2, encapsulated_key, &self.ek))
+    }
+}
+
+impl<K1, K2, C> Decapsulator<HybridEncappedKey<K1, K2>, HybridSharedSecret<C>>
+    for HybridDecapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    C: Combiner,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+    K1::SharedSecret: AsRef<[u8]>,
+    K2::SharedSecret: AsRef<[u8]>,
+    K1::EncapsulationKey: AsRef<[u8]> + Clone,
+    K2::EncapsulationKey: AsRef<[u8]> + Clone,
+{
+    type Encapsulator = HybridEncapsulationKey<K1, K2, C>;
+
+    fn encapsulator(&self) -> HybridEncapsulationKey<K1, K2, C> {
+        self.ek.clone()
+    }
+}
+
+impl<K1, K2, C> fmt::Debug for HybridDecapsulationKey<K1, K2, C>
+where
+    K1: Kem,
+    K2: Kem,
+    K1::EncapsulationKey: fmt::Debug,
+    K2::EncapsulationKey: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HybridDecapsulationKey")
+            .field("ek", &self.ek)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Length of a pair encoded with [`encode_pair`].
+pub fn encoded_pair_len(first: &[u8], second: &[u8]) -> usize {
+    LEN_PREFIX_SIZE + first.len() + second.len()
+}
+
+/// Encode a pair of components as the length of `first` as a big endian
+/// `u16`, followed by `first` and `second`.
+///
+/// Returns the number of bytes written to `out`, or [`Error::Encoding`] if
+/// `out` is too short or `first` is longer than `u16::MAX` bytes.
+pub fn encode_pair(first: &[u8], second: &[u8], out: &mut [u8]) -> Result<usize> {
+    let len = u16::try_from(first.len()).map_err(|_| Error::Encoding)?;
+    let total = encoded_pair_len(first, second);
+    let out = out.get_mut(..total).ok_or(Error::Encoding)?;
+
+    let (prefix, rest) = out.split_at_mut(LEN_PREFIX_SIZE);
+    let (out1, out2) = rest.split_at_mut(first.len());
+    prefix.copy_from_slice(&len.to_be_bytes());
+    out1.copy_from_slice(first);
+    out2.copy_from_slice(second);
+    Ok(total)
+}
+
+/// Decode a pair of components encoded with [`encode_pair`].
+pub fn decode_pair(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    if bytes.len() < LEN_PREFIX_SIZE {
+        return Err(Error::Encoding);
+    }
+
+    let (prefix, rest) = bytes.split_at(LEN_PREFIX_SIZE);
+    let len = usize::from(u16::from_be_bytes([prefix[0], prefix[1]]));
+    if rest.len() < len {
+        return Err(Error::Encoding);
+    }
+
+    Ok(rest.split_at(len))
+}
+
+#[cfg(feature = "alloc")]
+fn encode_pair_to_vec(first: &[u8], second: &[u8]) -> Result<Vec<u8>> {
+    let mut out = alloc::vec![0u8; encoded_pair_len(first, second)];
+    encode_pair(first, second, &mut out)?;
+    Ok(out)
+}
+
+fn combine<K1, K2, C>(
+    ss1: &K1::SharedSecret,
+    ss2: &K2::SharedSecret,
+    encapped_key: &HybridEncappedKey<K1, K2>,
+    encapsulation_key: &HybridEncapsulationKey<K1, K2, C>,
+) -> HybridSharedSecret<C>
+where
+    K1: Kem,
+    K2: Kem,
+    C: Combiner,
+    K1::EncappedKey: AsRef<[u8]>,
+    K2::EncappedKey: AsRef<[u8]>,
+    K1::SharedSecret: AsRef<[u8]>,
+    K2::SharedSecret: AsRef<[u8]>,
+    K1::EncapsulationKey: AsRef<[u8]>,
+    K2::EncapsulationKey: AsRef<[u8]>,
+{
+    HybridSharedSecret(C::combine(&CombinerInput {
+        ss1: ss1.as_ref(),
+        ss2: ss2.as_ref(),
+        ct1: encapped_key.ct1.as_ref(),
+        ct2: encapped_key.ct2.as_ref(),
+        ek1: encapsulation_key.ek1.as_ref(),
+        ek2: encapsulation_key.ek2.as_ref(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{CounterRng, ToyKem};
+    use hex_literal::hex;
+    use sha3::Sha3_256;
+
+    type TestKem<C> = HybridKem<ToyKem, ToyKem, C>;
+
+    fn round_trip<C: Combiner>() {
+        let mut rng = CounterRng(0);
+        let (dk, ek) = HybridDecapsulationKey::<ToyKem, ToyKem, C>::generate(&mut rng);
+        let (ct, ss1) = ek.encapsulate(&mut rng).unwrap();
+        let ss2 = dk.decapsulate(&ct).unwrap();
+        assert_eq!(ss1.as_bytes(), ss2.as_bytes());
+
+        let mut buf = [0u8; 64];
+        let len = ct.encode_into(&mut buf).unwrap();
+        assert_eq!(len, ct.encoded_len());
+        let decoded = <TestKem<C> as Kem>::EncappedKey::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(decoded.components(), ct.components());
+
+        let len = dk.encode_into(&mut buf).unwrap();
+        let decoded = HybridDecapsulationKey::<ToyKem, ToyKem, C>::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(decoded.components(), dk.components());
+        assert_eq!(decoded.decapsulate(&ct).unwrap().as_bytes(), ss1.as_bytes());
+
+        let len = ek.encode_into(&mut buf).unwrap();
+        let decoded = HybridEncapsulationKey::<ToyKem, ToyKem, C>::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(decoded.components(), dk.encapsulator().components());
+    }
+
+    #[test]
+    fn qsf_round_trip() {
+        round_trip::<Qsf<Sha3_256>>();
+    }
+
+    #[test]
+    fn cat_kdf_round_trip() {
+        round_trip::<CatKdf<sha2::Sha256>>();
+    }
+
+    #[test]
+    fn qsf_combiner() {
+        let ss1: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let ss2: [u8; 32] = core::array::from_fn(|i| i as u8 + 32);
+        let input = CombinerInput {
+            ss1: &ss1,
+            ss2: &ss2,
+            ct1: b"ignored",
+            ct2: &[0xaa; 32],
+            ek1: b"ignored",
+            ek2: &[0x55; 32],
+        };
+        assert_eq!(
+            Qsf::<Sha3_256>::combine(&input)[..],
+            hex!("9e55baa2af064986c8c1846b1cac17f5ed701dbceb105bab9c8c45a1e916ab98")
+        );
+    }
+
+    #[test]
+    fn cat_kdf_combiner() {
+        let ss1: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let ss2: [u8; 32] = core::array::from_fn(|i| i as u8 + 32);
+        let input = CombinerInput {
+            ss1: &ss1,
+            ss2: &ss2,
+            ct1: &[0x01; 16],
+            ct2: &[0xaa; 32],
+            ek1: &[0x02; 24],
+            ek2: &[0x55; 32],
+        };
+        assert_eq!(
+            CatKdf::<sha2::Sha256>::combine(&input)[..],
+            hex!("8a5338549b06c13c32700b7784763ad79210b8f2587b017ac2b9455a29128764")
+        );
+    }
+
+    #[test]
+    fn cat_kdf_frames_info() {
+        let input = CombinerInput {
+            ss1: &[1; 32],
+            ss2: &[2; 32],
+            ct1: b"ab",
+            ct2: b"c",
+            ek1: b"",
+            ek2: b"",
+        };
+        let shifted = CombinerInput {
+            ct1: b"a",
+            ct2: b"bc",
+            ..input
+        };
+        assert_ne!(
+            CatKdf::<sha2::Sha256>::combine(&input),
+            CatKdf::<sha2::Sha256>::combine(&shifted)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn to_vec_rejects_long_component() {
+        let long = alloc::vec![0u8; usize::from(u16::MAX) + 1];
+        assert_eq!(encode_pair_to_vec(&long, b""), Err(Error::Encoding));
+        assert_eq!(
+            encode_pair_to_vec(&long[1..], b"x").map(|v| v.len()),
+            Ok(long.len() + 2)
+        );
+    }
+
+    #[test]
+    fn pair_encoding() {
+        let mut buf = [0u8; 8];
+        assert_eq!(encode_pair(b"ab", b"cde", &mut buf), Ok(7));
+        assert_eq!(&buf[..7], b"\x00\x02abcde");
+        assert_eq!(decode_pair(&buf[..7]), Ok((&b"ab"[..], &b"cde"[..])));
+        assert_eq!(
+            encode_pair(b"ab", b"cdefgh", &mut buf),
+            Err(Error::Encoding)
+        );
+        assert_eq!(decode_pair(b"\x00"), Err(Error::Encoding));
+        assert_eq!(decode_pair(b"\x00\x03ab"), Err(Error::Encoding));
+    }
+}
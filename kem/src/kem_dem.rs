@@ -0,0 +1,331 @@
+//! KEM-DEM public-key encryption.
+//!
+//! [`KemDem`] provides one-shot public-key encryption of arbitrary messages by
+//! combining a [`Kem`] with an AEAD: a fresh shared secret is encapsulated to
+//! the recipient, the AEAD key is derived from it using a [`Kdf`], and the
+//! message is encrypted under that key.
+//!
+//! # Wire format
+//!
+//! | Field             | Size                        |
+//! |-------------------|-----------------------------|
+//! | encapped key size | 2 bytes (big endian `u16`)  |
+//! | encapped key      | encapped key size           |
+//! | AEAD ciphertext   | message length + tag size   |
+//!
+//! The AEAD ciphertext includes the tag at the position given by
+//! [`AeadCore::TAG_POSITION`][aead::AeadCore::TAG_POSITION]. Since every AEAD
+//! key is used only once, the all-zero nonce is used and it is not included in
+//! the wire format.
+
+use crate::{Decapsulate, Encapsulate, Kem};
+use aead::{AeadInOut, Buffer, Key, KeyInit, Nonce, Tag, TagPosition, array::typenum::Unsigned};
+use core::{fmt, marker::PhantomData};
+use digest::{Digest, crypto_common::BlockSizeUser};
+use hkdf::{Hkdf, hmac::SimpleHmac};
+use rand_core::CryptoRng;
+use zeroize::Zeroize;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Size of the encapsulated key size prefix.
+const LEN_PREFIX_SIZE: usize = 2;
+
+/// Info string used by [`HkdfKdf`].
+const HKDF_INFO: &[u8] = b"KEM-DEM key";
+
+/// KEM-DEM errors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Encapsulation failed.
+    Encap,
+    /// Decapsulation failed.
+    Decap,
+    /// Key derivation failed.
+    Kdf,
+    /// Malformed ciphertext encoding.
+    Encoding,
+    /// AEAD encryption or decryption failed.
+    Aead,
+    /// The buffer is too small to hold the ciphertext.
+    Capacity,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::Encap => "KEM-DEM encapsulation error",
+            Error::Decap => "KEM-DEM decapsulation error",
+            Error::Kdf => "KEM-DEM key derivation error",
+            Error::Encoding => "KEM-DEM malformed ciphertext",
+            Error::Aead => "KEM-DEM AEAD error",
+            Error::Capacity => "KEM-DEM buffer too small",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Result type alias with [`Error`].
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Key derivation function used to derive the AEAD key from the KEM shared
+/// secret.
+pub trait Kdf {
+    /// Derive `okm.len()` bytes of key material from `shared_secret`, bound to
+    /// `encapped_key`.
+    fn derive_key(shared_secret: &[u8], encapped_key: &[u8], okm: &mut [u8]) -> Result<()>;
+}
+
+/// HKDF-based [`Kdf`]:
+/// `HKDF-Expand(HKDF-Extract(encapped_key, shared_secret), "KEM-DEM key", L)`.
+pub struct HkdfKdf<D>(PhantomData<D>);
+
+impl<D> Kdf for HkdfKdf<D>
+where
+    D: BlockSizeUser + Clone + Digest,
+{
+    fn derive_key(shared_secret: &[u8], encapped_key: &[u8], okm: &mut [u8]) -> Result<()> {
+        Hkdf::<D, SimpleHmac<D>>::new(Some(encapped_key), shared_secret)
+            .expand(HKDF_INFO, okm)
+            .map_err(|_| Error::Kdf)
+    }
+}
+
+impl<D> fmt::Debug for HkdfKdf<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HkdfKdf")
+    }
+}
+
+/// KEM-DEM public-key encryption using the [`Kem`] `K`, the AEAD `A` and the
+/// [`Kdf`] `F`.
+pub struct KemDem<K, A, F>(PhantomData<(K, A, F)>);
+
+impl<K, A, F> KemDem<K, A, F>
+where
+    K: Kem,
+    K::EncappedKey: AsRef<[u8]> + for<'a> TryFrom<&'a [u8]>,
+    K::SharedSecret: AsRef<[u8]>,
+    A: AeadInOut + KeyInit,
+    F: Kdf,
+{
+    /// Encrypt the given buffer containing a plaintext message in-place to
+    /// `pk`, prepending the encapsulated key.
+    ///
+    /// Returns [`Error::Capacity`] and leaves the buffer unmodified if it
+    /// can't grow to [`KemDem::ciphertext_len`] bytes.
+    pub fn seal_in_place<R: CryptoRng + ?Sized>(
+        pk: &K::EncapsulationKey,
+        aad: &[u8],
+        buffer: &mut dyn Buffer,
+        rng: &mut R,
+    ) -> Result<()> {
+        let (encapped_key, shared_secret) = pk.encapsulate(rng).map_err(|_| Error::Encap)?;
+        let encapped_key = encapped_key.as_ref();
+        let len = u16::try_from(encapped_key.len()).map_err(|_| Error::Encoding)?;
+
+        // Reserve space for the header and tag up front, so running out of
+        // capacity can't be mistaken for an AEAD failure after encrypting.
+        let msg_len = buffer.len();
+        let reserved = buffer
+            .extend_from_slice(&len.to_be_bytes())
+            .and_then(|()| buffer.extend_from_slice(encapped_key))
+            .and_then(|()| buffer.extend_from_slice(&Tag::<A>::default()));
+        buffer.truncate(msg_len);
+        reserved.map_err(|_| Error::Capacity)?;
+
+        let aead = Self::aead(shared_secret.as_ref(), encapped_key)?;
+        aead.encrypt_in_place(&Nonce::<A>::default(), aad, buffer)
+            .map_err(|_| Error::Aead)?;
+
+        buffer
+            .extend_from_slice(&len.to_be_bytes())
+            .and_then(|()| buffer.extend_from_slice(encapped_key))
+            .map_err(|_| Error::Capacity)?;
+        buffer
+            .as_mut()
+            .rotate_right(LEN_PREFIX_SIZE + encapped_key.len());
+        Ok(())
+    }
+
+    /// Decrypt the given buffer containing an encapsulated key and ciphertext
+    /// in-place using `sk`, leaving only the plaintext message.
+    ///
+    /// The header and tag are only stripped once the ciphertext has been
+    /// authenticated: on error the buffer keeps its length, and its contents
+    /// are unmodified unless the AEAD clears its output on failure.
+    pub fn open_in_place(
+        sk: &K::DecapsulationKey,
+        aad: &[u8],
+        buffer: &mut dyn Buffer,
+    ) -> Result<()> {
+        let (encapped_key, header_len) = {
+            let bytes = buffer.as_ref();
+            if bytes.len() < LEN_PREFIX_SIZE {
+                return Err(Error::Encoding);
+            }
+
+            let len = usize::from(u16::from_be_bytes([bytes[0], bytes[1]]));
+            let header_len = LEN_PREFIX_SIZE + len;
+            let encapped_key = bytes
+                .get(LEN_PREFIX_SIZE..header_len)
+                .ok_or(Error::Encoding)?;
+            let encapped_key =
+                K::EncappedKey::try_from(encapped_key).map_err(|_| Error::Encoding)?;
+            (encapped_key, header_len)
+        };
+
+        let shared_secret = sk.decapsulate(&encapped_key).map_err(|_| Error::Decap)?;
+        let aead = Self::aead(shared_secret.as_ref(), encapped_key.as_ref())?;
+
+        let tag_size = A::TagSize::USIZE;
+        let ciphertext = &mut buffer.as_mut()[header_len..];
+        let msg_len = ciphertext
+            .len()
+            .checked_sub(tag_size)
+            .ok_or(Error::Encoding)?;
+        let (msg_start, msg, tag) = match A::TAG_POSITION {
+            TagPosition::Prefix => {
+                let (tag, msg) = ciphertext.split_at_mut(tag_size);
+                (header_len + tag_size, msg, tag)
+            }
+            TagPosition::Postfix => {
+                let (msg, tag) = ciphertext.split_at_mut(msg_len);
+                (header_len, msg, tag)
+            }
+        };
+        let tag = Tag::<A>::try_from(&*tag).expect("tag length mismatch");
+        aead.decrypt_inout_detached(&Nonce::<A>::default(), aad, msg.into(), &tag)
+            .map_err(|_| Error::Aead)?;
+
+        // Only strip the header and tag once the ciphertext is authenticated
+        buffer
+            .as_mut()
+            .copy_within(msg_start..msg_start + msg_len, 0);
+        buffer.truncate(msg_len);
+        Ok(())
+    }
+
+    /// Encrypt `msg` to `pk`.
+    #[cfg(feature = "alloc")]
+    pub fn seal<R: CryptoRng + ?Sized>(
+        pk: &K::EncapsulationKey,
+        aad: &[u8],
+        msg: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>> {
+        let mut buffer = Vec::from(msg);
+        Self::seal_in_place(pk, aad, &mut buffer, rng)?;
+        Ok(buffer)
+    }
+
+    /// Decrypt the ciphertext `ct` using `sk`.
+    #[cfg(feature = "alloc")]
+    pub fn open(sk: &K::DecapsulationKey, aad: &[u8], ct: &[u8]) -> Result<Vec<u8>> {
+        let mut buffer = Vec::from(ct);
+        Self::open_in_place(sk, aad, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Length of the ciphertext of a message of `msg_len` bytes, given the
+    /// length of the KEM's encapsulated key.
+    pub fn ciphertext_len(encapped_key_len: usize, msg_len: usize) -> usize {
+        LEN_PREFIX_SIZE + encapped_key_len + msg_len + A::TagSize::USIZE
+    }
+
+    fn aead(shared_secret: &[u8], encapped_key: &[u8]) -> Result<A> {
+        let mut key = Key::<A>::default();
+        F::derive_key(shared_secret, encapped_key, &mut key)?;
+        let aead = A::new(&key);
+        key.as_mut_slice().zeroize();
+        Ok(aead)
+    }
+}
+
+impl<K, A, F> fmt::Debug for KemDem<K, A, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KemDem")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{CounterRng, DummyAead, ToyKem, ToyKey};
+    use aead::heapless;
+    use sha2::Sha256;
+
+    type TestKemDem = KemDem<ToyKem, DummyAead, HkdfKdf<Sha256>>;
+
+    const KEY: ToyKey = ToyKey([0x42; 16]);
+    const AAD: &[u8] = b"associated data";
+    const MSG: &[u8] = b"hello, world";
+
+    #[test]
+    fn seal_open_in_place() {
+        let mut buffer = array_buffer(MSG);
+        TestKemDem::seal_in_place(&KEY, AAD, &mut buffer, &mut CounterRng(0)).unwrap();
+        assert_eq!(buffer.len(), TestKemDem::ciphertext_len(16, MSG.len()));
+        assert_eq!(&buffer[..2], &[0, 16]);
+
+        TestKemDem::open_in_place(&KEY, AAD, &mut buffer).unwrap();
+        assert_eq!(buffer, MSG);
+    }
+
+    #[test]
+    fn open_rejects_tampering() {
+        let mut buffer = array_buffer(MSG);
+        TestKemDem::seal_in_place(&KEY, AAD, &mut buffer, &mut CounterRng(0)).unwrap();
+        let mut tampered = buffer.clone();
+        tampered[20] ^= 1;
+        let expected = tampered.clone();
+        assert_eq!(
+            TestKemDem::open_in_place(&KEY, AAD, &mut tampered),
+            Err(Error::Aead)
+        );
+        assert_eq!(tampered, expected);
+        assert_eq!(
+            TestKemDem::open_in_place(&KEY, b"wrong", &mut buffer.clone()),
+            Err(Error::Aead)
+        );
+
+        let mut truncated = buffer.clone();
+        truncated.truncate(10);
+        assert_eq!(
+            TestKemDem::open_in_place(&KEY, AAD, &mut truncated),
+            Err(Error::Encoding)
+        );
+
+        // Room for the header but not the tag
+        let mut truncated = buffer.clone();
+        truncated.truncate(2 + 16 + 15);
+        assert_eq!(
+            TestKemDem::open_in_place(&KEY, AAD, &mut truncated),
+            Err(Error::Encoding)
+        );
+    }
+
+    #[test]
+    fn seal_rejects_short_buffer() {
+        let ciphertext_len = TestKemDem::ciphertext_len(16, MSG.len());
+        let mut buffer = heapless::Vec::<u8, 45>::from_slice(MSG).unwrap();
+        assert_eq!(buffer.capacity() + 1, ciphertext_len);
+        assert_eq!(
+            TestKemDem::seal_in_place(&KEY, AAD, &mut buffer, &mut CounterRng(0)),
+            Err(Error::Capacity)
+        );
+        assert_eq!(buffer, MSG);
+
+        let mut buffer = heapless::Vec::<u8, 46>::from_slice(MSG).unwrap();
+        TestKemDem::seal_in_place(&KEY, AAD, &mut buffer, &mut CounterRng(0)).unwrap();
+        assert_eq!(buffer.len(), ciphertext_len);
+    }
+
+    /// Fixed capacity buffer, so the tests cover the `in_place` methods without `alloc`.
+    fn array_buffer(msg: &[u8]) -> heapless::Vec<u8, 64> {
+        heapless::Vec::from_slice(msg).unwrap()
+    }
+}
@@ -0,0 +1,72 @@
+//! Labeled KDF of [RFC 9180 §4], i.e. HKDF with inputs prefixed by the
+//! `HPKE-v1` version label and a suite identifier.
+//!
+//! This is used by HPKE's key schedule as well as by KEMs defined in terms of
+//! it, e.g. DHKEM.
+//!
+//! [RFC 9180 §4]: https://www.rfc-editor.org/rfc/rfc9180.html#section-4
+
+use digest::{Digest, Output, crypto_common::BlockSizeUser};
+use hkdf::{Hkdf, HkdfExtract, InvalidLength, hmac::SimpleHmac};
+
+/// Version label prepended to all labeled KDF inputs.
+const VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+/// `LabeledExtract(salt, label, ikm)` as described in [RFC 9180 §4], with
+/// `ikm` passed as its concatenated components.
+///
+/// Returns the PRK along with the HKDF instance to expand it.
+///
+/// [RFC 9180 §4]: https://www.rfc-editor.org/rfc/rfc9180.html#section-4
+pub fn labeled_extract<D>(
+    suite_id: &[u8],
+    salt: &[u8],
+    label: &[u8],
+    ikm: &[&[u8]],
+) -> (Output<D>, Hkdf<D, SimpleHmac<D>>)
+where
+    D: BlockSizeUser + Clone + Digest,
+{
+    let mut extract = HkdfExtract::<D, SimpleHmac<D>>::new(Some(salt));
+    extract.input_ikm(VERSION_LABEL);
+    extract.input_ikm(suite_id);
+    extract.input_ikm(label);
+
+    for component in ikm {
+        extract.input_ikm(component);
+    }
+
+    extract.finalize()
+}
+
+/// `LabeledExpand(prk, label, info, L)` as described in [RFC 9180 §4], with
+/// `info` passed as its concatenated components and `L` being the length of
+/// `okm`.
+///
+/// At most four `info` components are supported.
+///
+/// [RFC 9180 §4]: https://www.rfc-editor.org/rfc/rfc9180.html#section-4
+pub fn labeled_expand<D>(
+    prk: &Hkdf<D, SimpleHmac<D>>,
+    suite_id: &[u8],
+    label: &[u8],
+    info: &[&[u8]],
+    okm: &mut [u8],
+) -> Result<(), InvalidLength>
+where
+    D: BlockSizeUser + Clone + Digest,
+{
+    let length = u16::try_from(okm.len())
+        .map_err(|_| InvalidLength)?
+        .to_be_bytes();
+
+    // Unused trailing components are left empty and don't affect the output
+    let mut components: [&[u8]; 8] = [&[]; 8];
+    components[..4].copy_from_slice(&[&length[..], VERSION_LABEL, suite_id, label]);
+    components
+        .get_mut(4..4 + info.len())
+        .ok_or(InvalidLength)?
+        .copy_from_slice(info);
+
+    prk.expand_multi_info(&components, okm)
+}
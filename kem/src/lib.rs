@@ -8,9 +8,43 @@
 )]
 #![forbid(unsafe_code)]
 #![warn(missing_docs, unused_qualifications, missing_debug_implementations)]
+#![allow(async_fn_in_trait)]
 
-use core::fmt::Debug;
-use rand_core::CryptoRng;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "dev")]
+pub mod dev;
+#[cfg(feature = "fo")]
+pub mod fo;
+#[cfg(feature = "hpke")]
+pub mod hpke;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+#[cfg(feature = "kem_dem")]
+pub mod kem_dem;
+#[cfg(feature = "labeled_kdf")]
+pub mod labeled_kdf;
+#[cfg(feature = "multi")]
+pub mod multi;
+#[cfg(test)]
+mod test_utils;
+
+pub use crypto_common::{array, typenum};
+
+use core::fmt::{self, Debug};
+use crypto_common::{array::ArraySize, typenum::Unsigned};
+use rand_core::{CryptoRng, TryCryptoRng};
+use zeroize::Zeroize;
+
+/// Encapsulated key used by [`EncappedKeySizeUser`] implementors.
+pub type EncappedKey<T> = array::Array<u8, <T as EncappedKeySizeUser>::EncappedKeySize>;
+
+/// Shared secret used by [`SharedSecretSizeUser`] implementors.
+pub type SharedSecret<T> = array::Array<u8, <T as SharedSecretSizeUser>::SharedSecretSize>;
+
+/// Seed used by [`KeyPairGen`] implementors.
+pub type Seed<T> = array::Array<u8, <T as KeyPairGen>::SeedSize>;
 
 /// A value that can be encapsulated to. Often, this will just be a public key. However, it can
 /// also be a bundle of public keys, or it can include a sender's private key for authenticated
@@ -23,6 +57,16 @@ pub trait Encapsulate<EK, SS> {
     fn encapsulate<R: CryptoRng + ?Sized>(&self, rng: &mut R) -> Result<(EK, SS), Self::Error>;
 }
 
+/// [`Encapsulate`] impls which can draw their randomness from a fallible [`TryCryptoRng`]
+/// (e.g. an HSM or OS entropy source), surfacing RNG failures as errors instead of panics.
+pub trait TryEncapsulate<EK, SS>: Encapsulate<EK, SS> {
+    /// Encapsulates a fresh shared secret, returning an error if the RNG fails.
+    fn try_encapsulate<R: TryCryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(EK, SS), TryEncapsulateError<Self::Error, R::Error>>;
+}
+
 /// A value that can be used to decapsulate an encapsulated key.
 ///
 /// Often, this will just be a secret key. But, as with [`Encapsulate`], it can be a bundle
@@ -34,3 +78,286 @@ pub trait Decapsulate<EK, SS> {
     /// Decapsulates the given encapsulated key
     fn decapsulate(&self, encapsulated_key: &EK) -> Result<SS, Self::Error>;
 }
+
+/// Marker for [`Encapsulate`] impls which perform authenticated encapsulation, binding the
+/// shared secret to a sender's secret key in addition to the recipient's public key
+/// (`AuthEncap` in RFC 9180 terms).
+pub trait AuthEncapsulate<EK, SS>: Encapsulate<EK, SS> {}
+
+/// Marker for [`Decapsulate`] impls which perform authenticated decapsulation, checking the
+/// encapsulated key against a sender's public key (`AuthDecap` in RFC 9180 terms).
+pub trait AuthDecapsulate<EK, SS>: Decapsulate<EK, SS> {}
+
+/// Asynchronously encapsulate a fresh shared secret (e.g. using a key held by an HSM).
+///
+/// This trait is an async equivalent of the [`Encapsulate`] trait.
+pub trait AsyncEncapsulate<EK, SS> {
+    /// Encapsulation error
+    type Error: Debug;
+
+    /// Encapsulates a fresh shared secret
+    async fn encapsulate_async<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(EK, SS), Self::Error>;
+}
+
+impl<EK, SS, T> AsyncEncapsulate<EK, SS> for T
+where
+    T: Encapsulate<EK, SS>,
+{
+    type Error = T::Error;
+
+    async fn encapsulate_async<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(EK, SS), Self::Error> {
+        self.encapsulate(rng)
+    }
+}
+
+/// Asynchronously decapsulate an encapsulated key (e.g. using a key held by an HSM).
+///
+/// This trait is an async equivalent of the [`Decapsulate`] trait.
+pub trait AsyncDecapsulate<EK, SS> {
+    /// Decapsulation error
+    type Error: Debug;
+
+    /// Decapsulates the given encapsulated key
+    ///
+    /// The main intended use case for decapsulation errors is when communicating with external
+    /// decapsulators, e.g. cloud KMS, HSMs, or other hardware tokens.
+    async fn decapsulate_async(&self, encapsulated_key: &EK) -> Result<SS, Self::Error>;
+}
+
+impl<EK, SS, T> AsyncDecapsulate<EK, SS> for T
+where
+    T: Decapsulate<EK, SS>,
+{
+    type Error = T::Error;
+
+    async fn decapsulate_async(&self, encapsulated_key: &EK) -> Result<SS, Self::Error> {
+        self.decapsulate(encapsulated_key)
+    }
+}
+
+/// Error returned by [`TryEncapsulate::try_encapsulate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TryEncapsulateError<E, R> {
+    /// Encapsulation failed.
+    Encapsulate(E),
+    /// The random number generator failed.
+    Rng(R),
+}
+
+impl<E: fmt::Display, R: fmt::Display> fmt::Display for TryEncapsulateError<E, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encapsulate(err) => write!(f, "encapsulation error: {err}"),
+            Self::Rng(err) => write!(f, "RNG error: {err}"),
+        }
+    }
+}
+
+impl<E, R> core::error::Error for TryEncapsulateError<E, R>
+where
+    E: core::error::Error,
+    R: core::error::Error,
+{
+}
+
+/// A key encapsulation mechanism, tying together the types of its keys, encapsulated keys and
+/// shared secrets.
+///
+/// This is usually implemented on a marker type and is used by generic constructions which are
+/// built on top of other KEMs, e.g. hybrid KEM combiners.
+pub trait Kem {
+    /// Encapsulated key (ciphertext) produced by this KEM.
+    type EncappedKey;
+
+    /// Shared secret produced by this KEM.
+    type SharedSecret;
+
+    /// Encapsulation (public) key.
+    type EncapsulationKey: Encapsulate<Self::EncappedKey, Self::SharedSecret>;
+
+    /// Decapsulation (secret) key.
+    type DecapsulationKey: Decapsulator<Self::EncappedKey, Self::SharedSecret, Encapsulator = Self::EncapsulationKey>;
+}
+
+/// A decapsulator which can produce the matching encapsulator, e.g. a secret key which can
+/// compute its public key.
+pub trait Decapsulator<EK, SS>: Decapsulate<EK, SS> {
+    /// Encapsulator paired with this decapsulator.
+    type Encapsulator: Encapsulate<EK, SS>;
+
+    /// Returns the encapsulator for this decapsulator.
+    fn encapsulator(&self) -> Self::Encapsulator;
+}
+
+/// Key pair generation, implemented by decapsulators.
+pub trait KeyPairGen: Sized {
+    /// Encapsulator paired with the generated decapsulator.
+    type Encapsulator;
+
+    /// Size of the seed used for deterministic key generation in bytes.
+    type SeedSize: ArraySize;
+
+    /// Error returned for seeds which don't produce a valid key pair, e.g. an
+    /// out-of-range scalar. KEMs which accept every seed should use
+    /// [`Infallible`][core::convert::Infallible].
+    type Error: Debug;
+
+    /// Deterministically derive a key pair from the given seed.
+    fn from_seed(seed: &Seed<Self>) -> Result<(Self, Self::Encapsulator), Self::Error>;
+
+    /// Generate a random key pair using the provided [`CryptoRng`].
+    ///
+    /// The default implementation draws fresh seeds until [`KeyPairGen::from_seed`]
+    /// accepts one.
+    #[inline]
+    fn generate<R: CryptoRng + ?Sized>(rng: &mut R) -> (Self, Self::Encapsulator) {
+        let mut seed = Seed::<Self>::default();
+
+        loop {
+            rng.fill_bytes(&mut seed);
+            let key_pair = Self::from_seed(&seed);
+            seed.as_mut_slice().zeroize();
+
+            if let Ok(key_pair) = key_pair {
+                return key_pair;
+            }
+        }
+    }
+
+    /// Return seed size in bytes.
+    #[inline(always)]
+    fn seed_size() -> usize {
+        Self::SeedSize::USIZE
+    }
+}
+
+/// Types which produce encapsulated keys of a fixed size.
+pub trait EncappedKeySizeUser {
+    /// Encapsulated key size in bytes.
+    type EncappedKeySize: ArraySize;
+
+    /// Return encapsulated key size in bytes.
+    #[inline(always)]
+    fn encapped_key_size() -> usize {
+        Self::EncappedKeySize::USIZE
+    }
+}
+
+/// Types which produce shared secrets of a fixed size.
+pub trait SharedSecretSizeUser {
+    /// Shared secret size in bytes.
+    type SharedSecretSize: ArraySize;
+
+    /// Return shared secret size in bytes.
+    #[inline(always)]
+    fn shared_secret_size() -> usize {
+        Self::SharedSecretSize::USIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{CounterRng, FailingRng, RngError, ToyKey};
+    use core::{
+        convert::Infallible,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    /// Toy KEM which samples its shared secret by rejection, like most DH-based KEMs do for
+    /// their ephemeral secret.
+    struct RejectionSamplingKey(u64);
+
+    impl Encapsulate<u64, u64> for RejectionSamplingKey {
+        type Error = Infallible;
+
+        fn encapsulate<R: CryptoRng + ?Sized>(
+            &self,
+            rng: &mut R,
+        ) -> Result<(u64, u64), Infallible> {
+            loop {
+                let ss = rng.next_u64();
+                if ss != 0 {
+                    return Ok((ss ^ self.0, ss));
+                }
+            }
+        }
+    }
+
+    impl TryEncapsulate<u64, u64> for RejectionSamplingKey {
+        fn try_encapsulate<R: TryCryptoRng + ?Sized>(
+            &self,
+            rng: &mut R,
+        ) -> Result<(u64, u64), TryEncapsulateError<Infallible, R::Error>> {
+            loop {
+                let ss = rng.try_next_u64().map_err(TryEncapsulateError::Rng)?;
+                if ss != 0 {
+                    return Ok((ss ^ self.0, ss));
+                }
+            }
+        }
+    }
+
+    /// Mock HSM which only supports asynchronous decapsulation.
+    struct MockHsm(ToyKey);
+
+    impl AsyncDecapsulate<[u8; 16], [u8; 16]> for MockHsm {
+        type Error = &'static str;
+
+        async fn decapsulate_async(
+            &self,
+            encapsulated_key: &[u8; 16],
+        ) -> Result<[u8; 16], &'static str> {
+            self.0
+                .decapsulate(encapsulated_key)
+                .map_err(|_| "HSM error")
+        }
+    }
+
+    /// Poll a future which is expected to complete immediately.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future did not complete"),
+        }
+    }
+
+    #[test]
+    fn async_round_trip() {
+        let key = ToyKey([42; 16]);
+        let hsm = MockHsm(key.clone());
+        let (ek, ss) = block_on(key.encapsulate_async(&mut CounterRng(0))).unwrap();
+        assert_eq!(block_on(key.decapsulate_async(&ek)), Ok(ss));
+        assert_eq!(block_on(hsm.decapsulate_async(&ek)), Ok(ss));
+    }
+
+    #[test]
+    fn try_encapsulate() {
+        let key = ToyKey([42; 16]);
+        let (ek, ss) = key.try_encapsulate(&mut CounterRng(0)).unwrap();
+        assert_eq!(key.decapsulate(&ek), Ok(ss));
+        assert_eq!(
+            key.try_encapsulate(&mut FailingRng),
+            Err(TryEncapsulateError::Rng(RngError))
+        );
+    }
+
+    #[test]
+    fn try_encapsulate_rejection_sampling() {
+        let key = RejectionSamplingKey(42);
+        let (ek, ss) = key.encapsulate(&mut CounterRng(0)).unwrap();
+        assert_eq!(ek ^ 42, ss);
+        assert_eq!(
+            key.try_encapsulate(&mut FailingRng),
+            Err(TryEncapsulateError::Rng(RngError))
+        );
+    }
+}
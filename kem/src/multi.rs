@@ -0,0 +1,397 @@
+//! Multi-recipient encapsulation.
+//!
+//! [`MultiEncapsulate`] encapsulates a fresh shared secret to each of many
+//! recipients.
+//!
+//! [`encapsulate_framed`] builds on it to distribute a single random payload
+//! key to all recipients: the payload key is wrapped under a key-encryption key
+//! (KEK) derived from each recipient's shared secret, and the results are
+//! framed together with the recipients' key identifiers, so that each recipient
+//! can find its own entry and recover the payload key with
+//! [`decapsulate_framed`].
+//!
+//! # Key wrapping
+//!
+//! The KEK of a recipient is derived with HKDF using the hash function `D`:
+//!
+//! ```text
+//! KEK = HKDF-Expand(HKDF-Extract("", shared_secret), info, Nk)
+//! info = "KEM multi-recipient KEK" || len(key_id) || key_id
+//!     || len(encapped_key) || encapped_key
+//! ```
+//!
+//! where the lengths are encoded as in the frame format below and `Nk` is the
+//! key size of the AEAD `A`. The payload key, which has the same size, is
+//! encrypted under the KEK with `A`, using the all-zero nonce and no associated
+//! data since every KEK is used only once. A wrapped key which fails to decrypt
+//! is rejected with [`Error::Decap`].
+//!
+//! # Frame format
+//!
+//! | Field                  | Size                          |
+//! |------------------------|-------------------------------|
+//! | number of entries      | 2 bytes (big endian `u16`)    |
+//! | entries                | variable                      |
+//!
+//! Each entry is encoded as:
+//!
+//! | Field                  | Size                          |
+//! |------------------------|-------------------------------|
+//! | key identifier size    | 1 byte                        |
+//! | key identifier         | key identifier size           |
+//! | encapped key size      | 2 bytes (big endian `u16`)    |
+//! | encapped key           | encapped key size             |
+//! | wrapped key size       | 2 bytes (big endian `u16`)    |
+//! | wrapped key            | wrapped key size              |
+//!
+//! The wrapped key is the AEAD ciphertext of the payload key, including the
+//! tag.
+
+use crate::{Decapsulate, Encapsulate};
+use aead::{AeadInOut, Key, KeyInit, Nonce, array::typenum::Unsigned};
+use alloc::vec::Vec;
+use core::{fmt, iter};
+use digest::{Digest, crypto_common::BlockSizeUser};
+use hkdf::{Hkdf, hmac::SimpleHmac};
+use rand_core::CryptoRng;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Info string prefix used to derive KEKs.
+const KEK_INFO: &[u8] = b"KEM multi-recipient KEK";
+
+/// Multi-recipient encapsulation errors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Encapsulation failed.
+    Encap,
+    /// Decapsulation failed, or the wrapped key could not be decrypted.
+    Decap,
+    /// Key derivation failed.
+    Kdf,
+    /// Malformed frame, or a value which is too large to be framed.
+    Encoding,
+    /// No recipients were given.
+    NoRecipients,
+    /// The frame contains no entry for the recipient's key identifier.
+    NotFound,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::Encap => "multi-recipient encapsulation error",
+            Error::Decap => "multi-recipient decapsulation error",
+            Error::Kdf => "multi-recipient key derivation error",
+            Error::Encoding => "malformed multi-recipient frame",
+            Error::NoRecipients => "no recipients",
+            Error::NotFound => "no entry for the recipient key identifier",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Result type alias with [`Error`].
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Encapsulation to multiple recipients at once.
+///
+/// The default implementation calls [`Encapsulate::encapsulate`] for each
+/// recipient.
+pub trait MultiEncapsulate<EK, SS>: Encapsulate<EK, SS> + Sized {
+    /// Encapsulates a fresh shared secret to each of `recipients`, returning
+    /// the encapsulated keys and shared secrets in the same order.
+    fn encapsulate_multi<R: CryptoRng + ?Sized>(
+        recipients: &[Self],
+        rng: &mut R,
+    ) -> core::result::Result<Vec<(EK, SS)>, Self::Error> {
+        recipients
+            .iter()
+            .map(|recipient| recipient.encapsulate(rng))
+            .collect()
+    }
+}
+
+/// Key identifier, used to find a recipient's entry in a frame.
+///
+/// Must be implemented consistently by the encapsulation and decapsulation keys
+/// of a recipient. Identifiers are limited to 255 bytes.
+pub trait KeyIdentifier {
+    /// Returns the key identifier.
+    fn key_id(&self) -> &[u8];
+}
+
+/// Encapsulate a fresh random payload key to all `recipients`, wrapping it
+/// with the AEAD `A` under KEKs derived using the hash function `D`, and return
+/// the frame containing each recipient's entry and the payload key.
+///
+/// The payload key has the key size of `A`.
+pub fn encapsulate_framed<A, D, K, EK, SS, R>(
+    recipients: &[K],
+    rng: &mut R,
+) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>)>
+where
+    A: AeadInOut + KeyInit,
+    D: BlockSizeUser + Clone + Digest,
+    K: MultiEncapsulate<EK, SS> + KeyIdentifier,
+    EK: AsRef<[u8]>,
+    SS: AsRef<[u8]>,
+    R: CryptoRng + ?Sized,
+{
+    let count = u16::try_from(recipients.len()).map_err(|_| Error::Encoding)?;
+    if recipients.is_empty() {
+        return Err(Error::NoRecipients);
+    }
+    let encapsulated = K::encapsulate_multi(recipients, rng).map_err(|_| Error::Encap)?;
+
+    let mut payload_key = Zeroizing::new(alloc::vec![0u8; A::KeySize::USIZE]);
+    rng.fill_bytes(&mut payload_key);
+
+    let mut frame = Vec::from(count.to_be_bytes());
+    let mut wrapped = Zeroizing::new(Vec::new());
+    for (recipient, (encapped_key, shared_secret)) in recipients.iter().zip(&encapsulated) {
+        let key_id = recipient.key_id();
+        let encapped_key = encapped_key.as_ref();
+        let kek = kek::<A, D>(shared_secret.as_ref(), key_id, encapped_key)?;
+
+        wrapped.clear();
+        wrapped.extend_from_slice(&payload_key);
+        kek.encrypt_in_place(&Nonce::<A>::default(), &[], &mut *wrapped)
+            .map_err(|_| Error::Encap)?;
+        write_entry(
+            &mut frame,
+            &Entry {
+                key_id,
+                encapped_key,
+                wrapped_key: &wrapped,
+            },
+        )?;
+    }
+
+    Ok((frame, payload_key))
+}
+
+/// Find the entry of the recipient `dk` in `frame`, decapsulate it and return
+/// the payload key, unwrapping it with the AEAD `A` under the KEK derived using
+/// the hash function `D`.
+pub fn decapsulate_framed<A, D, DK, EK, SS>(dk: &DK, frame: &[u8]) -> Result<Zeroizing<Vec<u8>>>
+where
+    A: AeadInOut + KeyInit,
+    D: BlockSizeUser + Clone + Digest,
+    DK: Decapsulate<EK, SS> + KeyIdentifier,
+    EK: for<'a> TryFrom<&'a [u8]>,
+    SS: AsRef<[u8]>,
+{
+    let entry = Frame::parse(frame)?
+        .find(dk.key_id())
+        .ok_or(Error::NotFound)?;
+    let encapped_key = EK::try_from(entry.encapped_key).map_err(|_| Error::Encoding)?;
+    let shared_secret = dk.decapsulate(&encapped_key).map_err(|_| Error::Decap)?;
+    let kek = kek::<A, D>(shared_secret.as_ref(), entry.key_id, entry.encapped_key)?;
+
+    let mut payload_key = Zeroizing::new(Vec::from(entry.wrapped_key));
+    kek.decrypt_in_place(&Nonce::<A>::default(), &[], &mut *payload_key)
+        .map_err(|_| Error::Decap)?;
+    Ok(payload_key)
+}
+
+/// Parsed multi-recipient frame.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame<'a> {
+    count: u16,
+    entries: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Parse and validate a frame.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let (count, entries) = read_u16(bytes)?;
+        let mut rest = entries;
+        for _ in 0..count {
+            read_entry(&mut rest)?;
+        }
+        if !rest.is_empty() {
+            return Err(Error::Encoding);
+        }
+
+        Ok(Self { count, entries })
+    }
+
+    /// Number of entries in the frame.
+    pub fn len(&self) -> usize {
+        usize::from(self.count)
+    }
+
+    /// Whether the frame has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Iterate over the entries of the frame.
+    pub fn entries(&self) -> impl Iterator<Item = Entry<'a>> + use<'a> {
+        let mut rest = self.entries;
+        iter::from_fn(move || read_entry(&mut rest).ok()).take(self.len())
+    }
+
+    /// Find the entry for the given key identifier.
+    pub fn find(&self, key_id: &[u8]) -> Option<Entry<'a>> {
+        self.entries().find(|entry| entry.key_id == key_id)
+    }
+}
+
+/// Entry of a multi-recipient [`Frame`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Entry<'a> {
+    /// Key identifier of the recipient.
+    pub key_id: &'a [u8],
+    /// Encapsulated key for the recipient.
+    pub encapped_key: &'a [u8],
+    /// Payload key wrapped under the recipient's KEK.
+    pub wrapped_key: &'a [u8],
+}
+
+/// Derive the KEK of a recipient and initialize the AEAD `A` with it.
+fn kek<A, D>(shared_secret: &[u8], key_id: &[u8], encapped_key: &[u8]) -> Result<A>
+where
+    A: KeyInit,
+    D: BlockSizeUser + Clone + Digest,
+{
+    let key_id_len = u8::try_from(key_id.len()).map_err(|_| Error::Encoding)?;
+    let encapped_key_len = u16::try_from(encapped_key.len()).map_err(|_| Error::Encoding)?;
+
+    let mut key = Key::<A>::default();
+    Hkdf::<D, SimpleHmac<D>>::new(None, shared_secret)
+        .expand_multi_info(
+            &[
+                KEK_INFO,
+                &[key_id_len],
+                key_id,
+                &encapped_key_len.to_be_bytes(),
+                encapped_key,
+            ],
+            &mut key,
+        )
+        .map_err(|_| Error::Kdf)?;
+    let kek = A::new(&key);
+    key.as_mut_slice().zeroize();
+    Ok(kek)
+}
+
+fn write_entry(frame: &mut Vec<u8>, entry: &Entry<'_>) -> Result<()> {
+    let key_id_len = u8::try_from(entry.key_id.len()).map_err(|_| Error::Encoding)?;
+    frame.push(key_id_len);
+    frame.extend_from_slice(entry.key_id);
+    for value in [entry.encapped_key, entry.wrapped_key] {
+        let len = u16::try_from(value.len()).map_err(|_| Error::Encoding)?;
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(value);
+    }
+    Ok(())
+}
+
+fn read_entry<'a>(bytes: &mut &'a [u8]) -> Result<Entry<'a>> {
+    let (&key_id_len, rest) = bytes.split_first().ok_or(Error::Encoding)?;
+    let (key_id, rest) = split(rest, usize::from(key_id_len))?;
+    let (len, rest) = read_u16(rest)?;
+    let (encapped_key, rest) = split(rest, usize::from(len))?;
+    let (len, rest) = read_u16(rest)?;
+    let (wrapped_key, rest) = split(rest, usize::from(len))?;
+
+    *bytes = rest;
+    Ok(Entry {
+        key_id,
+        encapped_key,
+        wrapped_key,
+    })
+}
+
+fn read_u16(bytes: &[u8]) -> Result<(u16, &[u8])> {
+    let (value, rest) = split(bytes, 2)?;
+    Ok((u16::from_be_bytes([value[0], value[1]]), rest))
+}
+
+fn split(bytes: &[u8], len: usize) -> Result<(&[u8], &[u8])> {
+    if bytes.len() < len {
+        return Err(Error::Encoding);
+    }
+    Ok(bytes.split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{CounterRng, DummyAead, ToyKey};
+    use sha2::Sha256;
+
+    impl MultiEncapsulate<[u8; 16], [u8; 16]> for ToyKey {}
+
+    /// The first 4 bytes of the key are used as its identifier.
+    impl KeyIdentifier for ToyKey {
+        fn key_id(&self) -> &[u8] {
+            &self.0[..4]
+        }
+    }
+
+    fn recipients() -> [ToyKey; 3] {
+        core::array::from_fn(|i| ToyKey([0x10 * i as u8 + 1; 16]))
+    }
+
+    fn encapsulate(recipients: &[ToyKey]) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>)> {
+        encapsulate_framed::<DummyAead, Sha256, _, _, _, _>(recipients, &mut CounterRng(0))
+    }
+
+    fn decapsulate(dk: &ToyKey, frame: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        decapsulate_framed::<DummyAead, Sha256, _, _, _>(dk, frame)
+    }
+
+    #[test]
+    fn round_trip() {
+        let recipients = recipients();
+        let (frame, payload_key) = encapsulate(&recipients).unwrap();
+        assert_eq!(payload_key.len(), 16);
+
+        let parsed = Frame::parse(&frame).unwrap();
+        assert_eq!(parsed.len(), recipients.len());
+        for (entry, recipient) in parsed.entries().zip(&recipients) {
+            assert_eq!(entry.key_id, recipient.key_id());
+            assert_eq!(entry.wrapped_key.len(), 32);
+        }
+
+        for recipient in &recipients {
+            assert_eq!(decapsulate(recipient, &frame).unwrap(), payload_key);
+        }
+    }
+
+    #[test]
+    fn unknown_recipient() {
+        let (frame, _) = encapsulate(&recipients()).unwrap();
+        let stranger = ToyKey([0xff; 16]);
+        assert_eq!(decapsulate(&stranger, &frame), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn unwrap_failure() {
+        let recipients = recipients();
+        let (frame, _) = encapsulate(&recipients).unwrap();
+
+        // Same key identifier, but a different shared secret and thus KEK
+        let mut impostor = recipients[1].clone();
+        impostor.0[15] ^= 1;
+        assert_eq!(decapsulate(&impostor, &frame), Err(Error::Decap));
+
+        // The last byte of the frame is part of the tag of the last entry
+        let mut tampered = frame.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(decapsulate(&recipients[2], &tampered), Err(Error::Decap));
+        assert!(decapsulate(&recipients[0], &tampered).is_ok());
+    }
+
+    #[test]
+    fn malformed_frame() {
+        let (frame, _) = encapsulate(&recipients()).unwrap();
+        assert!(Frame::parse(&frame[..frame.len() - 1]).is_err());
+        assert!(Frame::parse(&[frame.as_slice(), &[0]].concat()).is_err());
+        assert_eq!(encapsulate(&[]), Err(Error::NoRecipients));
+    }
+}
@@ -0,0 +1,241 @@
+//! Insecure fixtures shared by the unit tests.
+#![allow(dead_code)]
+
+use crate::{
+    Decapsulate, Decapsulator, EncappedKeySizeUser, Encapsulate, Kem, KeyPairGen, Seed,
+    SharedSecretSizeUser, TryEncapsulate, TryEncapsulateError,
+    typenum::{U12, U16},
+};
+use aead::{
+    AeadCore, AeadInOut, Key, KeyInit, KeySizeUser, Nonce, Tag, TagPosition, inout::InOutBuf,
+};
+use core::{convert::Infallible, fmt};
+use rand_core::{CryptoRng, RngCore, TryCryptoRng, TryRngCore};
+
+/// RNG which outputs consecutive byte values starting from the given one.
+///
+/// It is NOT cryptographically secure and implements [`CryptoRng`] only to be
+/// usable with the KEM traits in tests.
+#[derive(Debug)]
+pub struct CounterRng(pub u8);
+
+impl RngCore for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for byte in dst {
+            *byte = self.0;
+            self.0 = self.0.wrapping_add(1);
+        }
+    }
+}
+
+impl CryptoRng for CounterRng {}
+
+/// Error returned by [`FailingRng`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RngError;
+
+impl fmt::Display for RngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RNG failure")
+    }
+}
+
+impl core::error::Error for RngError {}
+
+/// Fallible RNG which always fails, used to test error handling of
+/// [`TryEncapsulate::try_encapsulate`].
+#[derive(Debug)]
+pub struct FailingRng;
+
+impl TryRngCore for FailingRng {
+    type Error = RngError;
+
+    fn try_next_u32(&mut self) -> Result<u32, RngError> {
+        Err(RngError)
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, RngError> {
+        Err(RngError)
+    }
+
+    fn try_fill_bytes(&mut self, _dst: &mut [u8]) -> Result<(), RngError> {
+        Err(RngError)
+    }
+}
+
+impl TryCryptoRng for FailingRng {}
+
+/// Toy (horribly insecure!) KEM key used for both encapsulation and
+/// decapsulation: the encapsulated key is the shared secret XORed with the key.
+///
+/// Used to test constructions which are generic over a [`Kem`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ToyKey(pub [u8; 16]);
+
+impl AsRef<[u8]> for ToyKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8]> for ToyKey {
+    type Error = core::array::TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        bytes.try_into().map(Self)
+    }
+}
+
+impl Encapsulate<[u8; 16], [u8; 16]> for ToyKey {
+    type Error = Infallible;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<([u8; 16], [u8; 16]), Infallible> {
+        let mut ss = [0u8; 16];
+        rng.fill_bytes(&mut ss);
+        Ok((xor(&ss, &self.0), ss))
+    }
+}
+
+impl TryEncapsulate<[u8; 16], [u8; 16]> for ToyKey {
+    fn try_encapsulate<R: TryCryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<([u8; 16], [u8; 16]), TryEncapsulateError<Infallible, R::Error>> {
+        let mut ss = [0u8; 16];
+        rng.try_fill_bytes(&mut ss)
+            .map_err(TryEncapsulateError::Rng)?;
+        Ok((xor(&ss, &self.0), ss))
+    }
+}
+
+impl Decapsulate<[u8; 16], [u8; 16]> for ToyKey {
+    type Error = Infallible;
+
+    fn decapsulate(&self, encapsulated_key: &[u8; 16]) -> Result<[u8; 16], Infallible> {
+        Ok(xor(encapsulated_key, &self.0))
+    }
+}
+
+impl Decapsulator<[u8; 16], [u8; 16]> for ToyKey {
+    type Encapsulator = ToyKey;
+
+    fn encapsulator(&self) -> ToyKey {
+        self.clone()
+    }
+}
+
+impl KeyPairGen for ToyKey {
+    type Encapsulator = ToyKey;
+    type SeedSize = U16;
+    type Error = Infallible;
+
+    fn from_seed(seed: &Seed<Self>) -> Result<(Self, ToyKey), Infallible> {
+        Ok((ToyKey(seed.0), ToyKey(seed.0)))
+    }
+}
+
+/// [`Kem`] using [`ToyKey`]s.
+#[derive(Debug)]
+pub struct ToyKem;
+
+impl Kem for ToyKem {
+    type EncappedKey = [u8; 16];
+    type SharedSecret = [u8; 16];
+    type EncapsulationKey = ToyKey;
+    type DecapsulationKey = ToyKey;
+}
+
+impl EncappedKeySizeUser for ToyKem {
+    type EncappedKeySize = U16;
+}
+
+impl SharedSecretSizeUser for ToyKem {
+    type SharedSecretSize = U16;
+}
+
+/// Dummy (horribly insecure!) AEAD with the sizes of AES-128-GCM.
+///
+/// Used to test constructions which are generic over an AEAD.
+#[derive(Clone, Debug)]
+pub struct DummyAead {
+    key: [u8; 16],
+}
+
+impl DummyAead {
+    /// Key this AEAD was initialized with.
+    pub fn key(&self) -> &[u8; 16] {
+        &self.key
+    }
+
+    fn apply_keystream(&self, mut buffer: InOutBuf<'_, '_, u8>) {
+        for i in 0..buffer.len() {
+            let mut byte = buffer.get(i);
+            *byte.get_out() = *byte.get_in() ^ self.key[i % 16];
+        }
+    }
+
+    fn tag(&self, nonce: &Nonce<Self>, aad: &[u8], ciphertext: &[u8]) -> Tag<Self> {
+        let mut tag = Tag::<Self>::from(self.key);
+        for (i, byte) in nonce.iter().chain(aad).chain(ciphertext).enumerate() {
+            tag[i % 16] = tag[i % 16].rotate_left(1) ^ byte;
+        }
+        tag
+    }
+}
+
+impl KeySizeUser for DummyAead {
+    type KeySize = U16;
+}
+
+impl KeyInit for DummyAead {
+    fn new(key: &Key<Self>) -> Self {
+        Self { key: key.0 }
+    }
+}
+
+impl AeadCore for DummyAead {
+    type NonceSize = U12;
+    type TagSize = U16;
+    const TAG_POSITION: TagPosition = TagPosition::Postfix;
+}
+
+impl AeadInOut for DummyAead {
+    fn encrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        mut buffer: InOutBuf<'_, '_, u8>,
+    ) -> aead::Result<Tag<Self>> {
+        self.apply_keystream(buffer.reborrow());
+        Ok(self.tag(nonce, aad, buffer.get_out()))
+    }
+
+    fn decrypt_inout_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: InOutBuf<'_, '_, u8>,
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        if &self.tag(nonce, aad, buffer.get_in()) != tag {
+            return Err(aead::Error);
+        }
+        self.apply_keystream(buffer);
+        Ok(())
+    }
+}
+
+fn xor(a: &[u8; 16], b: &[u8; 16]) -> [u8; 16] {
+    core::array::from_fn(|i| a[i] ^ b[i])
+}
@@ -0,0 +1,83 @@
+//! This module defines a dummy (horribly insecure!) KEM to test the
+//! implementation of the helper functions and macros in the `dev` module.
+#![cfg(feature = "dev")]
+use core::convert::Infallible;
+use kem::{
+    Decapsulate, Decapsulator, EncappedKeySizeUser, Encapsulate, Kem, KeyPairGen, Seed,
+    SharedSecretSizeUser, typenum::U16,
+};
+use rand_core::CryptoRng;
+
+/// Key used for both encapsulation and decapsulation.
+#[derive(Clone, Debug)]
+struct DummyKey([u8; 16]);
+
+impl AsRef<[u8]> for DummyKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Encapsulate<[u8; 16], [u8; 16]> for DummyKey {
+    type Error = Infallible;
+
+    fn encapsulate<R: CryptoRng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<([u8; 16], [u8; 16]), Infallible> {
+        let mut ss = [0u8; 16];
+        rng.fill_bytes(&mut ss);
+        Ok((xor(&ss, &self.0), ss))
+    }
+}
+
+impl Decapsulate<[u8; 16], [u8; 16]> for DummyKey {
+    type Error = Infallible;
+
+    fn decapsulate(&self, encapsulated_key: &[u8; 16]) -> Result<[u8; 16], Infallible> {
+        Ok(xor(encapsulated_key, &self.0))
+    }
+}
+
+impl Decapsulator<[u8; 16], [u8; 16]> for DummyKey {
+    type Encapsulator = DummyKey;
+
+    fn encapsulator(&self) -> DummyKey {
+        self.clone()
+    }
+}
+
+impl KeyPairGen for DummyKey {
+    type Encapsulator = DummyKey;
+    type SeedSize = U16;
+    type Error = Infallible;
+
+    fn from_seed(seed: &Seed<Self>) -> Result<(Self, DummyKey), Infallible> {
+        Ok((DummyKey(seed.0), DummyKey(seed.0)))
+    }
+}
+
+struct DummyKem;
+
+impl Kem for DummyKem {
+    type EncappedKey = [u8; 16];
+    type SharedSecret = [u8; 16];
+    type EncapsulationKey = DummyKey;
+    type DecapsulationKey = DummyKey;
+}
+
+impl EncappedKeySizeUser for DummyKem {
+    type EncappedKeySize = U16;
+}
+
+impl SharedSecretSizeUser for DummyKem {
+    type SharedSecretSize = U16;
+}
+
+fn xor(a: &[u8; 16], b: &[u8; 16]) -> [u8; 16] {
+    core::array::from_fn(|i| a[i] ^ b[i])
+}
+
+kem::new_test!(dummy_kat, "dummy", DummyKem);
+kem::new_round_trip_test!(dummy_round_trip, DummyKem);
+kem::new_implicit_rejection_test!(dummy_implicit_rejection, DummyKem);